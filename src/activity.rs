@@ -0,0 +1,149 @@
+/// src/activity.rs - Last-activity tracking used for idle shutdown and shutdown draining
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::constants::{DRAIN_CHECK_INTERVAL_MS, IDLE_CHECK_INTERVAL_SECONDS};
+use crate::utils::{log_info, log_warning};
+
+/// Timestamp of the last request plus the number of requests still in flight
+#[derive(Debug)]
+pub struct ActivityTracker {
+    last_activity_secs: AtomicU64,
+    in_flight: AtomicUsize,
+    shutting_down: AtomicBool,
+}
+
+impl ActivityTracker {
+    /// Create a tracker that counts startup as the last activity
+    pub fn new() -> Self {
+        Self {
+            last_activity_secs: AtomicU64::new(epoch_secs()),
+            in_flight: AtomicUsize::new(0),
+            shutting_down: AtomicBool::new(false),
+        }
+    }
+
+    /// Stop taking new requests (they get 503) while the ones in flight finish
+    pub fn begin_shutdown(&self) {
+        self.shutting_down.store(true, Ordering::Release);
+    }
+
+    pub fn is_shutting_down(&self) -> bool {
+        self.shutting_down.load(Ordering::Acquire)
+    }
+
+    /// Record activity now
+    pub fn touch(&self) {
+        self.last_activity_secs.fetch_max(epoch_secs(), Ordering::Relaxed);
+    }
+
+    /// Mark a request as started; activity is recorded again when the guard drops
+    pub fn begin(self: &Arc<Self>) -> ActivityGuard {
+        self.in_flight.fetch_add(1, Ordering::AcqRel);
+        self.touch();
+        ActivityGuard(self.clone())
+    }
+
+    /// Number of requests (including open response streams) still running
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.load(Ordering::Acquire)
+    }
+
+    /// Time since the last activity, or `None` while requests are in flight
+    pub fn idle_for(&self) -> Option<Duration> {
+        if self.in_flight() > 0 {
+            return None;
+        }
+        let last = self.last_activity_secs.load(Ordering::Relaxed);
+        Some(Duration::from_secs(epoch_secs().saturating_sub(last)))
+    }
+}
+
+impl Default for ActivityTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Keeps a request counted as in flight until dropped
+pub struct ActivityGuard(Arc<ActivityTracker>);
+
+impl Drop for ActivityGuard {
+    fn drop(&mut self) {
+        self.0.touch();
+        self.0.in_flight.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+/// Resolve once no request has been seen for `idle_timeout`
+pub async fn wait_for_idle(tracker: Arc<ActivityTracker>, idle_timeout: Duration) {
+    let check_interval = Duration::from_secs(IDLE_CHECK_INTERVAL_SECONDS).min(idle_timeout);
+    let mut interval = tokio::time::interval(check_interval);
+    loop {
+        interval.tick().await;
+        if tracker.idle_for().is_some_and(|idle| idle >= idle_timeout) {
+            log_info(&format!(
+                "No requests for {}s, shutting down",
+                idle_timeout.as_secs()
+            ));
+            return;
+        }
+    }
+}
+
+/// Resolve on Ctrl-C or, on Unix, SIGTERM
+pub async fn termination_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => {}
+                    _ = terminate.recv() => {}
+                }
+            }
+            Err(_) => {
+                let _ = tokio::signal::ctrl_c().await;
+            }
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = tokio::signal::ctrl_c().await;
+    }
+}
+
+/// Reject new requests, then wait up to `drain_timeout` for the ones in flight.
+/// Returns false if requests were still running when the time ran out.
+pub async fn drain_in_flight(tracker: &ActivityTracker, drain_timeout: Duration) -> bool {
+    tracker.begin_shutdown();
+    log_info(&format!(
+        "Shutting down: rejecting new requests, waiting up to {}s for {} in flight",
+        drain_timeout.as_secs(),
+        tracker.in_flight()
+    ));
+    let deadline = tokio::time::Instant::now() + drain_timeout;
+    let mut interval = tokio::time::interval(Duration::from_millis(DRAIN_CHECK_INTERVAL_MS));
+    loop {
+        interval.tick().await;
+        if tracker.in_flight() == 0 {
+            return true;
+        }
+        if tokio::time::Instant::now() >= deadline {
+            log_warning(
+                "Shutdown",
+                &format!("{} requests still running after {}s, stopping", tracker.in_flight(), drain_timeout.as_secs()),
+            );
+            return false;
+        }
+    }
+}
+
+fn epoch_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
@@ -2,29 +2,71 @@
 use serde::Serialize;
 // Added
 use serde_json::Value;
+use std::borrow::Cow;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
 use tokio_util::sync::CancellationToken;
 
 use crate::check_cancelled;
 use crate::constants::*;
-use crate::utils::{log_error, ProxyError};
+use crate::failover::Failover;
+use crate::handlers::retry::LoadTriggers;
+use crate::health::BackendRuntime;
+use crate::metrics::{begin_upstream_request, record_upstream_time};
+use crate::shadow::Shadow;
+use crate::utils::{log_error, log_info, log_warning, ProxyError};
 
 /// Lightweight request context for concurrent request handling
 #[derive(Clone)]
 pub struct RequestContext<'a> {
     pub client: &'a reqwest::Client,
     pub lmstudio_url: &'a str,
+    /// Client-requested timeout (`X-Request-Timeout-Seconds`), already validated against the configured max
+    pub timeout_seconds: Option<u64>,
+    /// Inbound headers listed in --forward-header, sent on to LM Studio
+    pub forwarded_headers: reqwest::header::HeaderMap,
+    /// Primary/fallback backend state (--lmstudio-url-fallback)
+    pub failover: Option<&'a Failover>,
+    /// Where native inference responses record their `runtime` for /health
+    pub backend_runtime: Option<Arc<BackendRuntime>>,
+    /// Backend that chat requests are mirrored to (--shadow-url)
+    pub shadow: Option<&'a Shadow>,
+    /// Single-flight model load triggers shared by concurrent requests
+    pub load_triggers: Option<&'a LoadTriggers>,
+}
+
+impl RequestContext<'_> {
+    /// Remember the `runtime` of a complete native inference response
+    pub fn record_backend_runtime(&self, response: &Value) {
+        if let Some(backend_runtime) = &self.backend_runtime {
+            backend_runtime.record(response);
+        }
+    }
+
+    /// Maximum wait between streamed chunks
+    pub fn stream_timeout_seconds(&self) -> u64 {
+        self.timeout_seconds.unwrap_or(DEFAULT_STREAM_TIMEOUT_SECONDS)
+    }
 }
 
 /// Optimized cancellable request handler
 pub struct CancellableRequest<'a> {
     context: RequestContext<'a>,
     token: CancellationToken,
+    failover: bool,
 }
 
 impl<'a> CancellableRequest<'a> {
     /// Create new cancellable request handler
     pub fn new(context: RequestContext<'a>, token: CancellationToken) -> Self {
-        Self { context, token }
+        Self { context, token, failover: true }
+    }
+
+    /// Always contact the given URL, ignoring `--lmstudio-url-fallback` (used to probe the primary)
+    pub fn without_failover(mut self) -> Self {
+        self.failover = false;
+        self
     }
 
     /// Make a cancellable HTTP request with proper error handling
@@ -36,31 +78,72 @@ impl<'a> CancellableRequest<'a> {
     ) -> Result<reqwest::Response, ProxyError> {
         check_cancelled!(self.token);
 
-        let mut request_builder = self.context.client.request(method, url);
+        let body_value = body
+            .map(|body_content| serde_json::to_value(&body_content))
+            .transpose()
+            .map_err(|e| ProxyError::bad_request(&format!("Invalid request body: {}", e)))?;
+        let failover = self.context.failover.filter(|_| self.failover);
+        let url = failover.map_or(Cow::Borrowed(url), |failover| failover.route_url(url));
 
-        if let Some(body_content) = body {
+        let mut result = self.send_once(method.clone(), &url, body_value.as_ref()).await?;
+        // Only connection failures fail over; LM Studio's own error responses are passed through
+        if let Some(failover) = failover.filter(|_| result.as_ref().is_err_and(|err| err.is_connect())) {
+            if let Some(alternate_url) = failover.fail_over_from(&url) {
+                result = self.send_once(method, &alternate_url, body_value.as_ref()).await?;
+            }
+        }
+
+        result.map_err(|err| {
+            if err.is_timeout() {
+                return ProxyError::gateway_timeout(ERROR_REQUEST_TIMEOUT);
+            }
+            let error_msg = if err.is_connect() {
+                ERROR_LM_STUDIO_UNAVAILABLE
+            } else if err.is_request() {
+                "Invalid request"
+            } else {
+                "Request failed"
+            };
+            log_error("CancellableRequest send", &format!("{}: {:?}", error_msg, err));
+            ProxyError::internal_server_error(error_msg)
+        })
+    }
+
+    /// Send one request, racing it against cancellation
+    async fn send_once(
+        &self,
+        method: reqwest::Method,
+        url: &str,
+        body_value: Option<&Value>,
+    ) -> Result<Result<reqwest::Response, reqwest::Error>, ProxyError> {
+        let mut request_builder = self
+            .context
+            .client
+            .request(method, url)
+            .headers(self.context.forwarded_headers.clone());
+
+        // Ask for uncompressed SSE so streamed chunks are forwarded as they arrive;
+        // streams use a per-chunk timeout instead of a whole-request one
+        let streaming = body_value.and_then(|body| body.get("stream")).and_then(|s| s.as_bool()) == Some(true);
+        if streaming {
+            request_builder = request_builder.header(reqwest::header::ACCEPT_ENCODING, "identity");
+        } else if let Some(timeout_seconds) = self.context.timeout_seconds {
+            request_builder = request_builder.timeout(std::time::Duration::from_secs(timeout_seconds));
+        }
+
+        if let Some(body_value) = body_value {
             request_builder = request_builder
                 .header("Content-Type", CONTENT_TYPE_JSON)
-                .json(&body_content); // reqwest::RequestBuilder::json takes &T where T: Serialize
+                .json(body_value);
         }
 
         // Race request against cancellation
+        let _upstream = begin_upstream_request();
+        let upstream_start = Instant::now();
         tokio::select! {
             result = request_builder.send() => {
-                match result {
-                    Ok(response) => Ok(response),
-                    Err(err) => {
-                        let error_msg = if err.is_connect() {
-                            ERROR_LM_STUDIO_UNAVAILABLE
-                        } else if err.is_request() {
-                            "Invalid request"
-                        } else {
-                            "Request failed"
-                        };
-                        log_error("CancellableRequest send", &format!("{}: {:?}", error_msg, err));
-                        Err(ProxyError::internal_server_error(error_msg))
-                    }
-                }
+                record_upstream_time(upstream_start.elapsed());
+                Ok(result)
             }
             _ = self.token.cancelled() => {
                 Err(ProxyError::request_cancelled())
@@ -69,6 +152,30 @@ impl<'a> CancellableRequest<'a> {
     }
 }
 
+/// Read a response body, counting the read as upstream time since LM Studio may still be generating it
+pub async fn read_upstream_body(response: reqwest::Response) -> reqwest::Result<bytes::Bytes> {
+    let read_start = Instant::now();
+    let result = response.bytes().await;
+    record_upstream_time(read_start.elapsed());
+    result
+}
+
+/// Timeout for the model-list fetch behind name resolution (--model-list-timeout-seconds; 0 = none)
+pub fn model_list_timeout_seconds() -> Option<u64> {
+    Some(get_runtime_config().model_list_timeout_seconds).filter(|seconds| *seconds > 0)
+}
+
+/// A timed-out model-list fetch means LM Studio is struggling: fail fast instead of triggering a model load
+pub fn model_list_timeout_error(error: ProxyError) -> ProxyError {
+    match model_list_timeout_seconds() {
+        Some(seconds) if error.status_code == 504 => ProxyError::lm_studio_unavailable(&format!(
+            "{} after {}s (--model-list-timeout-seconds)",
+            ERROR_MODEL_LIST_TIMEOUT, seconds
+        )),
+        _ => error,
+    }
+}
+
 /// Enhanced JSON response handling with cancellation support - passes through LM Studio errors
 pub async fn handle_json_response(
     response: reqwest::Response,
@@ -81,24 +188,25 @@ pub async fn handle_json_response(
     let is_error = !status.is_success();
 
     tokio::select! {
-        result = response.json::<Value>() => {
-            match result {
-                Ok(json_value) => {
-                    if is_error {
-                        // Pass through LM Studio errors as-is but in ProxyError format
-                        let error_message = json_value.get("error")
-                            .and_then(|e| e.get("message"))
-                            .and_then(|m| m.as_str())
-                            .map(|s| s.to_string())
-                            .unwrap_or_else(|| format!("LM Studio error: {}", status));
-                        Err(ProxyError::new(error_message, status.as_u16()))
-                    } else {
-                        Ok(json_value)
-                    }
-                }
-                Err(e) => {
-                    Err(ProxyError::internal_server_error(&format!("Invalid JSON from LM Studio: {}", e)))
+        result = read_upstream_body(response) => {
+            let bytes = result.map_err(|e| {
+                if e.is_timeout() {
+                    ProxyError::gateway_timeout(ERROR_REQUEST_TIMEOUT)
+                } else {
+                    ProxyError::internal_server_error(&format!("Failed to read LM Studio response: {}", e))
                 }
+            })?;
+            let json_value = parse_json_lossy(&bytes)?;
+            if is_error {
+                // Pass through LM Studio errors as-is but in ProxyError format
+                let error_message = json_value.get("error")
+                    .and_then(|e| e.get("message"))
+                    .and_then(|m| m.as_str())
+                    .map(|s| s.to_string())
+                    .unwrap_or_else(|| format!("LM Studio error: {}", status));
+                Err(ProxyError::new(error_message, status.as_u16()))
+            } else {
+                Ok(json_value)
             }
         }
         _ = cancellation_token.cancelled() => {
@@ -107,15 +215,79 @@ pub async fn handle_json_response(
     }
 }
 
-/// Enhanced model name extraction
-pub fn extract_model_name<'a>(body: &'a Value, field_name: &str) -> Result<&'a str, ProxyError> {
-    body.get(field_name)
-        .and_then(|m| m.as_str())
-        .filter(|s| !s.is_empty())
-        .ok_or_else(|| match field_name {
-            "model" => ProxyError::bad_request(ERROR_MISSING_MODEL),
-            _ => ProxyError::bad_request("Missing required field"),
-        })
+/// Turn a 404 from an LM Studio inference endpoint into an error; unless it is about the model, the endpoint
+/// itself is missing for this LM Studio build and the error suggests the other API mode
+pub async fn check_endpoint_found(response: reqwest::Response, use_legacy: bool) -> Result<reqwest::Response, ProxyError> {
+    if response.status() != reqwest::StatusCode::NOT_FOUND {
+        return Ok(response);
+    }
+    let body = response.text().await.unwrap_or_default();
+    let message = match serde_json::from_str::<Value>(&body) {
+        Ok(json_value) => json_value
+            .get("error")
+            .map(|error| error.get("message").unwrap_or(error))
+            .and_then(Value::as_str)
+            .map(str::to_string),
+        Err(_) => None,
+    }
+        .unwrap_or_else(|| format!("LM Studio error: {}", reqwest::StatusCode::NOT_FOUND));
+    let error = ProxyError::new(message, 404);
+    if error.message.to_lowercase().contains("model") {
+        return Err(error);
+    }
+    Err(ProxyError::new(crate::handle_api_compatibility_error(&error, use_legacy), 404))
+}
+
+/// Parse a JSON body strictly, falling back to a lossy UTF-8 decode when the body has invalid bytes
+pub fn parse_json_lossy(bytes: &[u8]) -> Result<Value, ProxyError> {
+    let strict_error = match serde_json::from_slice::<Value>(bytes) {
+        Ok(value) => return Ok(value),
+        Err(e) => e,
+    };
+    if std::str::from_utf8(bytes).is_ok() {
+        return Err(ProxyError::internal_server_error(&format!("Invalid JSON from LM Studio: {}", strict_error)));
+    }
+
+    let decoded = String::from_utf8_lossy(bytes);
+    match serde_json::from_str::<Value>(&decoded) {
+        Ok(value) => {
+            log_warning("LM Studio response", "Invalid UTF-8 replaced with U+FFFD");
+            Ok(value)
+        }
+        Err(e) => Err(ProxyError::internal_server_error(&format!("Invalid JSON from LM Studio: {}", e))),
+    }
+}
+
+/// Enhanced model name extraction (numeric ids are coerced to strings)
+pub fn extract_model_name<'a>(body: &'a Value, field_name: &str) -> Result<Cow<'a, str>, ProxyError> {
+    let missing = || match field_name {
+        "model" => ProxyError::bad_request(ERROR_MISSING_MODEL),
+        _ => ProxyError::bad_request("Missing required field"),
+    };
+
+    match body.get(field_name) {
+        None | Some(Value::Null) => Err(missing()),
+        Some(Value::String(s)) if s.is_empty() => Err(missing()),
+        Some(Value::String(s)) => Ok(Cow::Borrowed(s.as_str())),
+        Some(Value::Number(n)) => Ok(Cow::Owned(n.to_string())),
+        Some(other) => Err(ProxyError::bad_request(&format!(
+            "'{}' must be a string, got {}",
+            field_name,
+            json_type_name(other)
+        ))),
+    }
+}
+
+/// JSON type name for error messages
+pub fn json_type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
 }
 
 /// Enhanced request builder with common parameters
@@ -171,30 +343,47 @@ pub fn map_ollama_to_lmstudio_params(
 ) -> serde_json::Map<String, Value> {
     let mut params = serde_json::Map::new();
 
+    // `options: null`, `[]` or any other non-object means "no options"
+    let ollama_options = match ollama_options {
+        Some(options) if options.is_object() => Some(options),
+        Some(Value::Null) | None => None,
+        Some(other) => {
+            log_warning(
+                "Options",
+                &format!("Ignoring 'options' of type {} (expected an object)", json_type_name(other)),
+            );
+            None
+        }
+    };
+
     if let Some(options) = ollama_options {
         // Direct parameter mappings
-        const DIRECT_MAPPINGS: &[&str] = &[
-            "temperature",
-            "top_p",
-            "top_k",
-            "presence_penalty",
-            "frequency_penalty",
-            "seed",
-            "stop",
-        ];
-
-        for param in DIRECT_MAPPINGS {
+        for param in OLLAMA_DIRECT_OPTIONS {
             if let Some(value) = options.get(param) {
                 params.insert(param.to_string(), value.clone());
             }
         }
 
+        // `stop` may be a single string or an array; LM Studio always gets an array
+        match options.get("stop").map(normalize_stop) {
+            Some(stop) if !stop.is_empty() => {
+                params.insert("stop".to_string(), Value::from(stop));
+            }
+            _ => {
+                params.remove("stop");
+            }
+        }
+
         // Special mappings
         if let Some(max_tokens) = options.get("num_predict") {
             params.insert("max_tokens".to_string(), max_tokens.clone());
         }
 
-        if let Some(repeat_penalty_val) = options.get("repeat_penalty") {
+        // Ollama's repeat_last_n: 0 disables the repetition penalty; LM Studio has no window size
+        let repeat_penalty = options
+            .get("repeat_penalty")
+            .filter(|_| options.get("repeat_last_n").and_then(Value::as_i64) != Some(0));
+        if let Some(repeat_penalty_val) = repeat_penalty {
             // Map to frequency_penalty
             if !params.contains_key("frequency_penalty") && !params.contains_key("presence_penalty")
             {
@@ -211,11 +400,70 @@ pub fn map_ollama_to_lmstudio_params(
         if let Some(system) = options.get("system") {
             params.insert("system".to_string(), system.clone());
         }
+
+        let dropped = newly_dropped_ollama_options(options);
+        if !dropped.is_empty() {
+            log_info(&format!("Options: dropped unsupported {}", dropped.join(", ")));
+        }
+        if let Some(notice) = num_thread_notice(options) {
+            log_info(notice);
+        }
     }
 
     params
 }
 
+/// Ollama options in a request that are not forwarded to LM Studio
+pub fn dropped_ollama_options(options: &Value) -> Vec<&'static str> {
+    OLLAMA_IGNORED_OPTIONS
+        .iter()
+        .copied()
+        .filter(|option| options.get(option).is_some())
+        .collect()
+}
+
+/// Dropped option names already logged in this process
+static DROPPED_OPTIONS_NOTED: Mutex<Vec<&'static str>> = Mutex::new(Vec::new());
+
+/// Dropped options in a request that this process has not logged before
+pub fn newly_dropped_ollama_options(options: &Value) -> Vec<&'static str> {
+    let mut noted = DROPPED_OPTIONS_NOTED.lock().unwrap_or_else(|e| e.into_inner());
+    let dropped: Vec<_> = dropped_ollama_options(options)
+        .into_iter()
+        .filter(|option| !noted.contains(option))
+        .collect();
+    noted.extend(&dropped);
+    dropped
+}
+
+/// Set once the num_thread explanation has been logged
+static NUM_THREAD_NOTED: AtomicBool = AtomicBool::new(false);
+
+/// Explanation for the first request in this process that sets `num_thread`
+pub fn num_thread_notice(options: &Value) -> Option<&'static str> {
+    if options.get("num_thread").is_none() || NUM_THREAD_NOTED.swap(true, Ordering::Relaxed) {
+        return None;
+    }
+    Some(NOTICE_NUM_THREAD_IGNORED)
+}
+
+/// Normalize Ollama `stop` (string or array of strings) to at most `MAX_STOP_SEQUENCES` entries
+fn normalize_stop(stop: &Value) -> Vec<String> {
+    let mut sequences: Vec<String> = match stop {
+        Value::String(s) => vec![s.clone()],
+        Value::Array(items) => items.iter().filter_map(|i| i.as_str().map(str::to_string)).collect(),
+        _ => Vec::new(),
+    };
+    if sequences.len() > MAX_STOP_SEQUENCES {
+        log_warning(
+            "Options",
+            &format!("Truncating 'stop' from {} to {} sequences", sequences.len(), MAX_STOP_SEQUENCES),
+        );
+        sequences.truncate(MAX_STOP_SEQUENCES);
+    }
+    sequences
+}
+
 /// Utility function to merge JSON objects efficiently
 pub fn merge_json_objects(
     base: &mut serde_json::Map<String, Value>,
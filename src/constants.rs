@@ -1,23 +1,62 @@
 /// src/constants.rs - Runtime configurable constants and static values with native API support
-
 use std::sync::OnceLock;
 
 /// Global configuration that can be set at runtime
 #[derive(Debug, Clone)]
 pub struct RuntimeConfig {
     pub max_buffer_size: usize,
-    pub max_partial_content_size: usize,
     pub string_buffer_size: usize,
     pub enable_chunk_recovery: bool,
+    pub enable_tag_stripping: bool,
+    pub fake_context: bool,
+    pub legacy_max_tokens: bool,
+    pub coalesce_stream_ms: u64,
+    pub drop_reasoning: bool,
+    pub default_quantization: String,
+    pub load_trigger_retries: Option<u32>,
+    pub case_sensitive_match: bool,
+    pub passthrough_model_resolution: bool,
+    /// Only requests at least this slow are logged (0 = log every request)
+    pub slow_request_threshold_ms: u64,
+    pub ollama_stream_format: OllamaStreamFormat,
+    /// Limit on the model-list fetch used for name resolution (0 = no limit)
+    pub model_list_timeout_seconds: u64,
+    /// Most tool-call argument bytes accepted from one stream (0 = no limit)
+    pub max_tool_call_args_bytes: usize,
+    /// Copy LM Studio's `system_fingerprint` onto final /api/chat and /api/generate responses
+    pub include_system_fingerprint: bool,
+}
+
+/// Framing of streamed /api/chat and /api/generate responses (--ollama-stream-format)
+#[derive(clap::ValueEnum, serde::Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum OllamaStreamFormat {
+    /// One JSON object per line, as Ollama sends
+    Ndjson,
+    /// `data: {...}` events ending with `data: [DONE]`, as OpenAI-style clients expect
+    Sse,
 }
 
 impl Default for RuntimeConfig {
     fn default() -> Self {
         Self {
             max_buffer_size: usize::MAX, // No limit
-            max_partial_content_size: usize::MAX, // No limit
             string_buffer_size: 2048,
             enable_chunk_recovery: true,
+            enable_tag_stripping: true,
+            fake_context: false,
+            legacy_max_tokens: false,
+            coalesce_stream_ms: 0,
+            drop_reasoning: false,
+            default_quantization: DEFAULT_QUANTIZATION.to_string(),
+            load_trigger_retries: None,
+            case_sensitive_match: false,
+            passthrough_model_resolution: true,
+            slow_request_threshold_ms: 0,
+            ollama_stream_format: OllamaStreamFormat::Ndjson,
+            model_list_timeout_seconds: DEFAULT_MODEL_LIST_TIMEOUT_SECONDS,
+            max_tool_call_args_bytes: DEFAULT_MAX_TOOL_CALL_ARGS_BYTES,
+            include_system_fingerprint: false,
         }
     }
 }
@@ -49,6 +88,165 @@ pub const LM_STUDIO_NATIVE_CHAT: &str = "/api/v0/chat/completions";
 pub const LM_STUDIO_NATIVE_COMPLETIONS: &str = "/api/v0/completions";
 pub const LM_STUDIO_NATIVE_EMBEDDINGS: &str = "/api/v0/embeddings";
 
+/// Text embedded once per embeddings model (per resolution cache TTL) to learn its vector size for /api/show
+pub const EMBEDDING_PROBE_INPUT: &str = "dimension probe";
+/// Most embedding models whose probed vector size is kept
+pub const EMBEDDING_LENGTH_CACHE_CAPACITY: u64 = 256;
+
+/// Ollama endpoints served by the proxy (matched case-insensitively, trailing slash tolerated)
+pub const OLLAMA_ENDPOINTS: &[&str] = &[
+    "/api/tags",
+    "/api/chat",
+    "/api/generate",
+    "/api/embed",
+    "/api/embeddings",
+    "/api/show",
+    "/api/ps",
+    "/api/version",
+    "/api/capabilities",
+    "/api/create",
+    "/api/pull",
+    "/api/push",
+    "/api/delete",
+    "/api/copy",
+    "/health",
+    "/metrics",
+];
+
+/// Ollama endpoints with a real handler (not answered by the unsupported-endpoint fallback)
+pub const OLLAMA_SERVED_ENDPOINTS: &[&str] = &[
+    "/api/tags",
+    "/api/chat",
+    "/api/generate",
+    "/api/embed",
+    "/api/embeddings",
+    "/api/show",
+    "/api/ps",
+    "/api/version",
+    "/api/capabilities",
+];
+
+/// Methods accepted by each served Ollama endpoint (reported in `Allow` headers)
+pub const OLLAMA_ENDPOINT_METHODS: &[(&str, &str)] = &[
+    ("/api/tags", "GET"),
+    ("/api/chat", "POST"),
+    ("/api/generate", "POST"),
+    ("/api/embed", "POST"),
+    ("/api/embeddings", "POST"),
+    ("/api/show", "POST"),
+    ("/api/ps", "GET"),
+    ("/api/version", "GET"),
+    ("/api/capabilities", "GET"),
+];
+
+/// Ollama options forwarded to LM Studio under the same name
+pub const OLLAMA_DIRECT_OPTIONS: &[&str] = &[
+    "temperature",
+    "top_p",
+    "top_k",
+    "min_p",
+    "presence_penalty",
+    "frequency_penalty",
+    "seed",
+    "stop",
+];
+
+/// Valid range (min, max) of numeric sampling options; out-of-range values are clamped or rejected (--strict-params)
+pub const SAMPLING_OPTION_RANGES: &[(&str, f64, f64)] = &[
+    ("temperature", 0.0, f64::INFINITY),
+    ("top_p", 0.0, 1.0),
+    ("top_k", 0.0, f64::INFINITY),
+    ("min_p", 0.0, 1.0),
+];
+
+/// Ollama options forwarded to LM Studio under a different name (ollama, lm studio)
+pub const OLLAMA_RENAMED_OPTIONS: &[(&str, &str)] = &[
+    ("num_predict", "max_tokens"),
+    ("repeat_penalty", "repeat_penalty / frequency_penalty"),
+    ("system", "system"),
+];
+
+/// Sampling fields also accepted at the top level of /api/chat and /api/generate bodies (OpenAI style)
+pub const TOP_LEVEL_SAMPLING_OPTIONS: &[&str] = &[
+    "temperature",
+    "top_p",
+    "top_k",
+    "min_p",
+    "presence_penalty",
+    "frequency_penalty",
+    "repeat_penalty",
+    "seed",
+    "stop",
+    "num_predict",
+];
+
+pub const NOTICE_KEEP_ALIVE_ZERO_IGNORED: &str =
+    "keep_alive: 0 does not unload models through this proxy; LM Studio unloads them after its idle TTL (or use `lms unload`)";
+pub const NOTICE_NUM_THREAD_IGNORED: &str =
+    "Options: num_thread is ignored; LM Studio manages CPU threads itself (set them in LM Studio's model load settings)";
+
+/// Ollama options accepted but not forwarded (no LM Studio equivalent per request)
+pub const OLLAMA_IGNORED_OPTIONS: &[&str] = &[
+    "num_batch",
+    "num_ctx",
+    "num_keep",
+    "num_gpu",
+    "main_gpu",
+    "num_thread",
+    "numa",
+    "low_vram",
+    "f16_kv",
+    "vocab_only",
+    "use_mmap",
+    "use_mlock",
+    "repeat_last_n",
+    "penalize_newline",
+    "tfs_z",
+    "typical_p",
+    "mirostat",
+    "mirostat_tau",
+    "mirostat_eta",
+];
+
+/// Image validation defaults
+pub const DEFAULT_MAX_IMAGE_BYTES: &str = "20971520";
+pub const DEFAULT_MAX_IMAGE_DIMENSION: &str = "8192";
+pub const IMAGE_RESIZE_MAX_ATTEMPTS: usize = 4;
+pub const IMAGE_DECODE_MAX_DIMENSION: u32 = 32768;
+pub const IMAGE_DECODE_MAX_ALLOC_BYTES: u64 = 512 * 1024 * 1024;
+
+/// Idle connections kept per LM Studio host; more concurrent upstream requests than this open new connections
+pub const HTTP_POOL_MAX_IDLE_PER_HOST: usize = 10;
+
+/// Per-request timeouts
+pub const DEFAULT_STREAM_TIMEOUT_SECONDS: u64 = 60;
+/// Streamed tool-call arguments beyond this are treated as a runaway model (1 MiB)
+pub const DEFAULT_MAX_TOOL_CALL_ARGS_BYTES: usize = 1024 * 1024;
+/// Most queued stream chunks merged into one body write
+pub const STREAM_WRITE_BATCH_MAX_CHUNKS: usize = 64;
+/// Upper bound on a mirrored --shadow-url request, including a streamed body
+pub const SHADOW_REQUEST_TIMEOUT_SECONDS: u64 = 600;
+/// Most mirrored --shadow-url requests in flight; further ones are dropped
+pub const SHADOW_MAX_IN_FLIGHT: usize = 16;
+/// Most stop sequences forwarded to LM Studio (the OpenAI API limit)
+pub const MAX_STOP_SEQUENCES: usize = 4;
+/// Accepted --model-rate-limit range in requests per second (one per ~17 minutes up to a million)
+pub const MODEL_RATE_LIMIT_MIN_RPS: f64 = 0.001;
+pub const MODEL_RATE_LIMIT_MAX_RPS: f64 = 1_000_000.0;
+pub const DEFAULT_MAX_REQUEST_TIMEOUT_SECONDS: &str = "3600";
+/// Model-list fetches for resolution fail fast instead of holding up every request
+pub const DEFAULT_MODEL_LIST_TIMEOUT_SECONDS: u64 = 10;
+
+/// First delay between --load-trigger-retries attempts (doubles each retry)
+pub const LOAD_TRIGGER_RETRY_BASE_DELAY_MS: u64 = 500;
+/// How often --auto-load-on-resolve checks whether a triggered model has finished loading
+pub const AUTO_LOAD_POLL_INTERVAL_MS: u64 = 500;
+
+/// How often the idle-shutdown task checks for activity
+pub const IDLE_CHECK_INTERVAL_SECONDS: u64 = 5;
+/// How often a shutdown drain checks for finished requests
+pub const DRAIN_CHECK_INTERVAL_MS: u64 = 100;
+
 /// Timing and performance constants
 pub const TOKEN_TO_CHAR_RATIO: f64 = 0.25;
 pub const DEFAULT_LOAD_DURATION_NS: u64 = 1_000_000;
@@ -61,11 +259,28 @@ pub const DEFAULT_MODEL_SIZE_BYTES: u64 = 4_000_000_000;
 /// Response headers
 pub const CONTENT_TYPE_JSON: &str = "application/json; charset=utf-8";
 pub const CONTENT_TYPE_SSE: &str = "text/event-stream";
+pub const CONTENT_TYPE_HTML: &str = "text/html; charset=utf-8";
 pub const HEADER_CACHE_CONTROL: &str = "no-cache";
 pub const HEADER_CONNECTION: &str = "keep-alive";
+/// Streams are never compressed; saying so stops intermediaries from compressing them again
+pub const HEADER_CONTENT_ENCODING_STREAM: &str = "identity";
 pub const HEADER_ACCESS_CONTROL_ALLOW_ORIGIN: &str = "*";
 pub const HEADER_ACCESS_CONTROL_ALLOW_METHODS: &str = "GET, POST, PUT, DELETE, OPTIONS";
 pub const HEADER_ACCESS_CONTROL_ALLOW_HEADERS: &str = "Content-Type, Authorization";
+pub const HEADER_PROXY_UPSTREAM_MS: &str = "x-proxy-upstream-ms";
+pub const HEADER_PROXY_OVERHEAD_MS: &str = "x-proxy-overhead-ms";
+pub const HEADER_LMSTUDIO_MODEL: &str = "x-lmstudio-model";
+/// How the model id was chosen (cache, exact, substring, scored, alias, fallback); --dev-mode only
+pub const HEADER_RESOLUTION_METHOD: &str = "x-resolution-method";
+pub const HEADER_REQUEST_TIMEOUT: &str = "x-request-timeout-seconds";
+pub const HEADER_MAX_TOKENS: &str = "x-max-tokens";
+pub const HEADER_REQUEST_ID: &str = "x-request-id";
+
+/// Ollama endpoints that wait for a --max-concurrent-requests slot (as do all /v1/* passthrough requests)
+pub const QUEUED_ENDPOINTS: &[&str] = &["/api/chat", "/api/generate", "/api/embed", "/api/embeddings"];
+
+/// Endpoints whose responses carry the resolved LM Studio model id
+pub const MODEL_HEADER_ENDPOINTS: &[&str] = &["/api/chat", "/api/generate", "/api/embed", "/api/embeddings"];
 
 /// Default parameter values
 pub const DEFAULT_TEMPERATURE: f64 = 0.7;
@@ -73,22 +288,44 @@ pub const DEFAULT_TOP_P: f64 = 0.9;
 pub const DEFAULT_TOP_K: u32 = 40;
 pub const DEFAULT_REPEAT_PENALTY: f64 = 1.1;
 pub const DEFAULT_KEEP_ALIVE_MINUTES: i64 = 5;
+pub const DEFAULT_REPORTED_OLLAMA_VERSION: &str = "0.5.1";
+/// Quantization reported when LM Studio or the model id does not reveal one
+pub const DEFAULT_QUANTIZATION: &str = "Q4_K_M";
 
 /// Error messages
+pub const ERROR_SHUTTING_DOWN: &str = "Server shutting down";
 pub const ERROR_MISSING_MODEL: &str = "Missing 'model' field";
 pub const ERROR_MISSING_MESSAGES: &str = "Missing 'messages' field";
 pub const ERROR_MISSING_PROMPT: &str = "Missing 'prompt' field";
+pub const ERROR_PROMPT_ON_CHAT: &str = "Received 'prompt' on /api/chat; did you mean /api/generate?";
+pub const ERROR_MESSAGES_ON_GENERATE: &str = "Received 'messages' on /api/generate; did you mean /api/chat?";
+pub const ERROR_SAMPLING_OUT_OF_RANGE: &str = "Sampling option out of range";
+pub const ERROR_TOO_MANY_MESSAGES: &str = "Too many messages in chat request";
+pub const ERROR_MULTIPLE_CHOICES: &str = "Ollama endpoints return a single response; 'n' > 1 is not supported (use /v1/chat/completions for multiple choices)";
 pub const ERROR_MISSING_INPUT: &str = "Missing 'input' or 'prompt' field";
+pub const ERROR_EMBEDDINGS_STREAM: &str = "Embeddings cannot be streamed; send 'stream': false (or omit it) and read a single JSON response";
+pub const ERROR_EMPTY_MESSAGES_NO_LOAD_HINTS: &str = "Empty 'messages' field. An empty request is normally treated as a load hint (load the model and return an empty response), but load hints are disabled on this proxy (--no-load-hints)";
+pub const ERROR_EMPTY_PROMPT_NO_LOAD_HINTS: &str = "Empty 'prompt' field. An empty request is normally treated as a load hint (load the model and return an empty response), but load hints are disabled on this proxy (--no-load-hints)";
 pub const ERROR_BUFFER_OVERFLOW: &str = "Stream buffer overflow";
 pub const ERROR_CHUNK_LIMIT: &str = "Stream exceeded maximum chunk limit";
 pub const ERROR_TIMEOUT: &str = "Stream timeout";
+pub const ERROR_TOOL_CALL_ARGS_TOO_LARGE: &str = "Tool call arguments exceeded --max-tool-call-args-bytes; stream stopped";
+pub const ERROR_REQUEST_TIMEOUT: &str = "LM Studio request timed out";
+pub const ERROR_MODEL_LIST_TIMEOUT: &str = "LM Studio did not return its model list";
 pub const ERROR_CANCELLED: &str = "Request cancelled by client";
 pub const ERROR_LM_STUDIO_UNAVAILABLE: &str = "LM Studio not available";
 pub const ERROR_REQUEST_TOO_LARGE: &str = "Request body too large";
+pub const ERROR_INVALID_JSON_BODY: &str = "Request body is not valid JSON";
+pub const ERROR_EMPTY_EMBEDDINGS: &str = "LM Studio returned no embeddings for a non-empty input";
+pub const ERROR_INVALID_IMAGE: &str = "Invalid image";
+pub const ERROR_IMAGE_TOO_LARGE: &str = "Image exceeds configured limits";
+pub const ERROR_PASSTHROUGH_FORBIDDEN: &str = "Passthrough endpoint not allowed by proxy configuration";
+pub const ERROR_NO_MODELS_AVAILABLE: &str = "LM Studio has no models available; load a model in LM Studio first";
 pub const ERROR_NATIVE_API_UNAVAILABLE: &str = "LM Studio native API not available - use --legacy flag for older versions";
 
 /// SSE parsing constants
 pub const SSE_DATA_PREFIX: &str = "data: ";
+pub const SSE_DATA_FIELD: &str = "data:";
 pub const SSE_DONE_MESSAGE: &str = "[DONE]";
 pub const SSE_MESSAGE_BOUNDARY: &str = "\n\n";
 
@@ -101,5 +338,5 @@ pub const LOG_PREFIX_CANCEL: &str = "🚫";
 pub const LOG_PREFIX_INFO: &str = "ℹ️";
 pub const LOG_PREFIX_CONN: &str = "↔️";
 
-/// Default context array for generate responses
+/// Placeholder context array for generate responses (only returned with --fake-context)
 pub const DEFAULT_CONTEXT: [u32; 3] = [1, 2, 3];
@@ -0,0 +1,75 @@
+/// src/failover.rs - Primary/fallback LM Studio backend selection (--lmstudio-url-fallback)
+use std::borrow::Cow;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::utils::{log_info, log_warning};
+
+/// Primary and fallback backends, and which one requests currently go to
+pub struct Failover {
+    primary: String,
+    fallback: String,
+    on_fallback: AtomicBool,
+}
+
+/// The path of `url` under `origin`, only when `url` is on that whole origin (not e.g. `:12340` under `:1234`)
+fn path_under<'u>(url: &'u str, origin: &str) -> Option<&'u str> {
+    url.strip_prefix(origin.trim_end_matches('/'))
+        .filter(|path| path.is_empty() || path.starts_with('/'))
+}
+
+impl Failover {
+    /// Fail over from `primary` to `fallback`
+    pub fn new(primary: String, fallback: String) -> Self {
+        Self {
+            primary,
+            fallback,
+            on_fallback: AtomicBool::new(false),
+        }
+    }
+
+    /// The backend requests currently go to
+    pub fn active_backend(&self) -> &str {
+        if self.on_fallback.load(Ordering::Relaxed) {
+            &self.fallback
+        } else {
+            &self.primary
+        }
+    }
+
+    /// Point a primary-backend URL at whichever backend is currently active
+    pub fn route_url<'u>(&self, url: &'u str) -> Cow<'u, str> {
+        if !self.on_fallback.load(Ordering::Relaxed) {
+            return Cow::Borrowed(url);
+        }
+        path_under(url, &self.primary)
+            .map(|path| Cow::Owned(format!("{}{}", self.fallback.trim_end_matches('/'), path)))
+            .unwrap_or(Cow::Borrowed(url))
+    }
+
+    /// After a connection error on `url`, switch to the other backend and return the URL to retry
+    pub fn fail_over_from(&self, url: &str) -> Option<String> {
+        let (path, to_fallback) = match path_under(url, &self.fallback) {
+            Some(path) => (path, false),
+            None => (path_under(url, &self.primary)?, true),
+        };
+        let (from, to) = if to_fallback {
+            (&self.primary, &self.fallback)
+        } else {
+            (&self.fallback, &self.primary)
+        };
+        if self.on_fallback.swap(to_fallback, Ordering::Relaxed) != to_fallback {
+            log_warning("Failover", &format!("{} unreachable, switching to {}", from, to));
+        }
+        Some(format!("{}{}", to.trim_end_matches('/'), path))
+    }
+
+    /// Record a background probe of the primary: unreachable moves traffic to the fallback, anything else back
+    pub fn record_primary_reachable(&self, reachable: bool) {
+        let was_on_fallback = self.on_fallback.swap(!reachable, Ordering::Relaxed);
+        if was_on_fallback && reachable {
+            log_info(&format!("Failover: primary {} is back, switching from {}", self.primary, self.fallback));
+        } else if !was_on_fallback && !reachable {
+            log_warning("Failover", &format!("{} unreachable, switching to {}", self.primary, self.fallback));
+        }
+    }
+}
@@ -0,0 +1,184 @@
+/// src/fair_queue.rs - Backend concurrency limit with weighted round-robin across clients (--fair-queue)
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex};
+use tokio::sync::oneshot;
+
+use crate::utils::extract_client_ip;
+
+/// Parse `--fair-queue-weight <client>=<weight>` values (client is an IP address or API key)
+pub fn parse_fair_queue_weights(specs: &[String]) -> Result<HashMap<String, u32>, String> {
+    specs
+        .iter()
+        .map(|spec| {
+            spec.split_once('=')
+                .map(|(client, weight)| (client.trim(), weight.trim().parse::<u32>()))
+                .and_then(|(client, weight)| match weight {
+                    Ok(weight) if !client.is_empty() && weight > 0 => Some((client.to_string(), weight)),
+                    _ => None,
+                })
+                .ok_or_else(|| format!("Invalid --fair-queue-weight '{}': expected <client>=<weight> (>= 1)", spec))
+        })
+        .collect()
+}
+
+/// Parse `--fair-queue-trusted-proxy <ip>` values
+pub fn parse_trusted_proxies(specs: &[String]) -> Result<Vec<IpAddr>, String> {
+    specs
+        .iter()
+        .map(|spec| {
+            spec.trim()
+                .parse::<IpAddr>()
+                .map_err(|_| format!("Invalid --fair-queue-trusted-proxy '{}': expected an IP address", spec))
+        })
+        .collect()
+}
+
+/// Queue key for a request: the peer address. Requests from a trusted proxy are keyed by their bearer
+/// API key, else the forwarded client IP, since every client behind the proxy shares its address.
+pub fn client_key(headers: &warp::http::HeaderMap, remote_ip: Option<IpAddr>, trusted_proxies: &[IpAddr]) -> String {
+    let peer = remote_ip.map(|ip| ip.to_string());
+    if !remote_ip.is_some_and(|ip| trusted_proxies.contains(&ip)) {
+        return peer.unwrap_or_default();
+    }
+    headers
+        .get(warp::http::header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(|key| key.trim().to_string())
+        .filter(|key| !key.is_empty())
+        .or_else(|| extract_client_ip(headers))
+        .or(peer)
+        .unwrap_or_default()
+}
+
+#[derive(Default)]
+struct QueueState {
+    in_flight: usize,
+    waiting: HashMap<String, VecDeque<oneshot::Sender<FairPermit>>>,
+    /// Clients with waiting requests; the front one is being served
+    rotation: VecDeque<String>,
+    /// Requests handed to the front client during its current turn
+    turn_used: u32,
+}
+
+/// At most `capacity` requests reach the backend at once; waiting requests are released
+/// client by client, each client getting `weight` requests per turn (default 1)
+pub struct FairQueue {
+    capacity: usize,
+    weights: HashMap<String, u32>,
+    state: Mutex<QueueState>,
+}
+
+/// A backend slot; dropping it hands the slot to the next waiting request
+pub struct FairPermit {
+    /// `None` once the slot has been taken back by `release`
+    queue: Option<Arc<FairQueue>>,
+}
+
+impl Drop for FairPermit {
+    fn drop(&mut self) {
+        if let Some(queue) = self.queue.take() {
+            queue.release();
+        }
+    }
+}
+
+impl FairQueue {
+    /// Create a queue with `capacity` backend slots and per-client weights
+    pub fn new(capacity: usize, weights: HashMap<String, u32>) -> Arc<Self> {
+        Arc::new(Self {
+            capacity: capacity.max(1),
+            weights,
+            state: Mutex::new(QueueState::default()),
+        })
+    }
+
+    /// Requests currently holding a slot
+    pub fn in_flight(&self) -> usize {
+        self.lock().in_flight
+    }
+
+    /// Requests waiting for a slot
+    pub fn queued(&self) -> usize {
+        self.lock().waiting.values().map(VecDeque::len).sum()
+    }
+
+    /// Join the queue now and wait for a slot. `None` only if the queue is torn down.
+    pub fn acquire(self: &Arc<Self>, client: &str) -> impl Future<Output = Option<FairPermit>> {
+        let waiter = {
+            let mut state = self.lock();
+            if state.in_flight < self.capacity && state.rotation.is_empty() {
+                state.in_flight += 1;
+                Ok(FairPermit { queue: Some(self.clone()) })
+            } else {
+                let (tx, rx) = oneshot::channel();
+                if !state.waiting.contains_key(client) {
+                    state.rotation.push_back(client.to_string());
+                }
+                state.waiting.entry(client.to_string()).or_default().push_back(tx);
+                Err(rx)
+            }
+        };
+        async move {
+            match waiter {
+                Ok(permit) => Some(permit),
+                Err(rx) => rx.await.ok(),
+            }
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, QueueState> {
+        self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Hand a freed slot to the next waiter in weighted round-robin order, or return it
+    fn release(self: &Arc<Self>) {
+        loop {
+            let next = {
+                let mut state = self.lock();
+                let next = self.next_waiter(&mut state);
+                if next.is_none() {
+                    state.in_flight -= 1;
+                }
+                next
+            };
+            let Some(tx) = next else { return };
+            match tx.send(FairPermit { queue: Some(self.clone()) }) {
+                Ok(()) => return,
+                // The waiter gave up after it was picked; keep the slot and try the next one
+                Err(mut permit) => permit.queue = None,
+            }
+        }
+    }
+
+    fn next_waiter(&self, state: &mut QueueState) -> Option<oneshot::Sender<FairPermit>> {
+        loop {
+            let client = state.rotation.front()?.clone();
+            let queue = state.waiting.get_mut(&client)?;
+            // Waiters that gave up are dropped without using up the client's turn
+            while queue.front().is_some_and(oneshot::Sender::is_closed) {
+                queue.pop_front();
+            }
+            let tx = queue.pop_front();
+            let drained = queue.is_empty();
+            if tx.is_some() {
+                state.turn_used += 1;
+            }
+
+            let weight = self.weights.get(&client).copied().unwrap_or(1);
+            if drained {
+                state.waiting.remove(&client);
+                state.rotation.pop_front();
+                state.turn_used = 0;
+            } else if state.turn_used >= weight {
+                state.rotation.rotate_left(1);
+                state.turn_used = 0;
+            }
+            if tx.is_some() {
+                return tx;
+            }
+        }
+    }
+}
@@ -0,0 +1,81 @@
+/// src/handlers/debug.rs - Development-only diagnostics (enabled with --dev-mode)
+use serde_json::{json, Value};
+
+use crate::common::extract_model_name;
+use crate::failover::Failover;
+use crate::handlers::helpers::{check_single_choice, requested_draft_model, translate_inference_request, InferenceEndpoint};
+use crate::model::clean_model_name;
+use crate::server::{redact_url, ApiMode, Config, ModelResolverType};
+use crate::utils::ProxyError;
+
+/// Handle POST /debug/translate - show the LM Studio request an Ollama chat/generate body becomes.
+/// The body goes through the same preparation and translation as a real request (see `prepare_inference_body`).
+pub async fn handle_debug_translate(
+    model_resolver: ModelResolverType,
+    body: Value,
+    config: &Config,
+) -> Result<Value, ProxyError> {
+    let ollama_model_name = &*extract_model_name(&body, "model")?;
+    let endpoint = if body.get("messages").is_some() {
+        InferenceEndpoint::Chat
+    } else if body.get("prompt").is_some() {
+        InferenceEndpoint::Generate
+    } else {
+        return Err(ProxyError::bad_request(
+            "Expected an Ollama /api/chat body ('messages') or /api/generate body ('prompt')",
+        ));
+    };
+    check_single_choice(&body)?;
+
+    // Only pins and cached resolutions are used so LM Studio is never contacted
+    let (lm_studio_model_id, resolution) = peek_model_id(&model_resolver, ollama_model_name).await;
+    let legacy_api = matches!(model_resolver, ModelResolverType::Legacy(_));
+    let (lm_studio_endpoint, mut lm_request) =
+        translate_inference_request(endpoint, &body, &lm_studio_model_id, legacy_api)?;
+
+    if let Some(draft_model) = requested_draft_model(body.get("options"), config.draft_model.as_deref()) {
+        let (draft_model_id, _) = peek_model_id(&model_resolver, draft_model).await;
+        if let Some(request_obj) = lm_request.as_object_mut() {
+            request_obj.insert("draft_model".to_string(), json!(draft_model_id));
+        }
+    }
+
+    Ok(json!({
+        "ollama_endpoint": endpoint.path(),
+        "model": ollama_model_name,
+        "resolved_model": lm_studio_model_id,
+        "resolution": resolution,
+        "url": format!("{}{}", config.lmstudio_url, lm_studio_endpoint),
+        "request": lm_request
+    }))
+}
+
+/// The LM Studio id for a model from a pin or cached resolution, else its cleaned name, with how it was found
+async fn peek_model_id(model_resolver: &ModelResolverType, ollama_model_name: &str) -> (String, &'static str) {
+    match model_resolver.peek_resolution(ollama_model_name).await {
+        Some(resolved) if resolved.cache_hit() => (resolved.id, "cached"),
+        Some(resolved) => (resolved.id, "pinned"),
+        None => (clean_model_name(ollama_model_name).to_string(), "unresolved"),
+    }
+}
+
+/// Handle GET /debug/config - the effective configuration with secrets redacted, plus computed values
+pub fn handle_debug_config(config: &Config, model_resolver: &ModelResolverType, failover: Option<&Failover>) -> Value {
+    let api_mode = match model_resolver {
+        ModelResolverType::Native(_) => "native",
+        ModelResolverType::Legacy(_) if config.api_mode == ApiMode::Auto => "legacy (auto-detected)",
+        ModelResolverType::Legacy(_) if !config.legacy => "legacy (auto fallback)",
+        ModelResolverType::Legacy(_) => "legacy",
+    };
+    let active_backend = failover.map_or(config.lmstudio_url.as_str(), Failover::active_backend);
+
+    json!({
+        "version": crate::VERSION,
+        "config": serde_json::to_value(config).unwrap_or(Value::Null),
+        "computed": {
+            "api_mode": api_mode,
+            "active_backend": redact_url(active_backend),
+            "model_resolution_cache_ttl_seconds": config.model_resolution_cache_ttl_seconds
+        }
+    })
+}
@@ -1,10 +1,12 @@
 /// src/handlers/helpers.rs - Enhanced request/response transformation with native API support
-
 use serde_json::{json, Value};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::{Duration, Instant};
 
 use crate::common::{map_ollama_to_lmstudio_params, RequestBuilder};
 use crate::constants::*;
+use crate::handlers::retry::LoadMethod;
+use crate::server::ModelResolverType;
 
 /// Create JSON response with proper headers
 pub fn json_response(value: &Value) -> warp::reply::Response {
@@ -163,6 +165,7 @@ impl ResponseTransformer {
         start_time: Instant,
         use_native_stats: bool,
     ) -> Value {
+        Self::warn_extra_choices(lm_response);
         let content = Self::extract_chat_content_with_reasoning(lm_response);
 
         let timing = if use_native_stats {
@@ -201,9 +204,9 @@ impl ResponseTransformer {
             }
         }
 
-        json!({
+        let mut ollama_response = json!({
             "model": model_ollama_name,
-            "created_at": chrono::Utc::now().to_rfc3339(),
+            "created_at": ollama_timestamp(),
             "message": ollama_message,
             "done": true,
             "total_duration": timing.total_duration,
@@ -212,7 +215,9 @@ impl ResponseTransformer {
             "prompt_eval_duration": timing.prompt_eval_duration,
             "eval_count": timing.eval_count,
             "eval_duration": timing.eval_duration
-        })
+        });
+        attach_system_fingerprint(&mut ollama_response, system_fingerprint(lm_response));
+        ollama_response
     }
 
     /// Transform LM Studio completion response to Ollama format with native API support
@@ -223,6 +228,7 @@ impl ResponseTransformer {
         start_time: Instant,
         use_native_stats: bool,
     ) -> Value {
+        Self::warn_extra_choices(lm_response);
         let content = Self::extract_completion_content(lm_response);
 
         let timing = if use_native_stats {
@@ -244,30 +250,42 @@ impl ResponseTransformer {
             )
         };
 
-        json!({
+        let mut ollama_response = json!({
             "model": model_ollama_name,
-            "created_at": chrono::Utc::now().to_rfc3339(),
+            "created_at": ollama_timestamp(),
             "response": content,
             "done": true,
-            "context": DEFAULT_CONTEXT,
+            "context": generate_response_context(),
             "total_duration": timing.total_duration,
             "load_duration": timing.load_duration,
             "prompt_eval_count": timing.prompt_eval_count,
             "prompt_eval_duration": timing.prompt_eval_duration,
             "eval_count": timing.eval_count,
             "eval_duration": timing.eval_duration
-        })
+        });
+        attach_system_fingerprint(&mut ollama_response, system_fingerprint(lm_response));
+        ollama_response
     }
 
     /// Transform LM Studio embeddings response to Ollama format with native API support
     pub fn convert_to_ollama_embeddings(
         lm_response: &Value,
         model_ollama_name: &str,
+        input: &Value,
         start_time: Instant,
         use_native_stats: bool,
-    ) -> Value {
+    ) -> Result<Value, crate::utils::ProxyError> {
         let embeddings = Self::extract_embeddings(lm_response);
 
+        // An empty result for a non-empty input is an upstream failure, not a success
+        let input_count = embedding_input_count(input);
+        if embeddings.is_empty() && input_count > 0 {
+            return Err(crate::utils::ProxyError::new(
+                format!("{} ({} inputs)", ERROR_EMPTY_EMBEDDINGS, input_count),
+                502,
+            ));
+        }
+
         let estimated_input_tokens = 10;
         let estimated_output_tokens = embeddings.len().max(1) as u64;
 
@@ -285,17 +303,28 @@ impl ResponseTransformer {
             )
         };
 
-        json!({
+        Ok(json!({
             "model": model_ollama_name,
             "embeddings": embeddings,
             "total_duration": timing.total_duration,
             "load_duration": timing.load_duration,
             "prompt_eval_count": timing.prompt_eval_count,
             "prompt_eval_duration": timing.prompt_eval_duration
-        })
+        }))
     }
 
-    /// Extract chat content including reasoning
+    /// Ollama has room for one choice; say so if LM Studio returned more
+    fn warn_extra_choices(lm_response: &Value) {
+        let choice_count = lm_response.get("choices").and_then(|c| c.as_array()).map_or(0, |c| c.len());
+        if choice_count > 1 {
+            crate::utils::log_warning(
+                "Response",
+                &format!("LM Studio returned {} choices; only the first is sent to the client", choice_count),
+            );
+        }
+    }
+
+    /// Extract chat content including reasoning (or with all reasoning removed under --drop-reasoning)
     fn extract_chat_content_with_reasoning(lm_response: &Value) -> String {
         let base_content = lm_response
             .get("choices")
@@ -304,6 +333,10 @@ impl ResponseTransformer {
             .unwrap_or("")
             .to_string();
 
+        if get_runtime_config().drop_reasoning {
+            return strip_think_tags(&base_content);
+        }
+
         if let Some(reasoning) = lm_response
             .get("choices")
             .and_then(|c| c.as_array()?.first())
@@ -318,12 +351,22 @@ impl ResponseTransformer {
 
     /// Extract completion content from response
     fn extract_completion_content(lm_response: &Value) -> String {
-        lm_response
+        // Generate requests with images go through the chat endpoint; only the message text becomes `response`
+        let text = lm_response
             .get("choices")
             .and_then(|c| c.as_array()?.first())
-            .and_then(|choice| choice.get("text")?.as_str())
-            .unwrap_or("")
-            .to_string()
+            .and_then(|choice| {
+                choice
+                    .get("text")
+                    .or_else(|| choice.get("message")?.get("content"))?
+                    .as_str()
+            })
+            .unwrap_or("");
+        if get_runtime_config().drop_reasoning {
+            strip_think_tags(text)
+        } else {
+            text.to_string()
+        }
     }
 
     /// Extract embeddings from response
@@ -341,11 +384,13 @@ impl ResponseTransformer {
 }
 
 /// Build LM Studio request from Ollama parameters with enhanced parameter mapping
+/// (`legacy_api`: the request goes to the OpenAI-compatible API, e.g. after --auto-legacy-fallback)
 pub fn build_lm_studio_request(
     model_lm_studio_id: &str,
     request_type: LMStudioRequestType,
     ollama_options: Option<&Value>,
     ollama_tools: Option<&Value>,
+    legacy_api: bool,
 ) -> Value {
     let mut builder = RequestBuilder::new()
         .add_required("model", model_lm_studio_id);
@@ -353,7 +398,7 @@ pub fn build_lm_studio_request(
     match request_type {
         LMStudioRequestType::Chat { messages, stream } => {
             builder = builder
-                .add_required("messages", messages.clone())
+                .add_required("messages", normalize_tool_messages(messages))
                 .add_required("stream", stream);
             if let Some(tools_val) = ollama_tools {
                 if tools_val.is_array() && !tools_val.as_array().unwrap().is_empty() {
@@ -390,11 +435,212 @@ pub fn build_lm_studio_request(
         for (key, value) in lm_studio_mapped_params {
             request_obj.insert(key, value);
         }
+
+        // Chat endpoints deprecate `max_tokens`; the completions endpoint still expects it
+        let legacy_max_tokens = get_runtime_config().legacy_max_tokens || legacy_api;
+        if request_obj.contains_key("messages") && !legacy_max_tokens {
+            if let Some(max_tokens) = request_obj.remove("max_tokens") {
+                request_obj.insert("max_completion_tokens".to_string(), max_tokens);
+            }
+        }
     }
 
     request_json
 }
 
+/// Ollama inference endpoint an incoming body was sent to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InferenceEndpoint {
+    Chat,
+    Generate,
+}
+
+impl InferenceEndpoint {
+    /// The Ollama path for this endpoint
+    pub fn path(self) -> &'static str {
+        match self {
+            InferenceEndpoint::Chat => "/api/chat",
+            InferenceEndpoint::Generate => "/api/generate",
+        }
+    }
+}
+
+/// Translate an Ollama /api/chat or /api/generate body into the LM Studio endpoint path and request for a
+/// resolved model. Generate requests with images go to the chat endpoint as a single user message.
+pub fn translate_inference_request(
+    endpoint: InferenceEndpoint,
+    body: &Value,
+    model_lm_studio_id: &str,
+    legacy_api: bool,
+) -> Result<(&'static str, Value), crate::utils::ProxyError> {
+    let stream = crate::handlers::streaming::is_streaming_request(body);
+    let (lm_studio_endpoint, request_type, tools) = match endpoint {
+        InferenceEndpoint::Chat => {
+            let messages = body
+                .get("messages")
+                .filter(|m| m.is_array())
+                .ok_or_else(|| crate::utils::ProxyError::bad_request(ERROR_MISSING_MESSAGES))?;
+            let chat_endpoint = if legacy_api { LM_STUDIO_LEGACY_CHAT } else { LM_STUDIO_NATIVE_CHAT };
+            (chat_endpoint, LMStudioRequestType::Chat { messages, stream }, body.get("tools"))
+        }
+        InferenceEndpoint::Generate => {
+            let prompt = body
+                .get("prompt")
+                .and_then(|p| p.as_str())
+                .ok_or_else(|| crate::utils::ProxyError::bad_request(ERROR_MISSING_PROMPT))?;
+            let images = body
+                .get("images")
+                .filter(|i| i.as_array().is_some_and(|a| !a.is_empty()));
+            let generate_endpoint = match (images.is_some(), legacy_api) {
+                (true, false) => LM_STUDIO_NATIVE_CHAT,
+                (true, true) => LM_STUDIO_LEGACY_CHAT,
+                (false, false) => LM_STUDIO_NATIVE_COMPLETIONS,
+                (false, true) => LM_STUDIO_LEGACY_COMPLETIONS,
+            };
+            (generate_endpoint, LMStudioRequestType::Completion { prompt, stream, images }, None)
+        }
+    };
+
+    let mut lm_request = build_lm_studio_request(
+        model_lm_studio_id,
+        request_type,
+        body.get("options"),
+        tools,
+        legacy_api,
+    );
+    apply_response_format(&mut lm_request, body.get("format"));
+    Ok((lm_studio_endpoint, lm_request))
+}
+
+/// LM Studio `response_format` for an Ollama `format`: JSON mode only for exactly `"json"`, structured output
+/// for a schema object. Empty, unknown or other values mean no format.
+pub fn response_format_for(format: Option<&Value>) -> Option<Value> {
+    match format? {
+        Value::String(format) if format == "json" => Some(json!({"type": "json_object"})),
+        Value::Object(schema) if !schema.is_empty() => Some(json!({
+            "type": "json_schema",
+            "json_schema": {"name": "response", "strict": true, "schema": schema}
+        })),
+        Value::String(format) if format.trim().is_empty() => None,
+        Value::Null => None,
+        other => {
+            crate::utils::log_warning("Format", &format!("Ignoring unsupported 'format' value {}", other));
+            None
+        }
+    }
+}
+
+/// Add the `response_format` for an Ollama `format` to a built LM Studio request
+pub fn apply_response_format(lm_request: &mut Value, format: Option<&Value>) {
+    if let (Some(request_obj), Some(response_format)) = (lm_request.as_object_mut(), response_format_for(format)) {
+        request_obj.insert("response_format".to_string(), response_format);
+    }
+}
+
+/// Fill in what OpenAI-style tool calling needs from Ollama-style messages: assistant `tool_calls` get an
+/// `id`, `type` and string `arguments`, and `tool` results without a `tool_call_id` are matched to the pending
+/// call with the same `tool_name` (else the oldest one). Messages already in OpenAI form are left unchanged.
+pub fn normalize_tool_messages(messages: &Value) -> Value {
+    let Some(messages) = messages.as_array() else {
+        return messages.clone();
+    };
+    // (id, function name) of tool calls not answered yet
+    let mut pending: Vec<(String, String)> = Vec::new();
+    let normalized = messages
+        .iter()
+        .enumerate()
+        .map(|(message_index, message)| {
+            let mut message = message.clone();
+            let Some(message_obj) = message.as_object_mut() else {
+                return message;
+            };
+            match message_obj.get("role").and_then(Value::as_str) {
+                Some("assistant") => {
+                    pending.clear();
+                    let calls = message_obj.get_mut("tool_calls").and_then(Value::as_array_mut);
+                    for (call_index, call) in calls.into_iter().flatten().enumerate() {
+                        let Some(call_obj) = call.as_object_mut() else {
+                            continue;
+                        };
+                        let id = call_obj
+                            .entry("id")
+                            .or_insert_with(|| json!(format!("call_{}_{}", message_index, call_index)))
+                            .as_str()
+                            .unwrap_or_default()
+                            .to_string();
+                        call_obj.entry("type").or_insert_with(|| json!("function"));
+                        let mut name = String::new();
+                        if let Some(function) = call_obj.get_mut("function").and_then(Value::as_object_mut) {
+                            if let Some(arguments) = function.get_mut("arguments").filter(|a| !a.is_string()) {
+                                *arguments = Value::String(arguments.to_string());
+                            }
+                            name = function.get("name").and_then(Value::as_str).unwrap_or_default().to_string();
+                        }
+                        pending.push((id, name));
+                    }
+                }
+                Some("tool") => {
+                    let answered = match message_obj.get("tool_call_id").and_then(Value::as_str) {
+                        Some(id) => pending.iter().position(|(pending_id, _)| pending_id == id),
+                        None => {
+                            let tool_name = message_obj
+                                .get("tool_name")
+                                .or_else(|| message_obj.get("name"))
+                                .and_then(Value::as_str);
+                            let position = tool_name
+                                .and_then(|tool_name| pending.iter().position(|(_, name)| name == tool_name))
+                                .or((!pending.is_empty()).then_some(0));
+                            if let Some(position) = position {
+                                message_obj.insert("tool_call_id".to_string(), json!(pending[position].0));
+                            }
+                            position
+                        }
+                    };
+                    if let Some(position) = answered {
+                        pending.remove(position);
+                    }
+                }
+                _ => {}
+            }
+            message
+        })
+        .collect();
+    Value::Array(normalized)
+}
+
+/// Ollama embeddings endpoint a request came in on; each names the response field differently
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EmbeddingsEndpoint {
+    /// /api/embed: `embeddings`, an array of vectors
+    Embed,
+    /// /api/embeddings (older API): `embedding`, a single vector, for a string input
+    Embeddings,
+}
+
+impl EmbeddingsEndpoint {
+    pub fn path(self) -> &'static str {
+        match self {
+            EmbeddingsEndpoint::Embed => "/api/embed",
+            EmbeddingsEndpoint::Embeddings => "/api/embeddings",
+        }
+    }
+
+    /// Rename `embeddings` to `embedding` (the first vector) where the older endpoint expects it
+    pub fn shape_response(self, mut ollama_response: Value, input: &Value) -> Value {
+        if self != EmbeddingsEndpoint::Embeddings || !input.is_string() {
+            return ollama_response;
+        }
+        if let Some(response_obj) = ollama_response.as_object_mut() {
+            let first = response_obj
+                .remove("embeddings")
+                .and_then(|e| e.as_array()?.first().cloned())
+                .unwrap_or_else(|| json!([]));
+            response_obj.insert("embedding".to_string(), first);
+        }
+        ollama_response
+    }
+}
+
 /// Request type enumeration
 pub enum LMStudioRequestType<'a> {
     Chat { messages: &'a Value, stream: bool },
@@ -427,16 +673,211 @@ pub fn extract_content_from_chunk(chunk: &Value) -> Option<String> {
         })
 }
 
+/// How long Ollama should keep a model loaded after a request
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeepAlive {
+    /// Negative values: keep the model loaded indefinitely
+    Forever,
+    /// Zero: unload the model now
+    Unload,
+    Duration(Duration),
+}
+
+impl Default for KeepAlive {
+    fn default() -> Self {
+        KeepAlive::Duration(Duration::from_secs(DEFAULT_KEEP_ALIVE_MINUTES as u64 * 60))
+    }
+}
+
+impl KeepAlive {
+    /// None for NaN or values too large for a `Duration`
+    fn from_seconds(seconds: f64) -> Option<Self> {
+        if seconds < 0.0 {
+            Some(KeepAlive::Forever)
+        } else if seconds == 0.0 {
+            Some(KeepAlive::Unload)
+        } else {
+            Duration::try_from_secs_f64(seconds).ok().map(KeepAlive::Duration)
+        }
+    }
+}
+
+/// Parse Ollama `keep_alive`: seconds as a number or string, `-1` (forever), `0` (unload),
+/// or a Go-style duration such as `5m` or `1h30m`. Malformed values fall back to the default with a warning.
+pub fn parse_keep_alive(value: &Value) -> KeepAlive {
+    let parsed = match value {
+        Value::Null => return KeepAlive::default(),
+        Value::Number(n) => n.as_f64().and_then(KeepAlive::from_seconds),
+        Value::String(s) => parse_keep_alive_str(s.trim()),
+        _ => None,
+    };
+    parsed.unwrap_or_else(|| {
+        crate::utils::log_warning(
+            "keep_alive",
+            &format!("Ignoring invalid value {} (using default {}m)", value, DEFAULT_KEEP_ALIVE_MINUTES),
+        );
+        KeepAlive::default()
+    })
+}
+
+fn parse_keep_alive_str(raw: &str) -> Option<KeepAlive> {
+    if let Ok(seconds) = raw.parse::<f64>() {
+        return KeepAlive::from_seconds(seconds).filter(|_| seconds.is_finite());
+    }
+
+    let (negative, mut rest) = match raw.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, raw.strip_prefix('+').unwrap_or(raw)),
+    };
+    if rest.is_empty() {
+        return None;
+    }
+
+    let mut seconds = 0.0;
+    while !rest.is_empty() {
+        let number_len = rest.find(|c: char| !(c.is_ascii_digit() || c == '.')).unwrap_or(rest.len());
+        let number: f64 = rest[..number_len].parse().ok()?;
+        rest = &rest[number_len..];
+        let unit_len = rest.find(|c: char| c.is_ascii_digit() || c == '.').unwrap_or(rest.len());
+        let scale = match &rest[..unit_len] {
+            "ms" => 0.001,
+            "s" => 1.0,
+            "m" => 60.0,
+            "h" => 3600.0,
+            _ => return None,
+        };
+        seconds += number * scale;
+        rest = &rest[unit_len..];
+    }
+
+    KeepAlive::from_seconds(if negative { -seconds } else { seconds })
+}
+
+/// An empty request with `keep_alive: 0` asks Ollama to unload the model.
+/// LM Studio manages model lifetimes itself, so the proxy only acknowledges it.
+pub fn is_unload_request(body: &Value) -> bool {
+    body.get("keep_alive")
+        .is_some_and(|keep_alive| parse_keep_alive(keep_alive) == KeepAlive::Unload)
+}
+
+/// Set once the `keep_alive: 0` notice has been logged
+static KEEP_ALIVE_ZERO_NOTED: AtomicBool = AtomicBool::new(false);
+
+/// Log, once per process, that `keep_alive: 0` leaves unloading to LM Studio's idle TTL
+pub fn note_keep_alive_zero(body: &Value) {
+    if is_unload_request(body) && !KEEP_ALIVE_ZERO_NOTED.swap(true, Ordering::Relaxed) {
+        crate::utils::log_info(NOTICE_KEEP_ALIVE_ZERO_IGNORED);
+    }
+}
+
+/// How to load the model for this request: native mode JIT-loads with a TTL taken from `keep_alive`
+/// (LM Studio unloads it after that long idle), legacy mode sends a minimal chat ping
+pub fn load_method_for(model_resolver: &ModelResolverType, body: &Value) -> LoadMethod {
+    match model_resolver {
+        ModelResolverType::Legacy(_) => LoadMethod::Ping,
+        ModelResolverType::Native(_) => {
+            let keep_alive = body.get("keep_alive").map(parse_keep_alive).unwrap_or_default();
+            let ttl_seconds = match keep_alive {
+                KeepAlive::Duration(duration) => Some(duration.as_secs_f64().ceil().max(1.0) as u64),
+                // LM Studio has no "never unload" TTL; keep its JIT default
+                KeepAlive::Forever | KeepAlive::Unload => None,
+            };
+            LoadMethod::Native { ttl_seconds }
+        }
+    }
+}
+
+const THINK_OPEN: &str = "<think>";
+const THINK_CLOSE: &str = "</think>";
+
+/// Removes `<think>...</think>` blocks from text that may arrive in arbitrary pieces (--drop-reasoning)
+#[derive(Debug, Default)]
+pub struct ThinkTagFilter {
+    in_think: bool,
+    /// Whitespace right after a closed block is dropped until the answer starts
+    trim_next: bool,
+    /// Unprocessed tail that may be the start of a tag
+    pending: String,
+}
+
+impl ThinkTagFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed the next piece of text; returns what can be emitted now
+    pub fn push(&mut self, text: &str) -> String {
+        self.pending.push_str(text);
+        let mut output = String::new();
+        loop {
+            let tag = if self.in_think { THINK_CLOSE } else { THINK_OPEN };
+            if let Some(pos) = self.pending.find(tag) {
+                if !self.in_think {
+                    self.emit(pos, &mut output);
+                } else {
+                    self.trim_next = true;
+                }
+                self.pending.drain(..pos + tag.len());
+                self.in_think = !self.in_think;
+                continue;
+            }
+
+            // Hold back a suffix that could be the beginning of the tag
+            let held = (1..tag.len())
+                .rev()
+                .find(|&n| self.pending.ends_with(&tag[..n]))
+                .unwrap_or(0);
+            let ready = self.pending.len() - held;
+            if self.in_think {
+                self.pending.drain(..ready);
+            } else {
+                self.emit(ready, &mut output);
+                self.pending.drain(..ready);
+            }
+            return output;
+        }
+    }
+
+    /// Flush at end of input; an unterminated block is dropped
+    pub fn finish(&mut self) -> String {
+        let mut output = String::new();
+        if !self.in_think {
+            self.emit(self.pending.len(), &mut output);
+        }
+        self.pending.clear();
+        output
+    }
+
+    fn emit(&mut self, len: usize, output: &mut String) {
+        let mut text = &self.pending[..len];
+        if self.trim_next {
+            text = text.trim_start();
+            self.trim_next = text.is_empty();
+        }
+        output.push_str(text);
+    }
+}
+
+/// Remove all `<think>` blocks from a complete response
+pub fn strip_think_tags(text: &str) -> String {
+    let mut filter = ThinkTagFilter::new();
+    filter.push(text) + &filter.finish()
+}
+
+/// Ollama-style `created_at` timestamp (RFC3339, UTC, millisecond precision)
+pub fn ollama_timestamp() -> String {
+    chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true)
+}
+
 /// Create Ollama streaming chunk with enhanced metadata support
 pub fn create_ollama_streaming_chunk(
     model_ollama_name: &str,
+    created_at: &str,
     content: &str,
     is_chat_endpoint: bool,
     done: bool,
     tool_calls_delta: Option<&Value>,
 ) -> Value {
-    let timestamp = chrono::Utc::now().to_rfc3339();
-
     if is_chat_endpoint {
         let mut message_obj = json!({
             "role": "assistant",
@@ -450,24 +891,38 @@ pub fn create_ollama_streaming_chunk(
 
         json!({
             "model": model_ollama_name,
-            "created_at": timestamp,
+            "created_at": created_at,
             "message": message_obj,
             "done": done
         })
     } else {
         json!({
             "model": model_ollama_name,
-            "created_at": timestamp,
+            "created_at": created_at,
             "response": content,
             "done": done,
-            "context": if done { Some(DEFAULT_CONTEXT.to_vec()) } else { None }
+            "context": if done { Some(generate_response_context()) } else { None }
         })
     }
 }
 
+/// Context returned by /api/generate (stateful context is not supported by LM Studio)
+pub fn generate_response_context() -> Vec<u32> {
+    if get_runtime_config().fake_context {
+        DEFAULT_CONTEXT.to_vec()
+    } else {
+        Vec::new()
+    }
+}
+
 /// Create error chunk for streaming
-pub fn create_error_chunk(model_ollama_name: &str, error_message: &str, is_chat_endpoint: bool) -> Value {
-    let mut chunk = create_ollama_streaming_chunk(model_ollama_name, "", is_chat_endpoint, true, None);
+pub fn create_error_chunk(
+    model_ollama_name: &str,
+    created_at: &str,
+    error_message: &str,
+    is_chat_endpoint: bool,
+) -> Value {
+    let mut chunk = create_ollama_streaming_chunk(model_ollama_name, created_at, "", is_chat_endpoint, true, None);
     if let Some(chunk_obj) = chunk.as_object_mut() {
         chunk_obj.insert("error".to_string(), json!(error_message));
         if is_chat_endpoint {
@@ -482,13 +937,14 @@ pub fn create_error_chunk(model_ollama_name: &str, error_message: &str, is_chat_
 /// Create cancellation chunk with enhanced timing
 pub fn create_cancellation_chunk(
     model_ollama_name: &str,
+    created_at: &str,
     duration: Duration,
     tokens_generated_estimate: u64,
     is_chat_endpoint: bool,
 ) -> Value {
     let timing = TimingInfo::calculate_legacy(Instant::now() - duration, 10, tokens_generated_estimate, None, Some(tokens_generated_estimate));
 
-    let mut chunk = create_ollama_streaming_chunk(model_ollama_name, "", is_chat_endpoint, true, None);
+    let mut chunk = create_ollama_streaming_chunk(model_ollama_name, created_at, "", is_chat_endpoint, true, None);
 
     if let Some(chunk_obj) = chunk.as_object_mut() {
         let content_field_value = if tokens_generated_estimate > 0 {
@@ -516,12 +972,23 @@ pub fn create_cancellation_chunk(
     chunk
 }
 
+/// Map an OpenAI-style finish_reason to an Ollama done_reason
+pub fn map_finish_reason_to_done_reason(finish_reason: &str) -> &str {
+    match finish_reason {
+        "stop" | "eos" | "tool_calls" | "function_call" => "stop",
+        "length" | "max_tokens" => "length",
+        other => other,
+    }
+}
+
 /// Create final completion chunk for streaming with enhanced timing
 pub fn create_final_chunk(
     model_ollama_name: &str,
+    created_at: &str,
     duration: Duration,
     chunk_count_for_token_estimation: u64,
     is_chat_endpoint: bool,
+    finish_reason: Option<&str>,
 ) -> Value {
     let timing = TimingInfo::calculate_legacy(
         Instant::now() - duration,
@@ -531,7 +998,7 @@ pub fn create_final_chunk(
         None,
     );
 
-    let mut chunk = create_ollama_streaming_chunk(model_ollama_name, "", is_chat_endpoint, true, None);
+    let mut chunk = create_ollama_streaming_chunk(model_ollama_name, created_at, "", is_chat_endpoint, true, None);
 
     if let Some(chunk_obj) = chunk.as_object_mut() {
         chunk_obj.insert("total_duration".to_string(), json!(timing.total_duration));
@@ -540,14 +1007,219 @@ pub fn create_final_chunk(
         chunk_obj.insert("prompt_eval_duration".to_string(), json!(timing.prompt_eval_duration));
         chunk_obj.insert("eval_count".to_string(), json!(timing.eval_count));
         chunk_obj.insert("eval_duration".to_string(), json!(timing.eval_duration));
+        chunk_obj.insert(
+            "done_reason".to_string(),
+            json!(finish_reason.map_or("stop", map_finish_reason_to_done_reason)),
+        );
     }
     chunk
 }
 
+/// `system_fingerprint` of an LM Studio response or stream chunk, if it sent one
+pub fn system_fingerprint(lm_value: &Value) -> Option<&str> {
+    lm_value.get("system_fingerprint").and_then(Value::as_str).filter(|f| !f.is_empty())
+}
+
+/// Copy LM Studio's `system_fingerprint` onto a final Ollama response (--include-system-fingerprint)
+pub fn attach_system_fingerprint(ollama_response: &mut Value, fingerprint: Option<&str>) {
+    if !get_runtime_config().include_system_fingerprint {
+        return;
+    }
+    if let (Some(fingerprint), Some(response_obj)) = (fingerprint, ollama_response.as_object_mut()) {
+        response_obj.insert("system_fingerprint".to_string(), json!(fingerprint));
+    }
+}
+
+/// Number of texts in an embeddings input (a string or an array of strings)
+fn embedding_input_count(input: &Value) -> usize {
+    match input {
+        Value::String(s) => usize::from(!s.is_empty()),
+        Value::Array(items) => items.len(),
+        _ => 0,
+    }
+}
+
 /// Estimate token count from text
 fn estimate_token_count(text: &str) -> u64 {
-    if text.is_empty() { return 0; }
-    ((text.len() as f64) * TOKEN_TO_CHAR_RATIO).ceil() as u64
+    estimate_tokens_from_len(text.len())
+}
+
+/// Estimate token count from a text length in bytes
+pub fn estimate_tokens_from_len(len: usize) -> u64 {
+    ((len as f64) * TOKEN_TO_CHAR_RATIO).ceil() as u64
+}
+
+/// Reject `n` > 1 (top-level or in `options`): Ollama responses carry exactly one choice
+pub fn check_single_choice(body: &Value) -> Result<(), crate::utils::ProxyError> {
+    let requested = [body.get("n"), body.get("options").and_then(|o| o.get("n"))]
+        .into_iter()
+        .flatten()
+        .filter_map(|n| n.as_f64())
+        .fold(1.0, f64::max);
+    if requested > 1.0 {
+        return Err(crate::utils::ProxyError::bad_request(ERROR_MULTIPLE_CHOICES));
+    }
+    Ok(())
+}
+
+/// Parse an `X-Request-Timeout-Seconds` value; invalid, zero or over-limit values are ignored with a warning
+pub fn parse_request_timeout(header: Option<&str>, max_seconds: u64) -> Option<u64> {
+    let raw = header?.trim();
+    match raw.parse::<u64>() {
+        Ok(seconds) if seconds > 0 && seconds <= max_seconds => Some(seconds),
+        _ => {
+            crate::utils::log_warning(
+                "Request timeout",
+                &format!("Ignoring {} '{}' (expected 1-{})", HEADER_REQUEST_TIMEOUT, raw, max_seconds),
+            );
+            None
+        }
+    }
+}
+
+/// Draft model for speculative decoding: the request's `options.draft_model`, else --draft-model
+pub fn requested_draft_model<'a>(options: Option<&'a Value>, configured: Option<&'a str>) -> Option<&'a str> {
+    options
+        .and_then(|options| options.get("draft_model"))
+        .and_then(Value::as_str)
+        .or(configured)
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+}
+
+/// Inbound headers named by --forward-header, converted for the LM Studio request
+pub fn forwarded_headers(inbound: &warp::http::HeaderMap, names: &[String]) -> reqwest::header::HeaderMap {
+    let mut forwarded = reqwest::header::HeaderMap::new();
+    for name in names {
+        let Ok(header_name) = reqwest::header::HeaderName::from_bytes(name.trim().as_bytes()) else {
+            continue;
+        };
+        for value in inbound.get_all(header_name.as_str()) {
+            if let Ok(value) = reqwest::header::HeaderValue::from_bytes(value.as_bytes()) {
+                forwarded.append(header_name.clone(), value);
+            }
+        }
+    }
+    forwarded
+}
+
+/// Move OpenAI-style top-level sampling fields (and `max_tokens` as `num_predict`) into `options`;
+/// values already in `options` take precedence
+pub fn merge_top_level_options(body: &mut Value) {
+    let Some(body_obj) = body.as_object_mut() else {
+        return;
+    };
+    let mut top_level: serde_json::Map<String, Value> = TOP_LEVEL_SAMPLING_OPTIONS
+        .iter()
+        .filter_map(|key| body_obj.remove(*key).map(|value| (key.to_string(), value)))
+        .collect();
+    if let Some(max_tokens) = body_obj.remove("max_tokens") {
+        top_level.entry("num_predict").or_insert(max_tokens);
+    }
+    if top_level.is_empty() {
+        return;
+    }
+    let options = body_obj.entry("options").or_insert_with(|| json!({}));
+    if !options.is_object() {
+        *options = json!({});
+    }
+    let Some(options) = options.as_object_mut() else {
+        return;
+    };
+    for (key, value) in top_level {
+        options.entry(key).or_insert(value);
+    }
+}
+
+/// Check numeric sampling options against `SAMPLING_OPTION_RANGES`: out-of-range values are clamped
+/// with a warning, or rejected with 400 when `strict`
+pub fn validate_sampling_options(body: &mut Value, strict: bool) -> Result<(), crate::utils::ProxyError> {
+    let Some(options) = body.get_mut("options").and_then(Value::as_object_mut) else {
+        return Ok(());
+    };
+    for (name, min, max) in SAMPLING_OPTION_RANGES {
+        let Some(value) = options.get(*name).and_then(Value::as_f64) else {
+            continue;
+        };
+        if value >= *min && value <= *max {
+            continue;
+        }
+        let range = if max.is_finite() { format!("[{}, {}]", min, max) } else { format!(">= {}", min) };
+        if strict {
+            return Err(crate::utils::ProxyError::bad_request(&format!(
+                "{}: '{}' is {} (expected {})",
+                ERROR_SAMPLING_OUT_OF_RANGE, name, value, range
+            )));
+        }
+        let clamped = value.clamp(*min, *max);
+        crate::utils::log_warning(
+            "Options",
+            &format!("Clamping '{}' from {} to {} (expected {})", name, value, clamped, range),
+        );
+        // Keep integers (e.g. top_k) integral
+        let clamped = if options[*name].is_i64() { json!(clamped as i64) } else { json!(clamped) };
+        options.insert(name.to_string(), clamped);
+    }
+    Ok(())
+}
+
+/// Apply an `X-Max-Tokens` header: lowers `options.num_predict` when the header is smaller (or none is set),
+/// never raises it. Invalid or zero values are ignored with a warning.
+pub fn apply_max_tokens_header(body: &mut Value, header: Option<&str>) {
+    let Some(raw) = header.map(str::trim) else {
+        return;
+    };
+    let cap = match raw.parse::<u64>() {
+        Ok(cap) if cap > 0 => cap,
+        _ => {
+            crate::utils::log_warning(
+                "Max tokens",
+                &format!("Ignoring {} '{}' (expected a positive integer)", HEADER_MAX_TOKENS, raw),
+            );
+            return;
+        }
+    };
+    let Some(body_obj) = body.as_object_mut() else {
+        return;
+    };
+    let options = body_obj.entry("options").or_insert_with(|| json!({}));
+    if !options.is_object() {
+        *options = json!({});
+    }
+    let Some(options) = options.as_object_mut() else {
+        return;
+    };
+    // Negative num_predict means "no limit" in Ollama
+    let requested = options.get("num_predict").and_then(Value::as_i64).filter(|n| *n >= 0);
+    if requested.is_none_or(|requested| requested as u64 > cap) {
+        options.insert("num_predict".to_string(), json!(cap));
+    }
+}
+
+/// Normalize an inbound /api/chat or /api/generate body before translation: top-level sampling fields,
+/// the `X-Max-Tokens` cap and sampling option ranges
+pub fn prepare_inference_body(
+    body: &mut Value,
+    max_tokens_header: Option<&str>,
+    strict_params: bool,
+) -> Result<(), crate::utils::ProxyError> {
+    merge_top_level_options(body);
+    apply_max_tokens_header(body, max_tokens_header);
+    validate_sampling_options(body, strict_params)
+}
+
+/// Sleep for the simulated latency before contacting LM Studio (only active with --dev-mode)
+pub async fn apply_simulated_latency(
+    config: &crate::server::Config,
+    cancellation_token: &tokio_util::sync::CancellationToken,
+) -> Result<(), crate::utils::ProxyError> {
+    if !config.dev_mode || config.simulate_latency_ms == 0 {
+        return Ok(());
+    }
+    tokio::select! {
+        _ = tokio::time::sleep(Duration::from_millis(config.simulate_latency_ms)) => Ok(()),
+        _ = cancellation_token.cancelled() => Err(crate::utils::ProxyError::request_cancelled()),
+    }
 }
 
 /// Execute request with optional retry logic (dual API support)
@@ -557,6 +1229,7 @@ pub async fn execute_request_with_retry<F, Fut, T>(
     operation: F,
     use_model_retry: bool,
     load_timeout_seconds: u64,
+    load_method: LoadMethod,
     cancellation_token: tokio_util::sync::CancellationToken,
 ) -> Result<T, crate::utils::ProxyError>
 where
@@ -568,6 +1241,7 @@ where
             context,
             model_name_for_retry_logic,
             load_timeout_seconds,
+            load_method,
             operation,
             cancellation_token,
         ).await
@@ -2,15 +2,32 @@
 use serde_json::Value;
 use std::time::Instant;
 use tokio_util::sync::CancellationToken;
+use warp::{Filter, Rejection};
 
-use crate::common::{handle_json_response, CancellableRequest, RequestContext};
+use crate::common::{extract_model_name, handle_json_response, read_upstream_body, CancellableRequest, RequestContext};
 use crate::constants::*;
 use crate::handlers::helpers::json_response;
-use crate::handlers::retry::{with_retry_and_cancellation, with_simple_retry};
+use crate::handlers::retry::{with_retry_and_cancellation, with_simple_retry, LoadMethod};
 use crate::handlers::streaming::{handle_passthrough_streaming_response, is_streaming_request};
-use crate::server::ModelResolverType;
+use crate::server::{redact_url, ModelResolverType};
 use crate::utils::{format_duration, log_request, log_timed, ProxyError};
 
+/// Passthrough request body: no body (or only whitespace) is `Null`, anything else must be valid JSON
+pub fn passthrough_body() -> impl Filter<Extract = (Value,), Error = Rejection> + Clone {
+    warp::body::bytes().and_then(|bytes: warp::hyper::body::Bytes| async move {
+        parse_passthrough_body(&bytes).map_err(warp::reject::custom)
+    })
+}
+
+/// Parse a passthrough body, rejecting a present but malformed body with 400
+pub fn parse_passthrough_body(bytes: &[u8]) -> Result<Value, ProxyError> {
+    if bytes.iter().all(u8::is_ascii_whitespace) {
+        return Ok(Value::Null);
+    }
+    serde_json::from_slice(bytes)
+        .map_err(|e| ProxyError::bad_request(&format!("{}: {}", ERROR_INVALID_JSON_BODY, e)))
+}
+
 /// Handle direct LM Studio API passthrough with model loading detection
 pub async fn handle_lmstudio_passthrough(
     context: RequestContext<'_>,
@@ -23,7 +40,18 @@ pub async fn handle_lmstudio_passthrough(
 ) -> Result<warp::reply::Response, ProxyError> {
     let start_time = Instant::now();
 
-    let original_model_name = body.get("model").and_then(|m| m.as_str());
+    let original_model_name = match body.get("model") {
+        Some(_) => Some(extract_model_name(&body, "model")?),
+        None => None,
+    };
+    let original_model_name = original_model_name.as_deref();
+    // OpenAI-style clients may set LM Studio's own `ttl`; native mode forwards it to the JIT load
+    let load_method = match model_resolver {
+        ModelResolverType::Native(_) => LoadMethod::Native {
+            ttl_seconds: body.get("ttl").and_then(|ttl| ttl.as_u64()),
+        },
+        ModelResolverType::Legacy(_) => LoadMethod::Ping,
+    };
 
     let operation = {
         let context = context.clone();
@@ -44,8 +72,9 @@ pub async fn handle_lmstudio_passthrough(
             let current_original_model_name = original_model_name_clone.clone();
 
             async move {
-                // Resolve model name based on API type
-                if let Some(ref model_name) = current_original_model_name {
+                // Resolve model name based on API type, unless --no-passthrough-model-resolution
+                let resolve_model_names = get_runtime_config().passthrough_model_resolution;
+                if let Some(model_name) = current_original_model_name.as_ref().filter(|_| resolve_model_names) {
                     let resolved_model = match &model_resolver {
                         ModelResolverType::Native(resolver) => {
                             resolver
@@ -74,7 +103,7 @@ pub async fn handle_lmstudio_passthrough(
 
                 // Determine the correct endpoint URL based on API type and requested endpoint
                 let final_endpoint_url = determine_passthrough_endpoint_url(
-                    &context.lmstudio_url,
+                    context.lmstudio_url,
                     &current_endpoint,
                     &model_resolver,
                 );
@@ -150,12 +179,26 @@ pub async fn handle_lmstudio_passthrough(
                     handle_passthrough_streaming_response(
                         response,
                         current_cancellation_token.clone(),
-                        60,
+                        context.stream_timeout_seconds(),
                     )
                         .await
                 } else {
+                    // Keep the upstream 2xx (201, 202, 206, ...) instead of forcing 200
+                    let status = warp::http::StatusCode::from_u16(response.status().as_u16())
+                        .unwrap_or(warp::http::StatusCode::OK);
+                    if status == warp::http::StatusCode::NO_CONTENT || response.content_length() == Some(0) {
+                        let mut reply = warp::reply::Response::new(warp::hyper::Body::empty());
+                        *reply.status_mut() = status;
+                        return Ok(reply);
+                    }
+                    if !has_json_content_type(response.headers()) {
+                        return forward_raw_response(response, status, current_cancellation_token).await;
+                    }
                     let json_data = handle_json_response(response, current_cancellation_token).await?;
-                    Ok(json_response(&json_data))
+                    context.record_backend_runtime(&json_data);
+                    let mut reply = json_response(&json_data);
+                    *reply.status_mut() = status;
+                    Ok(reply)
                 }
             }
         }
@@ -166,6 +209,7 @@ pub async fn handle_lmstudio_passthrough(
             &context,
             model,
             load_timeout_seconds,
+            load_method,
             operation,
             cancellation_token,
         )
@@ -178,6 +222,49 @@ pub async fn handle_lmstudio_passthrough(
     Ok(result)
 }
 
+/// JSON bodies are parsed and re-serialized; a missing Content-Type is treated as JSON
+fn has_json_content_type(headers: &reqwest::header::HeaderMap) -> bool {
+    headers
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| {
+            let mime = value.split(';').next().unwrap_or_default().trim().to_ascii_lowercase();
+            mime == "application/json" || mime.ends_with("+json")
+        })
+        .unwrap_or(true)
+}
+
+/// Forward a non-JSON body (plain text, images, ...) byte for byte with its original Content-Type
+async fn forward_raw_response(
+    response: reqwest::Response,
+    status: warp::http::StatusCode,
+    cancellation_token: CancellationToken,
+) -> Result<warp::reply::Response, ProxyError> {
+    let content_type = response.headers().get(reqwest::header::CONTENT_TYPE).cloned();
+    let bytes = tokio::select! {
+        result = read_upstream_body(response) => result.map_err(|e| {
+            if e.is_timeout() {
+                ProxyError::gateway_timeout(ERROR_REQUEST_TIMEOUT)
+            } else {
+                ProxyError::internal_server_error(&format!("Failed to read LM Studio response: {}", e))
+            }
+        })?,
+        _ = cancellation_token.cancelled() => return Err(ProxyError::request_cancelled()),
+    };
+
+    let mut builder = warp::http::Response::builder()
+        .status(status)
+        .header("Access-Control-Allow-Origin", HEADER_ACCESS_CONTROL_ALLOW_ORIGIN)
+        .header("Access-Control-Allow-Methods", HEADER_ACCESS_CONTROL_ALLOW_METHODS)
+        .header("Access-Control-Allow-Headers", HEADER_ACCESS_CONTROL_ALLOW_HEADERS);
+    if let Some(content_type) = content_type.as_ref().and_then(|v| v.to_str().ok()) {
+        builder = builder.header("Content-Type", content_type);
+    }
+    builder
+        .body(warp::hyper::Body::from(bytes))
+        .map_err(|e| ProxyError::internal_server_error(&format!("Failed to build passthrough response: {}", e)))
+}
+
 /// Determine the correct endpoint URL based on API type and requested path
 fn determine_passthrough_endpoint_url(
     lmstudio_base_url: &str,
@@ -232,37 +319,32 @@ pub async fn get_lmstudio_status(
             let mut additional_info = serde_json::Map::new();
 
             if is_healthy {
-                match response.json::<Value>().await {
-                    Ok(models_response) => {
-                        let model_count = models_response
-                            .get("data")
-                            .and_then(|d| d.as_array())
-                            .map(|arr| arr.len())
-                            .unwrap_or(0);
-                        additional_info.insert("model_count".to_string(), serde_json::json!(model_count));
-
-                        // For native API, include additional metadata
-                        if endpoint.starts_with("/api/v0/") {
-                            if let Some(data) = models_response.get("data").and_then(|d| d.as_array()) {
-                                let loaded_count = data.iter()
-                                    .filter(|model| {
-                                        model.get("state")
-                                            .and_then(|s| s.as_str())
-                                            .map_or(false, |state| state == "loaded")
-                                    })
-                                    .count();
-                                additional_info.insert("loaded_models".to_string(), serde_json::json!(loaded_count));
-                            }
+                if let Ok(models_response) = response.json::<Value>().await {
+                    let model_count = models_response
+                        .get("data")
+                        .and_then(|d| d.as_array())
+                        .map(|arr| arr.len())
+                        .unwrap_or(0);
+                    additional_info.insert("model_count".to_string(), serde_json::json!(model_count));
+
+                    // For native API, include additional metadata
+                    if endpoint.starts_with("/api/v0/") {
+                        if let Some(data) = models_response.get("data").and_then(|d| d.as_array()) {
+                            let loaded_count = data.iter()
+                                .filter(|model| {
+                                    model.get("state").and_then(|s| s.as_str()) == Some("loaded")
+                                })
+                                .count();
+                            additional_info.insert("loaded_models".to_string(), serde_json::json!(loaded_count));
                         }
                     }
-                    Err(_) => {}
                 }
             }
 
             let response_time = health_check_start.elapsed();
             let mut result = serde_json::json!({
                 "status": if is_healthy { "healthy" } else { "unhealthy" },
-                "lmstudio_url": context.lmstudio_url,
+                "lmstudio_url": redact_url(context.lmstudio_url),
                 "http_status": status.as_u16(),
                 "api_endpoint": endpoint,
                 "response_time_ms": response_time.as_millis(),
@@ -290,7 +372,7 @@ pub async fn get_lmstudio_status(
 
             let result = serde_json::json!({
                 "status": status_message,
-                "lmstudio_url": context.lmstudio_url,
+                "lmstudio_url": redact_url(context.lmstudio_url),
                 "error": ERROR_LM_STUDIO_UNAVAILABLE,
                 "api_endpoint": endpoint,
                 "response_time_ms": health_check_start.elapsed().as_millis(),
@@ -344,3 +426,23 @@ pub fn is_endpoint_supported(endpoint: &str, api_type: &ModelResolverType) -> bo
         }
     }
 }
+
+/// Check a /v1 passthrough path against the configured allowlist and denylist
+pub fn is_passthrough_allowed(endpoint: &str, allow: &[String], deny: &[String]) -> bool {
+    let path = endpoint.trim_end_matches('/');
+    if deny.iter().any(|pattern| passthrough_pattern_matches(pattern, path)) {
+        return false;
+    }
+    allow.is_empty() || allow.iter().any(|pattern| passthrough_pattern_matches(pattern, path))
+}
+
+/// Match a passthrough pattern (exact path, or prefix when ending with '*')
+fn passthrough_pattern_matches(pattern: &str, path: &str) -> bool {
+    let pattern = pattern.trim();
+    match pattern.strip_suffix('*') {
+        Some(prefix) => path
+            .get(..prefix.len())
+            .is_some_and(|head| head.eq_ignore_ascii_case(prefix)),
+        None => path.eq_ignore_ascii_case(pattern.trim_end_matches('/')),
+    }
+}
@@ -1,13 +1,15 @@
 /// src/handlers/mod.rs - Module exports for API endpoint handlers with native and legacy support
-
 pub mod retry;
 pub mod streaming;
 pub mod helpers;
 pub mod ollama;
 pub mod lmstudio;
+pub mod debug;
+pub mod status;
 
 // Ollama handler exports with enhanced signatures for dual API support
 pub use ollama::{
+    handle_cancel_request,
     handle_health_check,
     handle_ollama_chat,
     handle_ollama_embeddings,
@@ -16,7 +18,9 @@ pub use ollama::{
     handle_ollama_show,
     handle_ollama_tags,
     handle_ollama_version,
+    handle_proxy_capabilities,
     handle_unsupported,
+    probe_lmstudio_health,
 };
 
 // LM Studio handler exports with dual API support
@@ -25,19 +29,32 @@ pub use lmstudio::{
     get_lmstudio_status,
     handle_lmstudio_passthrough,
     is_endpoint_supported,
+    is_passthrough_allowed,
 };
 
+// Development-only handler exports
+pub use debug::{handle_debug_config, handle_debug_translate};
+
+// Status page exports
+pub use status::{handle_status_page, render_status_page};
+
 // Streaming handler exports
 pub use streaming::{
+    batch_ready_chunks,
+    DeltaCoalescer,
     handle_passthrough_streaming_response,
     handle_streaming_response,
     is_streaming_request,
+    sse_event_data,
+    tool_call_arguments_len,
 };
 
 // Retry handler exports
 pub use retry::{
     calculate_backoff_delay,
     check_lm_studio_availability,
+    LoadMethod,
+    LoadTriggers,
     should_retry_error,
     trigger_model_loading,
     trigger_model_loading_for_ollama,
@@ -48,15 +65,43 @@ pub use retry::{
 
 // Helper exports with enhanced native API support
 pub use helpers::{
+    apply_max_tokens_header,
+    attach_system_fingerprint,
+    apply_response_format,
+    apply_simulated_latency,
     build_lm_studio_request,
+    check_single_choice,
     create_cancellation_chunk,
     create_error_chunk,
     create_final_chunk,
     create_ollama_streaming_chunk,
+    EmbeddingsEndpoint,
+    estimate_tokens_from_len,
     execute_request_with_retry,
     extract_content_from_chunk,
+    forwarded_headers,
+    is_unload_request,
+    load_method_for,
+    note_keep_alive_zero,
+    merge_top_level_options,
+    response_format_for,
+    generate_response_context,
     json_response,
+    map_finish_reason_to_done_reason,
+    normalize_tool_messages,
+    ollama_timestamp,
+    parse_keep_alive,
+    parse_request_timeout,
+    prepare_inference_body,
+    requested_draft_model,
+    strip_think_tags,
+    system_fingerprint,
+    translate_inference_request,
+    validate_sampling_options,
+    InferenceEndpoint,
+    KeepAlive,
     LMStudioRequestType,
     ResponseTransformer,
+    ThinkTagFilter,
     TimingInfo,
 };
@@ -1,26 +1,32 @@
 /// src/handlers/ollama.rs - Ollama API endpoint handlers with native and legacy support
 use serde_json::{json, Value};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::Instant;
 use tokio_util::sync::CancellationToken;
 
-use crate::common::{extract_model_name, handle_json_response, CancellableRequest, RequestContext};
+use crate::common::{check_endpoint_found, extract_model_name, handle_json_response, CancellableRequest, RequestContext};
 use crate::constants::*;
 use crate::handlers::helpers::{
-    build_lm_studio_request, execute_request_with_retry, json_response, LMStudioRequestType,
-    ResponseTransformer,
+    apply_simulated_latency, build_lm_studio_request, check_single_choice, execute_request_with_retry, json_response, is_unload_request,
+    load_method_for, note_keep_alive_zero, ollama_timestamp, requested_draft_model, translate_inference_request, EmbeddingsEndpoint,
+    InferenceEndpoint, LMStudioRequestType, ResponseTransformer,
 };
-use crate::handlers::retry::trigger_model_loading_for_ollama;
+use crate::handlers::retry::{trigger_model_loading_for_ollama, LoadMethod};
+use crate::rate_limit::check_model_rate_limit;
 use crate::handlers::streaming::{handle_streaming_response, is_streaming_request};
+use crate::images::{prepare_request_images, ImageLimits};
 use crate::model::ModelInfo;
 use crate::model_legacy::ModelInfoLegacy;
-use crate::server::{Config, ModelResolverType};
-use crate::utils::{log_error, log_request, log_timed, log_warning, ProxyError};
+use crate::request_registry::RequestRegistry;
+use crate::server::{redact_url, Config, ModelResolverType};
+use crate::utils::{log_error, log_model_resolution, log_request, log_timed, log_warning, ProxyError};
 
-/// Handle GET /api/tags - list available models
+/// Handle GET /api/tags - list available models (only loaded ones when `loaded_only`, native mode)
 pub async fn handle_ollama_tags(
     context: RequestContext<'_>,
     model_resolver: ModelResolverType,
     cancellation_token: CancellationToken,
+    loaded_only: bool,
 ) -> Result<warp::reply::Response, ProxyError> {
     let start_time = Instant::now();
 
@@ -31,7 +37,11 @@ pub async fn handle_ollama_tags(
         async move {
             match model_resolver {
                 ModelResolverType::Native(resolver) => {
-                    let models = resolver.get_all_models(context.client, cancellation_token).await?;
+                    let models = if loaded_only {
+                        resolver.get_loaded_models(context.client, cancellation_token).await?
+                    } else {
+                        resolver.get_all_models(context.client, cancellation_token).await?
+                    };
                     let ollama_models: Vec<Value> = models
                         .iter()
                         .map(|model| model.to_ollama_tags_model())
@@ -77,6 +87,7 @@ pub async fn handle_ollama_tags(
         operation,
         false,
         0,
+        LoadMethod::Ping,
         cancellation_token.clone(),
     )
         .await
@@ -89,11 +100,13 @@ pub async fn handle_ollama_tags(
     Ok(json_response(&result))
 }
 
-/// Handle GET /api/ps - list running models
+/// Handle GET /api/ps - list running models.
+/// With `error_on_unreachable`, a failed model fetch is a 503 instead of an empty list.
 pub async fn handle_ollama_ps(
     context: RequestContext<'_>,
     model_resolver: ModelResolverType,
     cancellation_token: CancellationToken,
+    error_on_unreachable: bool,
 ) -> Result<warp::reply::Response, ProxyError> {
     let start_time = Instant::now();
     log_request("GET", "/api/ps", None);
@@ -143,19 +156,30 @@ pub async fn handle_ollama_ps(
         }
     };
 
-    let result = execute_request_with_retry(
+    let result = match execute_request_with_retry(
         &context,
         "_system_ps_",
         operation,
         false,
         0,
+        LoadMethod::Ping,
         cancellation_token.clone(),
     )
         .await
-        .unwrap_or_else(|e| {
+    {
+        Ok(result) => result,
+        Err(e) if e.is_cancelled() => return Err(e),
+        Err(e) => {
             log_error("PS fetch", &e.message);
+            if error_on_unreachable {
+                return Err(ProxyError::lm_studio_unavailable(&format!(
+                    "{}: {}",
+                    ERROR_LM_STUDIO_UNAVAILABLE, e.message
+                )));
+            }
             json!({ "models": [] })
-        });
+        }
+    };
 
     log_timed(LOG_PREFIX_SUCCESS, "Ollama ps", start_time);
     Ok(json_response(&result))
@@ -163,25 +187,46 @@ pub async fn handle_ollama_ps(
 
 /// Handle POST /api/show - show model info
 pub async fn handle_ollama_show(
+    context: RequestContext<'_>,
     body: Value,
     model_resolver: ModelResolverType,
+    cancellation_token: CancellationToken,
 ) -> Result<warp::reply::Response, ProxyError> {
-    let ollama_model_name = extract_model_name(&body, "model")?;
+    let ollama_model_name = &*extract_model_name(&body, "model")?;
 
     let response = match model_resolver {
-        ModelResolverType::Native(_) => {
-            // For native API, we could fetch real model data, but for simplicity we'll create from name
-            let model_info = ModelInfo::from_native_data(&crate::model::NativeModelData {
-                id: ollama_model_name.to_string(),
-                object: "model".to_string(),
-                model_type: "llm".to_string(),
-                publisher: Some("unknown".to_string()),
-                arch: "unknown".to_string(),
-                compatibility_type: "gguf".to_string(),
-                quantization: "Q4_K_M".to_string(),
-                state: "unknown".to_string(),
-                max_context_length: 4096,
-            });
+        ModelResolverType::Native(resolver) => {
+            // Prefer LM Studio's own metadata; fall back to a placeholder built from the name
+            let model_info = match resolver
+                .find_model_info(ollama_model_name, context.client, cancellation_token.clone())
+                .await
+            {
+                Some(mut model_info) => {
+                    if model_info.chat_template.is_none() {
+                        model_info.chat_template = resolver
+                            .fetch_chat_template(&model_info.id, context.client, cancellation_token.clone())
+                            .await;
+                    }
+                    if model_info.model_type == "embeddings" {
+                        model_info.embedding_length = resolver
+                            .fetch_embedding_length(&model_info.id, context.client, cancellation_token)
+                            .await;
+                    }
+                    model_info
+                }
+                None => ModelInfo::from_native_data(&crate::model::NativeModelData {
+                    id: ollama_model_name.to_string(),
+                    object: "model".to_string(),
+                    model_type: "llm".to_string(),
+                    publisher: Some("unknown".to_string()),
+                    arch: "unknown".to_string(),
+                    compatibility_type: "gguf".to_string(),
+                    quantization: get_runtime_config().default_quantization.clone(),
+                    state: "unknown".to_string(),
+                    max_context_length: 4096,
+                    chat_template: None,
+                }),
+            };
             model_info.to_show_response()
         }
         ModelResolverType::Legacy(_) => {
@@ -193,6 +238,38 @@ pub async fn handle_ollama_show(
     Ok(json_response(&response))
 }
 
+/// Resolve the requested draft model like the main model and set it on the LM Studio request.
+/// An unresolvable draft model is skipped with a warning; speculative decoding is only an optimization.
+async fn apply_draft_model(
+    lm_request: &mut Value,
+    draft_model: Option<&str>,
+    model_resolver: &ModelResolverType,
+    context: &RequestContext<'_>,
+    cancellation_token: CancellationToken,
+) -> Result<(), ProxyError> {
+    let Some(draft_model) = draft_model else {
+        return Ok(());
+    };
+    match model_resolver.resolve_model(draft_model, context.client, cancellation_token).await {
+        Ok(resolved) => {
+            let api_mode = match model_resolver {
+                ModelResolverType::Native(_) => "native",
+                ModelResolverType::Legacy(_) => "legacy",
+            };
+            log_model_resolution("draft_model", draft_model, &resolved, api_mode);
+            if let Some(request_obj) = lm_request.as_object_mut() {
+                request_obj.insert("draft_model".to_string(), json!(resolved.id));
+            }
+            Ok(())
+        }
+        Err(e) if e.is_cancelled() => Err(e),
+        Err(e) => {
+            log_warning("Draft model", &format!("Skipping '{}': {}", draft_model, e.message));
+            Ok(())
+        }
+    }
+}
+
 /// Handle POST /api/chat - chat completion with streaming support
 pub async fn handle_ollama_chat(
     context: RequestContext<'_>,
@@ -202,21 +279,59 @@ pub async fn handle_ollama_chat(
     config: &Config,
 ) -> Result<warp::reply::Response, ProxyError> {
     let start_time = Instant::now();
-    let ollama_model_name = extract_model_name(&body, "model")?;
+    let created_at = ollama_timestamp();
+    let body = prepare_request_images(body, ImageLimits::from_config(config)).await?;
+    let ollama_model_name = &*extract_model_name(&body, "model")?;
 
+    if body.get("messages").is_none() && body.get("prompt").is_some() {
+        return Err(ProxyError::bad_request(ERROR_PROMPT_ON_CHAT));
+    }
     let messages = body
         .get("messages")
         .and_then(|m| m.as_array())
         .ok_or_else(|| ProxyError::bad_request(ERROR_MISSING_MESSAGES))?;
+    if config.max_messages > 0 && messages.len() > config.max_messages {
+        return Err(ProxyError::bad_request(&format!(
+            "{}: {} (limit {})",
+            ERROR_TOO_MANY_MESSAGES,
+            messages.len(),
+            config.max_messages
+        )));
+    }
+    check_single_choice(&body)?;
+    let load_method = load_method_for(&model_resolver, &body);
+
+    note_keep_alive_zero(&body);
+
+    // Empty messages with keep_alive 0 is an unload request; LM Studio's idle TTL does the unloading
+    if messages.is_empty() && is_unload_request(&body) {
+        log_timed(LOG_PREFIX_INFO, &format!("Unload request for {}", ollama_model_name), start_time);
+        let fabricated_response = json!({
+            "model": ollama_model_name,
+            "created_at": created_at,
+            "message": {"role": "assistant", "content": ""},
+            "done_reason": "unload",
+            "done": true
+        });
+        return Ok(json_response(&fabricated_response));
+    }
 
     // Empty messages trigger
     if messages.is_empty() {
+        if config.no_load_hints {
+            return Err(ProxyError::bad_request(ERROR_EMPTY_MESSAGES_NO_LOAD_HINTS));
+        }
         log_timed(LOG_PREFIX_INFO, &format!("Load hint for {}", ollama_model_name), start_time);
-        trigger_model_loading_for_ollama(&context, ollama_model_name, cancellation_token.clone())
+        trigger_model_loading_for_ollama(
+            &context,
+            ollama_model_name,
+            load_method,
+            cancellation_token.clone(),
+        )
             .await?;
         let fabricated_response = json!({
             "model": ollama_model_name,
-            "created_at": chrono::Utc::now().to_rfc3339(),
+            "created_at": created_at,
             "message": {"role": "assistant", "content": ""},
             "done_reason": "load",
             "done": true
@@ -225,57 +340,71 @@ pub async fn handle_ollama_chat(
         return Ok(json_response(&fabricated_response));
     }
 
+    // Retries rebuild the request; rate limits and the shadow backend only see the first attempt
+    let first_attempt = AtomicBool::new(true);
+    let draft_model = requested_draft_model(body.get("options"), config.draft_model.as_deref()).map(str::to_string);
     let operation = || {
         let context = context.clone();
         let model_resolver = model_resolver.clone();
         let body_clone = body.clone();
         let cancellation_token_clone = cancellation_token.clone();
         let ollama_model_name_clone = ollama_model_name.to_string();
+        let created_at = created_at.clone();
+        let first_attempt = &first_attempt;
+        let draft_model = &draft_model;
 
         async move {
-            let current_ollama_model_name = extract_model_name(&body_clone, "model")?;
+            let current_ollama_model_name = &*extract_model_name(&body_clone, "model")?;
             let current_messages = body_clone
                 .get("messages")
                 .and_then(|m| m.as_array())
                 .ok_or_else(|| ProxyError::bad_request(ERROR_MISSING_MESSAGES))?;
             let stream = is_streaming_request(&body_clone);
-            let ollama_options = body_clone.get("options");
-            let ollama_tools = body_clone.get("tools");
+            let legacy_api = matches!(model_resolver, ModelResolverType::Legacy(_));
 
-            let (lm_studio_model_id, endpoint_url) = match &model_resolver {
+            let lm_studio_model_id = match &model_resolver {
                 ModelResolverType::Native(resolver) => {
-                    let model_id = resolver
-                        .resolve_model_name(
+                    let resolved = resolver
+                        .resolve_model(
                             current_ollama_model_name,
                             context.client,
                             cancellation_token_clone.clone(),
                         )
                         .await?;
-                    let url = format!("{}{}", context.lmstudio_url, LM_STUDIO_NATIVE_CHAT);
-                    (model_id, url)
+                    log_model_resolution("/api/chat", current_ollama_model_name, &resolved, "native");
+                    resolved.id
                 }
                 ModelResolverType::Legacy(resolver) => {
-                    let model_id = resolver
-                        .resolve_model_name_legacy(
+                    let resolved = resolver
+                        .resolve_model_legacy(
                             current_ollama_model_name,
                             context.client,
                             cancellation_token_clone.clone(),
                         )
                         .await?;
-                    let url = format!("{}{}", context.lmstudio_url, LM_STUDIO_LEGACY_CHAT);
-                    (model_id, url)
+                    log_model_resolution("/api/chat", current_ollama_model_name, &resolved, "legacy");
+                    resolved.id
                 }
             };
 
-            let lm_request = build_lm_studio_request(
-                &lm_studio_model_id,
-                LMStudioRequestType::Chat {
-                    messages: &json!(current_messages),
-                    stream,
-                },
-                ollama_options,
-                ollama_tools,
-            );
+            let (lm_studio_endpoint, mut lm_request) =
+                translate_inference_request(InferenceEndpoint::Chat, &body_clone, &lm_studio_model_id, legacy_api)?;
+            let endpoint_url = format!("{}{}", context.lmstudio_url, lm_studio_endpoint);
+            apply_draft_model(
+                &mut lm_request,
+                draft_model.as_deref(),
+                &model_resolver,
+                &context,
+                cancellation_token_clone.clone(),
+            )
+                .await?;
+
+            if first_attempt.swap(false, Ordering::Relaxed) {
+                check_model_rate_limit(current_ollama_model_name, &lm_studio_model_id)?;
+                if let (Some(shadow), Some(path)) = (context.shadow, endpoint_url.strip_prefix(context.lmstudio_url)) {
+                    shadow.mirror_request(path, &lm_request);
+                }
+            }
 
             let request_obj = CancellableRequest::new(context.clone(), cancellation_token_clone.clone());
             log_request("POST", &endpoint_url, Some(&lm_studio_model_id));
@@ -283,19 +412,23 @@ pub async fn handle_ollama_chat(
             let response = request_obj
                 .make_request(reqwest::Method::POST, &endpoint_url, Some(lm_request))
                 .await?;
+            let response = check_endpoint_found(response, legacy_api).await?;
 
             if stream {
                 handle_streaming_response(
                     response,
                     true,
                     &ollama_model_name_clone,
+                    &created_at,
                     start_time,
                     cancellation_token_clone.clone(),
-                    60,
+                    context.stream_timeout_seconds(),
+                    context.backend_runtime.clone(),
                 )
                     .await
             } else {
                 let lm_response_value = handle_json_response(response, cancellation_token_clone).await?;
+                context.record_backend_runtime(&lm_response_value);
                 let ollama_response = ResponseTransformer::convert_to_ollama_chat(
                     &lm_response_value,
                     &ollama_model_name_clone,
@@ -308,12 +441,15 @@ pub async fn handle_ollama_chat(
         }
     };
 
+    apply_simulated_latency(config, &cancellation_token).await?;
+
     let result = execute_request_with_retry(
         &context,
         ollama_model_name,
         operation,
         true,
         config.load_timeout_seconds,
+        load_method,
         cancellation_token.clone(),
     )
         .await?;
@@ -331,24 +467,54 @@ pub async fn handle_ollama_generate(
     config: &Config,
 ) -> Result<warp::reply::Response, ProxyError> {
     let start_time = Instant::now();
-    let ollama_model_name = extract_model_name(&body, "model")?;
+    let created_at = ollama_timestamp();
+    let body = prepare_request_images(body, ImageLimits::from_config(config)).await?;
+    let ollama_model_name = &*extract_model_name(&body, "model")?;
 
+    if body.get("prompt").is_none() && body.get("messages").is_some() {
+        return Err(ProxyError::bad_request(ERROR_MESSAGES_ON_GENERATE));
+    }
+    check_single_choice(&body)?;
+    let load_method = load_method_for(&model_resolver, &body);
     let prompt = body
         .get("prompt")
         .and_then(|p| p.as_str())
         .ok_or_else(|| ProxyError::bad_request(ERROR_MISSING_PROMPT))?;
     let images = body.get("images");
 
+    let no_images = images.is_none_or(|i| i.as_array().is_none_or(|a| a.is_empty()));
+
+    note_keep_alive_zero(&body);
+
+    // Empty prompt with keep_alive 0 is an unload request; LM Studio's idle TTL does the unloading
+    if prompt.is_empty() && no_images && is_unload_request(&body) {
+        log_timed(LOG_PREFIX_INFO, &format!("Unload request for {}", ollama_model_name), start_time);
+        let fabricated_response = json!({
+            "model": ollama_model_name,
+            "created_at": created_at,
+            "response": "",
+            "done_reason": "unload",
+            "done": true
+        });
+        return Ok(json_response(&fabricated_response));
+    }
+
     // Empty prompt trigger
-    if prompt.is_empty()
-        && images.map_or(true, |i| i.as_array().map_or(true, |a| a.is_empty()))
-    {
+    if prompt.is_empty() && no_images {
+        if config.no_load_hints {
+            return Err(ProxyError::bad_request(ERROR_EMPTY_PROMPT_NO_LOAD_HINTS));
+        }
         log_timed(LOG_PREFIX_INFO, &format!("Load hint for {}", ollama_model_name), start_time);
-        trigger_model_loading_for_ollama(&context, ollama_model_name, cancellation_token.clone())
+        trigger_model_loading_for_ollama(
+            &context,
+            ollama_model_name,
+            load_method,
+            cancellation_token.clone(),
+        )
             .await?;
         let fabricated_response = json!({
             "model": ollama_model_name,
-            "created_at": chrono::Utc::now().to_rfc3339(),
+            "created_at": created_at,
             "response": "",
             "done": true
         });
@@ -356,83 +522,68 @@ pub async fn handle_ollama_generate(
         return Ok(json_response(&fabricated_response));
     }
 
+    // Retries rebuild the request; rate limits only see the first attempt
+    let first_attempt = AtomicBool::new(true);
+    let draft_model = requested_draft_model(body.get("options"), config.draft_model.as_deref()).map(str::to_string);
     let operation = || {
         let context = context.clone();
         let model_resolver = model_resolver.clone();
         let body_clone = body.clone();
         let cancellation_token_clone = cancellation_token.clone();
         let ollama_model_name_clone = ollama_model_name.to_string();
+        let created_at = created_at.clone();
+        let first_attempt = &first_attempt;
+        let draft_model = &draft_model;
 
         async move {
-            let current_ollama_model_name = extract_model_name(&body_clone, "model")?;
+            let current_ollama_model_name = &*extract_model_name(&body_clone, "model")?;
             let current_prompt = body_clone
                 .get("prompt")
                 .and_then(|p| p.as_str())
                 .ok_or_else(|| ProxyError::bad_request(ERROR_MISSING_PROMPT))?;
-            let current_images = body_clone.get("images");
             let stream = is_streaming_request(&body_clone);
-            let ollama_options = body_clone.get("options");
+            let legacy_api = matches!(model_resolver, ModelResolverType::Legacy(_));
 
-            let (lm_studio_model_id, endpoint_url_base) = match &model_resolver {
+            let lm_studio_model_id = match &model_resolver {
                 ModelResolverType::Native(resolver) => {
-                    let model_id = resolver
-                        .resolve_model_name(
+                    let resolved = resolver
+                        .resolve_model(
                             current_ollama_model_name,
                             context.client,
                             cancellation_token_clone.clone(),
                         )
                         .await?;
-                    (model_id, context.lmstudio_url.to_string())
+                    log_model_resolution("/api/generate", current_ollama_model_name, &resolved, "native");
+                    resolved.id
                 }
                 ModelResolverType::Legacy(resolver) => {
-                    let model_id = resolver
-                        .resolve_model_name_legacy(
+                    let resolved = resolver
+                        .resolve_model_legacy(
                             current_ollama_model_name,
                             context.client,
                             cancellation_token_clone.clone(),
                         )
                         .await?;
-                    (model_id, context.lmstudio_url.to_string())
+                    log_model_resolution("/api/generate", current_ollama_model_name, &resolved, "legacy");
+                    resolved.id
                 }
             };
 
-            // Determine endpoint based on API type and whether images are present
-            let (lm_studio_target_url, lm_request_type) = if current_images.is_some()
-                && current_images.unwrap().as_array().map_or(false, |a| !a.is_empty())
-            {
-                let chat_endpoint = match &model_resolver {
-                    ModelResolverType::Native(_) => LM_STUDIO_NATIVE_CHAT,
-                    ModelResolverType::Legacy(_) => LM_STUDIO_LEGACY_CHAT,
-                };
-                (
-                    format!("{}{}", endpoint_url_base, chat_endpoint),
-                    LMStudioRequestType::Completion {
-                        prompt: current_prompt,
-                        stream,
-                        images: current_images,
-                    },
-                )
-            } else {
-                let completions_endpoint = match &model_resolver {
-                    ModelResolverType::Native(_) => LM_STUDIO_NATIVE_COMPLETIONS,
-                    ModelResolverType::Legacy(_) => LM_STUDIO_LEGACY_COMPLETIONS,
-                };
-                (
-                    format!("{}{}", endpoint_url_base, completions_endpoint),
-                    LMStudioRequestType::Completion {
-                        prompt: current_prompt,
-                        stream,
-                        images: None,
-                    },
-                )
-            };
+            if first_attempt.swap(false, Ordering::Relaxed) {
+                check_model_rate_limit(current_ollama_model_name, &lm_studio_model_id)?;
+            }
 
-            let lm_request = build_lm_studio_request(
-                &lm_studio_model_id,
-                lm_request_type,
-                ollama_options,
-                None,
-            );
+            let (lm_studio_endpoint, mut lm_request) =
+                translate_inference_request(InferenceEndpoint::Generate, &body_clone, &lm_studio_model_id, legacy_api)?;
+            let lm_studio_target_url = format!("{}{}", context.lmstudio_url, lm_studio_endpoint);
+            apply_draft_model(
+                &mut lm_request,
+                draft_model.as_deref(),
+                &model_resolver,
+                &context,
+                cancellation_token_clone.clone(),
+            )
+                .await?;
 
             let request_obj = CancellableRequest::new(context.clone(), cancellation_token_clone.clone());
             log_request("POST", &lm_studio_target_url, Some(&lm_studio_model_id));
@@ -440,19 +591,23 @@ pub async fn handle_ollama_generate(
             let response = request_obj
                 .make_request(reqwest::Method::POST, &lm_studio_target_url, Some(lm_request))
                 .await?;
+            let response = check_endpoint_found(response, legacy_api).await?;
 
             if stream {
                 handle_streaming_response(
                     response,
                     false,
                     &ollama_model_name_clone,
+                    &created_at,
                     start_time,
                     cancellation_token_clone.clone(),
-                    60,
+                    context.stream_timeout_seconds(),
+                    context.backend_runtime.clone(),
                 )
                     .await
             } else {
                 let lm_response_value = handle_json_response(response, cancellation_token_clone).await?;
+                context.record_backend_runtime(&lm_response_value);
                 let ollama_response = ResponseTransformer::convert_to_ollama_generate(
                     &lm_response_value,
                     &ollama_model_name_clone,
@@ -465,12 +620,15 @@ pub async fn handle_ollama_generate(
         }
     };
 
+    apply_simulated_latency(config, &cancellation_token).await?;
+
     let result = execute_request_with_retry(
         &context,
         ollama_model_name,
         operation,
         true,
         config.load_timeout_seconds,
+        load_method,
         cancellation_token.clone(),
     )
         .await?;
@@ -484,12 +642,18 @@ pub async fn handle_ollama_embeddings(
     context: RequestContext<'_>,
     model_resolver: ModelResolverType,
     body: Value,
+    endpoint: EmbeddingsEndpoint,
     cancellation_token: CancellationToken,
     config: &Config,
 ) -> Result<warp::reply::Response, ProxyError> {
     let start_time = Instant::now();
-    let ollama_model_name = extract_model_name(&body, "model")?;
+    let ollama_model_name = &*extract_model_name(&body, "model")?;
+    // A streaming client would wait for NDJSON that never comes
+    if is_streaming_request(&body) {
+        return Err(ProxyError::bad_request(ERROR_EMBEDDINGS_STREAM));
+    }
 
+    let load_method = load_method_for(&model_resolver, &body);
     let operation = || {
         let context = context.clone();
         let model_resolver = model_resolver.clone();
@@ -498,7 +662,7 @@ pub async fn handle_ollama_embeddings(
         let ollama_model_name_clone = ollama_model_name.to_string();
 
         async move {
-            let current_ollama_model_name = extract_model_name(&body_clone, "model")?;
+            let current_ollama_model_name = &*extract_model_name(&body_clone, "model")?;
             let input_value = body_clone
                 .get("input")
                 .or_else(|| body_clone.get("prompt"))
@@ -507,24 +671,28 @@ pub async fn handle_ollama_embeddings(
 
             let (lm_studio_model_id, endpoint_url) = match &model_resolver {
                 ModelResolverType::Native(resolver) => {
-                    let model_id = resolver
-                        .resolve_model_name(
+                    let resolved = resolver
+                        .resolve_model(
                             current_ollama_model_name,
                             context.client,
                             cancellation_token_clone.clone(),
                         )
                         .await?;
+                    log_model_resolution(endpoint.path(), current_ollama_model_name, &resolved, "native");
+                    let model_id = resolved.id;
                     let url = format!("{}{}", context.lmstudio_url, LM_STUDIO_NATIVE_EMBEDDINGS);
                     (model_id, url)
                 }
                 ModelResolverType::Legacy(resolver) => {
-                    let model_id = resolver
-                        .resolve_model_name_legacy(
+                    let resolved = resolver
+                        .resolve_model_legacy(
                             current_ollama_model_name,
                             context.client,
                             cancellation_token_clone.clone(),
                         )
                         .await?;
+                    log_model_resolution(endpoint.path(), current_ollama_model_name, &resolved, "legacy");
+                    let model_id = resolved.id;
                     let url = format!("{}{}", context.lmstudio_url, LM_STUDIO_LEGACY_EMBEDDINGS);
                     (model_id, url)
                 }
@@ -537,6 +705,7 @@ pub async fn handle_ollama_embeddings(
                 },
                 None,
                 None,
+                matches!(model_resolver, ModelResolverType::Legacy(_)),
             );
 
             let request_obj = CancellableRequest::new(context.clone(), cancellation_token_clone.clone());
@@ -545,24 +714,31 @@ pub async fn handle_ollama_embeddings(
             let response = request_obj
                 .make_request(reqwest::Method::POST, &endpoint_url, Some(lm_request))
                 .await?;
+            let response =
+                check_endpoint_found(response, matches!(model_resolver, ModelResolverType::Legacy(_))).await?;
             let lm_response_value = handle_json_response(response, cancellation_token_clone).await?;
+            context.record_backend_runtime(&lm_response_value);
 
             let ollama_response = ResponseTransformer::convert_to_ollama_embeddings(
                 &lm_response_value,
                 &ollama_model_name_clone,
+                &input_value,
                 start_time,
                 matches!(model_resolver, ModelResolverType::Native(_)),
-            );
-            Ok(json_response(&ollama_response))
+            )?;
+            Ok(json_response(&endpoint.shape_response(ollama_response, &input_value)))
         }
     };
 
+    apply_simulated_latency(config, &cancellation_token).await?;
+
     let result = execute_request_with_retry(
         &context,
         ollama_model_name,
         operation,
         true,
         config.load_timeout_seconds,
+        load_method,
         cancellation_token.clone(),
     )
         .await?;
@@ -571,15 +747,52 @@ pub async fn handle_ollama_embeddings(
     Ok(result)
 }
 
-/// Handle GET /api/version - return version info
-pub async fn handle_ollama_version() -> Result<warp::reply::Response, ProxyError> {
+/// Handle GET /api/version - return Ollama-compatible version info
+pub async fn handle_ollama_version(reported_version: &str) -> Result<warp::reply::Response, ProxyError> {
     let response = json!({
-        "version": crate::VERSION,
+        "version": reported_version,
+        "proxy_version": crate::VERSION,
         "proxy_backend": "lmstudio"
     });
     Ok(json_response(&response))
 }
 
+/// Handle DELETE /api/requests/{id} - cancel a running request sent with `X-Request-Id: {id}`
+pub fn handle_cancel_request(requests: &RequestRegistry, request_id: &str) -> Result<warp::reply::Response, ProxyError> {
+    if !requests.cancel(request_id) {
+        return Err(ProxyError::not_found(&format!("No active request with id '{}'", request_id)));
+    }
+    log_warning("Cancel", &format!("Request '{}' cancelled by client", request_id));
+    Ok(json_response(&json!({ "id": request_id, "cancelled": true })))
+}
+
+/// Handle GET /api/capabilities - proxy-specific report of option mapping and API mode features
+pub async fn handle_proxy_capabilities(use_legacy: bool) -> Result<warp::reply::Response, ProxyError> {
+    let (mode, description) = crate::get_api_mode_info(use_legacy);
+    let mapped_options: serde_json::Map<String, Value> = OLLAMA_DIRECT_OPTIONS
+        .iter()
+        .map(|option| (option.to_string(), json!(option)))
+        .chain(
+            OLLAMA_RENAMED_OPTIONS
+                .iter()
+                .map(|(option, target)| (option.to_string(), json!(target))),
+        )
+        .collect();
+
+    let response = json!({
+        "api_mode": mode,
+        "api_description": description,
+        "lm_studio_requirements": crate::get_lm_studio_requirements(use_legacy),
+        "options": {
+            "mapped": mapped_options,
+            "ignored": OLLAMA_IGNORED_OPTIONS
+        },
+        "features": crate::ApiFeatureComparison::new().to_json(),
+        "proxy_version": crate::VERSION
+    });
+    Ok(json_response(&response))
+}
+
 /// Handle unsupported endpoints with helpful messages
 pub async fn handle_unsupported(endpoint: &str) -> Result<warp::reply::Response, ProxyError> {
     let (message, suggestion) = match endpoint {
@@ -615,16 +828,37 @@ pub async fn handle_unsupported(endpoint: &str) -> Result<warp::reply::Response,
     )))
 }
 
-/// Handle health check that tests actual model availability
+/// Handle GET /health - probe LM Studio and report its status
 pub async fn handle_health_check(
     context: RequestContext<'_>,
+    native: bool,
+    cancellation_token: CancellationToken,
+) -> Result<Value, ProxyError> {
+    let start_time = Instant::now();
+    let report = probe_lmstudio_health(context, native, cancellation_token).await?;
+    let status = report.get("status").and_then(|s| s.as_str()).unwrap_or("unknown");
+    if status == "healthy" {
+        let model_count = report.get("models_known_to_lmstudio").and_then(|c| c.as_u64()).unwrap_or(0);
+        log_timed(LOG_PREFIX_SUCCESS, &format!("Health check - {} models", model_count), start_time);
+    } else {
+        log_timed(LOG_PREFIX_ERROR, &format!("Health check - {}", status), start_time);
+    }
+    Ok(report)
+}
+
+/// Probe LM Studio once and build the /health report (no logging).
+/// In native mode a healthy report also gets `backend_info` when LM Studio provides any.
+pub async fn probe_lmstudio_health(
+    context: RequestContext<'_>,
+    native: bool,
     cancellation_token: CancellationToken,
 ) -> Result<Value, ProxyError> {
     let start_time = Instant::now();
     let url = format!("{}/v1/models", context.lmstudio_url);
-    let request = CancellableRequest::new(context.clone(), cancellation_token.clone());
+    // Always probe the configured URL so a fallback never masks the primary's state
+    let request = CancellableRequest::new(context.clone(), cancellation_token.clone()).without_failover();
 
-    match request
+    let mut report = match request
         .make_request(reqwest::Method::GET, &url, None::<Value>)
         .await
     {
@@ -634,27 +868,18 @@ pub async fn handle_health_check(
             let mut model_count = 0;
 
             if is_healthy {
-                match response.json::<Value>().await {
-                    Ok(models_response) => {
-                        model_count = models_response
-                            .get("data")
-                            .and_then(|d| d.as_array())
-                            .map(|arr| arr.len())
-                            .unwrap_or(0);
-                    }
-                    Err(_) => {}
+                if let Ok(models_response) = response.json::<Value>().await {
+                    model_count = models_response
+                        .get("data")
+                        .and_then(|d| d.as_array())
+                        .map(|arr| arr.len())
+                        .unwrap_or(0);
                 }
             }
 
-            log_timed(
-                if is_healthy { LOG_PREFIX_SUCCESS } else { LOG_PREFIX_ERROR },
-                &format!("Health check - {} models", model_count),
-                start_time
-            );
-
             Ok(json!({
                 "status": if is_healthy { "healthy" } else { "unhealthy" },
-                "lmstudio_url": context.lmstudio_url,
+                "lmstudio_url": redact_url(context.lmstudio_url),
                 "http_status": status.as_u16(),
                 "models_known_to_lmstudio": model_count,
                 "response_time_ms": start_time.elapsed().as_millis(),
@@ -664,10 +889,9 @@ pub async fn handle_health_check(
         }
         Err(e) if e.is_cancelled() => Err(ProxyError::request_cancelled()),
         Err(e) => {
-            log_timed(LOG_PREFIX_ERROR, &format!("Health check failed: {}", e.message), start_time);
             Ok(json!({
                 "status": "unreachable",
-                "lmstudio_url": context.lmstudio_url,
+                "lmstudio_url": redact_url(context.lmstudio_url),
                 "error_message": e.message,
                 "error_details": ERROR_LM_STUDIO_UNAVAILABLE,
                 "response_time_ms": start_time.elapsed().as_millis(),
@@ -675,5 +899,40 @@ pub async fn handle_health_check(
                 "proxy_version": crate::VERSION
             }))
         }
+    }?;
+
+    if native && report.get("status").and_then(Value::as_str) == Some("healthy") {
+        if let Some(backend_info) = probe_backend_info(&context, cancellation_token).await {
+            report["backend_info"] = backend_info;
+        }
+    }
+    if let Some(failover) = context.failover {
+        report["active_backend"] = json!(redact_url(failover.active_backend()));
+    }
+    Ok(report)
+}
+
+/// Backend details from the native API: loaded model count, plus the engine (`runtime`) seen on the
+/// latest native response. LM Studio's REST API does not expose GPU, VRAM or app version.
+async fn probe_backend_info(context: &RequestContext<'_>, cancellation_token: CancellationToken) -> Option<Value> {
+    let url = format!("{}{}", context.lmstudio_url, LM_STUDIO_NATIVE_MODELS);
+    let response = CancellableRequest::new(context.clone(), cancellation_token)
+        .without_failover()
+        .make_request(reqwest::Method::GET, &url, None::<Value>)
+        .await
+        .ok()
+        .filter(|response| response.status().is_success())?;
+    let models = response.json::<Value>().await.ok()?;
+    let loaded_models = models
+        .get("data")?
+        .as_array()?
+        .iter()
+        .filter(|model| model.get("state").and_then(Value::as_str) == Some("loaded"))
+        .count();
+
+    let mut backend_info = json!({ "loaded_models": loaded_models });
+    if let Some(runtime) = context.backend_runtime.as_ref().and_then(|backend_runtime| backend_runtime.get()) {
+        backend_info["runtime"] = runtime;
     }
+    Some(backend_info)
 }
@@ -1,14 +1,19 @@
 /// src/handlers/retry.rs - Enhanced retry logic with model loading detection and timing
+use dashmap::DashMap;
 use serde::Serialize;
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::time::sleep;
 use tokio_util::sync::CancellationToken;
 
 use crate::check_cancelled;
 use crate::common::{CancellableRequest, RequestContext};
-use crate::constants::ERROR_LM_STUDIO_UNAVAILABLE;
+use crate::constants::{
+    get_runtime_config, ERROR_LM_STUDIO_UNAVAILABLE, ERROR_NO_MODELS_AVAILABLE, LM_STUDIO_LEGACY_CHAT,
+    LM_STUDIO_NATIVE_CHAT, LOAD_TRIGGER_RETRY_BASE_DELAY_MS,
+};
 use crate::model_legacy::clean_model_name_legacy;
-use crate::utils::{is_model_loading_error, log_error, log_timed, log_warning, ProxyError};
+use crate::utils::{format_duration, is_model_loading_error, log_error, log_timed, log_warning, ProxyError};
 
 #[derive(Serialize)]
 struct MinimalChatMessage<'a> {
@@ -22,18 +27,173 @@ struct MinimalChatRequestPayload<'a> {
     messages: Vec<MinimalChatMessage<'a>>,
     max_tokens: u32,
     stream: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    ttl: Option<u64>,
 }
 
-/// Trigger model loading via minimal request
+/// How the proxy asks LM Studio to load a model
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LoadMethod {
+    /// Minimal chat ping on the OpenAI-compatible endpoint (legacy mode)
+    #[default]
+    Ping,
+    /// JIT load through the native endpoint; LM Studio unloads the model after `ttl_seconds` idle
+    /// (`None` keeps LM Studio's own JIT default)
+    Native { ttl_seconds: Option<u64> },
+}
+
+/// Outcome of the most recent load trigger for a model (finish time, result)
+type TriggerSlot = Arc<tokio::sync::Mutex<Option<(Instant, Result<bool, ProxyError>)>>>;
+
+/// Single-flight load triggers: one slot per model while a trigger for it is running or awaited,
+/// so concurrent cold-start requests share a single trigger
+#[derive(Debug, Default)]
+pub struct LoadTriggers {
+    slots: DashMap<String, TriggerSlot>,
+}
+
+impl LoadTriggers {
+    /// Create an empty set of trigger slots
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Models with a trigger running or awaited
+    pub fn len(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// Whether no trigger is running or awaited
+    pub fn is_empty(&self) -> bool {
+        self.slots.is_empty()
+    }
+}
+
+/// A request's hold on a model's trigger slot; the last holder removes the slot from the map
+struct SlotHold<'a> {
+    triggers: &'a LoadTriggers,
+    key: String,
+    slot: TriggerSlot,
+}
+
+impl Drop for SlotHold<'_> {
+    fn drop(&mut self) {
+        // Only the map and this hold reference the slot: nobody is waiting on it. The map's shard lock is held
+        // during the check, so no new holder can appear before the removal.
+        self.triggers.slots.remove_if(&self.key, |_, slot| Arc::strong_count(slot) == 2);
+    }
+}
+
+/// What a single load trigger request told us
+enum TriggerOutcome {
+    /// LM Studio took the request (or rejected it for a reason unrelated to the model)
+    Triggered,
+    /// Failed in a way that may clear up (5xx, broken connection); the model might still be loading
+    Failed(String),
+    /// LM Studio does not have the model
+    ModelNotFound(String),
+}
+
+/// Trigger model loading via minimal request (single-flight per model through `context.load_triggers`)
 pub async fn trigger_model_loading(
     context: &RequestContext<'_>,
     ollama_model_name: &str,
+    load_method: LoadMethod,
     cancellation_token: CancellationToken,
 ) -> Result<bool, ProxyError> {
+    let Some(triggers) = context.load_triggers else {
+        return run_load_trigger(context, ollama_model_name, load_method, cancellation_token).await;
+    };
+    let key = clean_model_name_legacy(ollama_model_name).to_lowercase();
+    let slot = triggers.slots.entry(key.clone()).or_default().clone();
+    let hold = SlotHold { triggers, key, slot };
+
+    let requested_at = Instant::now();
+    let mut last_trigger = tokio::select! {
+        guard = hold.slot.lock() => guard,
+        _ = cancellation_token.cancelled() => return Err(ProxyError::request_cancelled()),
+    };
+
+    // A trigger that finished while we waited already did the work
+    if let Some((finished_at, result)) = last_trigger.as_ref() {
+        if *finished_at >= requested_at {
+            return result.clone();
+        }
+    }
+
+    let result = run_load_trigger(context, ollama_model_name, load_method, cancellation_token).await;
+    if !result.as_ref().is_err_and(|e| e.is_cancelled()) {
+        *last_trigger = Some((Instant::now(), result.clone()));
+    }
+    result
+}
+
+/// Send the load trigger. By default any outcome but a transient failure counts as success and the caller
+/// proceeds; with --load-trigger-retries, transient failures are retried and then, like a missing model,
+/// end in a "could not load model" error.
+async fn run_load_trigger(
+    context: &RequestContext<'_>,
+    ollama_model_name: &str,
+    load_method: LoadMethod,
+    cancellation_token: CancellationToken,
+) -> Result<bool, ProxyError> {
+    let Some(max_retries) = get_runtime_config().load_trigger_retries else {
+        let outcome = send_load_trigger(context, ollama_model_name, load_method, cancellation_token).await?;
+        return Ok(!matches!(outcome, TriggerOutcome::Failed(_)));
+    };
+
+    let mut attempt = 0;
+    loop {
+        match send_load_trigger(context, ollama_model_name, load_method, cancellation_token.clone()).await? {
+            TriggerOutcome::Triggered => return Ok(true),
+            TriggerOutcome::ModelNotFound(reason) => {
+                return Err(ProxyError::not_found(&format!(
+                    "Could not load model '{}': {}",
+                    ollama_model_name, reason
+                )));
+            }
+            TriggerOutcome::Failed(reason) if attempt >= max_retries => {
+                return Err(ProxyError::new(
+                    format!(
+                        "Could not load model '{}' after {} attempt(s): {}",
+                        ollama_model_name,
+                        attempt + 1,
+                        reason
+                    ),
+                    503,
+                ));
+            }
+            TriggerOutcome::Failed(reason) => {
+                let delay = calculate_backoff_delay(attempt, LOAD_TRIGGER_RETRY_BASE_DELAY_MS);
+                log_warning(
+                    "Model trigger",
+                    &format!("{} ({}), retrying in {}", reason, ollama_model_name, format_duration(delay)),
+                );
+                tokio::select! {
+                    _ = sleep(delay) => {}
+                    _ = cancellation_token.cancelled() => return Err(ProxyError::request_cancelled()),
+                }
+                attempt += 1;
+            }
+        }
+    }
+}
+
+/// Send the minimal request that makes LM Studio load a model
+async fn send_load_trigger(
+    context: &RequestContext<'_>,
+    ollama_model_name: &str,
+    load_method: LoadMethod,
+    cancellation_token: CancellationToken,
+) -> Result<TriggerOutcome, ProxyError> {
     let cleaned_ollama_model_for_logging = clean_model_name_legacy(ollama_model_name);
     let model_for_lm_studio_trigger = cleaned_ollama_model_for_logging;
 
-    let url = format!("{}/v1/chat/completions", context.lmstudio_url);
+    let (endpoint, ttl) = match load_method {
+        LoadMethod::Ping => (LM_STUDIO_LEGACY_CHAT, None),
+        LoadMethod::Native { ttl_seconds } => (LM_STUDIO_NATIVE_CHAT, ttl_seconds),
+    };
+    let url = format!("{}{}", context.lmstudio_url, endpoint);
     let minimal_request_body = MinimalChatRequestPayload {
         model: model_for_lm_studio_trigger,
         messages: vec![MinimalChatMessage {
@@ -42,6 +202,7 @@ pub async fn trigger_model_loading(
         }],
         max_tokens: 1,
         stream: false,
+        ttl,
     };
 
     let request = CancellableRequest::new(context.clone(), cancellation_token.clone());
@@ -56,12 +217,19 @@ pub async fn trigger_model_loading(
     {
         Ok(response) => {
             let status = response.status();
-            let trigger_considered_successful = status.is_success() || status.is_client_error();
-
-            if !trigger_considered_successful {
+            if status.is_success() {
+                return Ok(TriggerOutcome::Triggered);
+            }
+            if !status.is_client_error() {
                 log_warning("Model trigger", &format!("Status: {}", status));
+                return Ok(TriggerOutcome::Failed(format!("LM Studio returned {}", status)));
+            }
+            let body = response.text().await.unwrap_or_default();
+            if status == reqwest::StatusCode::NOT_FOUND || is_model_missing_error(&body) {
+                Ok(TriggerOutcome::ModelNotFound(format!("not found in LM Studio ({})", status)))
+            } else {
+                Ok(TriggerOutcome::Triggered)
             }
-            Ok(trigger_considered_successful)
         }
         Err(e) if e.is_cancelled() => Err(ProxyError::request_cancelled()),
         Err(e) if e.is_lm_studio_unavailable() => {
@@ -71,18 +239,27 @@ pub async fn trigger_model_loading(
         }
         Err(e) => {
             log_error("Model trigger", &e.message);
-            Ok(false)
+            Ok(TriggerOutcome::Failed(e.message))
         }
     }
 }
 
+/// A 4xx body saying the model does not exist (as opposed to being busy or still loading)
+fn is_model_missing_error(body: &str) -> bool {
+    let lower = body.to_lowercase();
+    ["model not found", "not found", "does not exist", "unknown model", "invalid model"]
+        .iter()
+        .any(|pattern| lower.contains(pattern))
+}
+
 /// Trigger model loading for Ollama load hints
 pub async fn trigger_model_loading_for_ollama(
     context: &RequestContext<'_>,
     ollama_model_name: &str,
+    load_method: LoadMethod,
     cancellation_token: CancellationToken,
 ) -> Result<(), ProxyError> {
-    match trigger_model_loading(context, ollama_model_name, cancellation_token).await {
+    match trigger_model_loading(context, ollama_model_name, load_method, cancellation_token).await {
         Ok(true) => Ok(()),
         Ok(false) => {
             log_warning("Load hint", &format!("Trigger for '{}' failed, proceeding", ollama_model_name));
@@ -97,6 +274,7 @@ pub async fn with_retry_and_cancellation<F, Fut, T>(
     context: &RequestContext<'_>,
     ollama_model_name: &str,
     load_timeout_seconds: u64,
+    load_method: LoadMethod,
     operation: F,
     cancellation_token: CancellationToken,
 ) -> Result<T, ProxyError>
@@ -113,12 +291,14 @@ where
             log_error("Request failed", "LM Studio unavailable - failing fast");
             Err(e)
         }
+        // Nothing to load, so waiting for a load cannot help
+        Err(e) if e.message == ERROR_NO_MODELS_AVAILABLE => Err(e),
         Err(e) => {
             if is_model_loading_error(&e.message) {
                 let model_loading_start = Instant::now();
                 log_timed(crate::constants::LOG_PREFIX_INFO, &format!("{} not loaded, triggering", ollama_model_name), model_loading_start);
 
-                match trigger_model_loading(context, ollama_model_name, cancellation_token.clone())
+                match trigger_model_loading(context, ollama_model_name, load_method, cancellation_token.clone())
                     .await
                 {
                     Ok(true) => {
@@ -211,6 +391,7 @@ pub async fn with_health_check_and_retry<F, Fut, T>(
     context: &RequestContext<'_>,
     ollama_model_name: Option<&str>,
     load_timeout_seconds: u64,
+    load_method: LoadMethod,
     operation: F,
     cancellation_token: CancellationToken,
 ) -> Result<T, ProxyError>
@@ -224,6 +405,7 @@ where
                 context,
                 model,
                 load_timeout_seconds,
+                load_method,
                 operation,
                 cancellation_token,
             )
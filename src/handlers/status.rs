@@ -0,0 +1,178 @@
+/// src/handlers/status.rs - Self-contained HTML status page for GET / (--enable-status-page)
+use serde_json::{json, Value};
+use std::fmt::Write;
+use tokio_util::sync::CancellationToken;
+
+use crate::common::RequestContext;
+use crate::constants::*;
+use crate::handlers::ollama::probe_lmstudio_health;
+use crate::server::{redact_url, ModelResolverType};
+use crate::utils::ProxyError;
+
+/// Handle GET / - gather proxy, backend, model and metrics info and render it as HTML.
+/// `cached_health` is the background prober's last report, if one is running.
+pub async fn handle_status_page(
+    context: RequestContext<'_>,
+    model_resolver: ModelResolverType,
+    cached_health: Option<Value>,
+    metrics: Value,
+    cancellation_token: CancellationToken,
+) -> Result<warp::reply::Response, ProxyError> {
+    let health = match cached_health {
+        Some(report) => report,
+        None => {
+            let native = matches!(model_resolver, ModelResolverType::Native(_));
+            probe_lmstudio_health(context.clone(), native, cancellation_token.clone()).await?
+        }
+    };
+
+    let (api_mode, models_label, models) = match &model_resolver {
+        ModelResolverType::Native(resolver) => (
+            "Native (LM Studio REST API)",
+            "Loaded models",
+            resolver
+                .get_loaded_models(context.client, cancellation_token)
+                .await
+                .map(|models| models.into_iter().map(|m| m.id).collect()),
+        ),
+        ModelResolverType::Legacy(_) => (
+            "Legacy (OpenAI-compatible)",
+            "Available models",
+            model_resolver.available_model_ids(context.client, cancellation_token).await,
+        ),
+    };
+    if let Err(e) = &models {
+        if e.is_cancelled() {
+            return Err(ProxyError::request_cancelled());
+        }
+    }
+
+    let status = json!({
+        "version": crate::VERSION,
+        "lmstudio_url": redact_url(context.lmstudio_url),
+        "active_backend": context.failover.map(|failover| redact_url(failover.active_backend())),
+        "api_mode": api_mode,
+        "health": health,
+        "models_label": models_label,
+        "models": models.ok(),
+        "metrics": metrics,
+    });
+
+    Ok(html_response(render_status_page(&status)))
+}
+
+/// Render the status page from the JSON gathered by `handle_status_page`
+pub fn render_status_page(status: &Value) -> String {
+    let text = |value: &Value| match value {
+        Value::String(s) => escape_html(s),
+        Value::Null => "-".to_string(),
+        other => escape_html(&other.to_string()),
+    };
+    let health = &status["health"];
+    let health_status = health["status"].as_str().unwrap_or("unknown");
+    let health_class = if health_status == "healthy" { "ok" } else { "bad" };
+
+    let mut html = String::with_capacity(4096);
+    html.push_str(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+         <title>Ollama-LMStudio Proxy</title>\n<style>\n\
+         body{font-family:system-ui,sans-serif;max-width:52rem;margin:2rem auto;padding:0 1rem;color:#222}\n\
+         table{border-collapse:collapse;width:100%;margin-bottom:1.5rem}\n\
+         th,td{text-align:left;padding:.35rem .6rem;border-bottom:1px solid #ddd}\n\
+         .ok{color:#1a7f37;font-weight:600}.bad{color:#c62828;font-weight:600}\n\
+         </style>\n</head>\n<body>\n<h1>Ollama-LMStudio Proxy</h1>\n",
+    );
+
+    let _ = write!(
+        html,
+        "<h2>Proxy</h2>\n<table>\n\
+         <tr><th>Version</th><td>{}</td></tr>\n\
+         <tr><th>LM Studio URL</th><td>{}</td></tr>\n",
+        text(&status["version"]),
+        text(&status["lmstudio_url"]),
+    );
+    if !status["active_backend"].is_null() {
+        let _ = writeln!(html, "<tr><th>Active backend</th><td>{}</td></tr>", text(&status["active_backend"]));
+    }
+    let _ = write!(
+        html,
+        "<tr><th>API mode</th><td>{}</td></tr>\n\
+         <tr><th>LM Studio</th><td class=\"{}\">{}</td></tr>\n\
+         <tr><th>Response time</th><td>{} ms</td></tr>\n</table>\n",
+        text(&status["api_mode"]),
+        health_class,
+        escape_html(health_status),
+        text(&health["response_time_ms"]),
+    );
+
+    let _ = writeln!(html, "<h2>{}</h2>", text(&status["models_label"]));
+    match status["models"].as_array() {
+        Some(models) if !models.is_empty() => {
+            html.push_str("<ul>\n");
+            for model in models {
+                let _ = writeln!(html, "<li>{}</li>", text(model));
+            }
+            html.push_str("</ul>\n");
+        }
+        Some(_) => html.push_str("<p>None</p>\n"),
+        None => html.push_str("<p class=\"bad\">Could not fetch models from LM Studio</p>\n"),
+    }
+
+    let metrics = &status["metrics"];
+    let _ = write!(
+        html,
+        "<h2>Metrics</h2>\n<table>\n\
+         <tr><th>Requests</th><td>{}</td></tr>\n\
+         <tr><th>Errors</th><td>{}</td></tr>\n\
+         <tr><th>Average time</th><td>{:.1} ms</td></tr>\n</table>\n",
+        text(&metrics["requests_total"]),
+        text(&metrics["errors_total"]),
+        metrics["avg_handler_time_ms"].as_f64().unwrap_or(0.0),
+    );
+    if let Some(endpoints) = metrics["endpoints"].as_object().filter(|e| !e.is_empty()) {
+        html.push_str("<table>\n<tr><th>Endpoint</th><th>Requests</th><th>Errors</th><th>Average</th></tr>\n");
+        for (endpoint, stats) in endpoints {
+            let _ = writeln!(
+                html,
+                "<tr><td>{}</td><td>{}</td><td>{}</td><td>{:.1} ms</td></tr>",
+                escape_html(endpoint),
+                text(&stats["requests"]),
+                text(&stats["errors"]),
+                stats["avg_time_ms"].as_f64().unwrap_or(0.0),
+            );
+        }
+        html.push_str("</table>\n");
+    }
+
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+fn escape_html(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn html_response(html: String) -> warp::reply::Response {
+    let mut response = warp::reply::Response::new(html.into());
+    response.headers_mut().insert(
+        warp::http::header::CONTENT_TYPE,
+        warp::http::HeaderValue::from_static(CONTENT_TYPE_HTML),
+    );
+    response.headers_mut().insert(
+        warp::http::header::CACHE_CONTROL,
+        warp::http::HeaderValue::from_static(HEADER_CACHE_CONTROL),
+    );
+    response
+}
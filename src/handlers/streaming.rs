@@ -1,16 +1,18 @@
 /// src/handlers/streaming.rs - Enhanced streaming with model loading detection and better timing
-
 use futures_util::StreamExt;
 use serde_json::{json, Value};
 use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::sync::mpsc;
 use tokio::time::timeout;
 use tokio_util::sync::CancellationToken;
 
 use crate::constants::*;
+use crate::health::BackendRuntime;
 use crate::handlers::helpers::{
-    create_cancellation_chunk, create_error_chunk, create_final_chunk, create_ollama_streaming_chunk,
+    attach_system_fingerprint, create_cancellation_chunk, create_error_chunk, create_final_chunk,
+    create_ollama_streaming_chunk, estimate_tokens_from_len, system_fingerprint, ThinkTagFilter,
 };
 use crate::utils::{log_error, log_timed, log_warning, ProxyError};
 
@@ -19,20 +21,81 @@ static STREAM_COUNTER: AtomicU64 = AtomicU64::new(0);
 /// Threshold for detecting slow stream starts (likely model loading)
 const STREAM_START_LOADING_THRESHOLD_MS: u128 = 500;
 
+/// Buffers small content deltas for up to a time window (or until a newline) before emitting them
+pub struct DeltaCoalescer {
+    window: Option<Duration>,
+    pending: String,
+    deadline: Option<tokio::time::Instant>,
+}
+
+impl DeltaCoalescer {
+    /// A zero window disables coalescing
+    pub fn new(window_ms: u64) -> Self {
+        Self {
+            window: (window_ms > 0).then(|| Duration::from_millis(window_ms)),
+            pending: String::new(),
+            deadline: None,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.window.is_some()
+    }
+
+    /// Buffer content; returns true when it should be flushed right away
+    pub fn push(&mut self, content: &str) -> bool {
+        let Some(window) = self.window else {
+            return true;
+        };
+        self.pending.push_str(content);
+        let deadline = *self
+            .deadline
+            .get_or_insert_with(|| tokio::time::Instant::now() + window);
+        content.contains('\n') || tokio::time::Instant::now() >= deadline
+    }
+
+    /// When buffered content must be flushed even if no new delta arrives
+    pub fn deadline(&self) -> Option<tokio::time::Instant> {
+        self.deadline
+    }
+
+    /// Take all buffered content
+    pub fn take(&mut self) -> String {
+        self.deadline = None;
+        std::mem::take(&mut self.pending)
+    }
+}
+
 /// Check if request is streaming
 pub fn is_streaming_request(body: &Value) -> bool {
     body.get("stream").and_then(|s| s.as_bool()).unwrap_or(false)
 }
 
+/// Argument bytes carried by one streamed `tool_calls` delta
+pub fn tool_call_arguments_len(tool_calls: &[Value]) -> usize {
+    tool_calls
+        .iter()
+        .filter_map(|call| call.get("function")?.get("arguments"))
+        .map(|arguments| match arguments {
+            Value::String(text) => text.len(),
+            other => other.to_string().len(),
+        })
+        .sum()
+}
+
 /// Handle streaming response with model loading detection
+#[allow(clippy::too_many_arguments)]
 pub async fn handle_streaming_response(
     lm_studio_response: reqwest::Response,
     is_chat_endpoint: bool,
     ollama_model_name: &str,
+    created_at: &str,
     start_time: Instant,
     cancellation_token: CancellationToken,
     stream_timeout_seconds: u64,
+    backend_runtime: Option<Arc<BackendRuntime>>,
 ) -> Result<warp::reply::Response, ProxyError> {
+    crate::metrics::mark_streamed();
     let runtime_config = get_runtime_config();
     let ollama_model_name = ollama_model_name.to_string();
     let (tx, rx) = mpsc::unbounded_channel::<Result<bytes::Bytes, std::io::Error>>();
@@ -41,29 +104,48 @@ pub async fn handle_streaming_response(
     let model_loading_start = Instant::now();
 
     let model_clone_for_task = ollama_model_name.clone();
+    let created_at = created_at.to_string();
     let token_clone = cancellation_token.clone();
 
     tokio::spawn(async move {
         let mut stream = lm_studio_response.bytes_stream();
         let mut sse_buffer = String::with_capacity(runtime_config.max_buffer_size.min(1024 * 1024));
         let mut chunk_count = 0u64;
-        let mut accumulated_tool_calls: Option<Vec<Value>> = None;
+        // Only the length is kept for the cancellation token estimate, not the content itself
+        let mut streamed_content_len = 0usize;
+        let mut tool_call_args_bytes = 0usize;
+        let mut last_finish_reason: Option<String> = None;
+        let mut last_system_fingerprint: Option<String> = None;
+        // Recorded once the stream completes rather than locking on every chunk
+        let mut last_runtime: Option<Value> = None;
         let mut first_chunk_received = false;
+        let mut coalescer = DeltaCoalescer::new(runtime_config.coalesce_stream_ms);
+        let mut think_filter = runtime_config.drop_reasoning.then(ThinkTagFilter::new);
 
         let stream_result = 'stream_loop: loop {
+            let flush_deadline = coalescer.deadline();
             tokio::select! {
                 biased; // Prioritize cancellation
                 _ = token_clone.cancelled() => {
+                    flush_coalesced(&tx, &model_clone_for_task, &created_at, is_chat_endpoint, &mut coalescer, &mut chunk_count).await;
                     let cancellation_chunk = create_cancellation_chunk(
                         &model_clone_for_task,
+                        &created_at,
                         start_time.elapsed(),
-                        chunk_count,
+                        estimate_tokens_from_len(streamed_content_len),
                         is_chat_endpoint,
                     );
                     send_chunk_and_close_channel(&tx, cancellation_chunk).await;
                     break 'stream_loop Err(ERROR_CANCELLED.to_string());
                 }
 
+                // Flush buffered deltas once the coalescing window passes without new data
+                _ = tokio::time::sleep_until(flush_deadline.unwrap_or_else(tokio::time::Instant::now)), if flush_deadline.is_some() => {
+                    if !flush_coalesced(&tx, &model_clone_for_task, &created_at, is_chat_endpoint, &mut coalescer, &mut chunk_count).await {
+                        break 'stream_loop Ok(());
+                    }
+                }
+
                 chunk_result = timeout(Duration::from_secs(stream_timeout_seconds), stream.next()) => {
                     match chunk_result {
                         Ok(Some(Ok(bytes_chunk))) => {
@@ -86,35 +168,67 @@ pub async fn handle_streaming_response(
 
                                     if message_text.trim().is_empty() { continue; }
 
-                                    if let Some(data_content) = message_text.strip_prefix(SSE_DATA_PREFIX) {
+                                    if let Some(data_content) = sse_event_data(&message_text) {
                                         if data_content.trim() == SSE_DONE_MESSAGE {
                                             break 'stream_loop Ok(());
                                         }
 
-                                        match serde_json::from_str::<Value>(data_content) {
-                                            Ok(lm_studio_json_chunk) => {
+                                        match serde_json::from_str::<Value>(&data_content) {
+                                            Ok(mut lm_studio_json_chunk) => {
+                                                if let Some(runtime) = lm_studio_json_chunk.get_mut("runtime").map(Value::take) {
+                                                    last_runtime = Some(runtime);
+                                                }
+                                                if let Some(fingerprint) = system_fingerprint(&lm_studio_json_chunk) {
+                                                    last_system_fingerprint = Some(fingerprint.to_string());
+                                                }
                                                 let mut content_to_send = String::new();
                                                 let mut tool_calls_delta: Option<Value> = None;
 
                                                 if let Some(choices) = lm_studio_json_chunk.get("choices").and_then(|c| c.as_array()) {
                                                     if let Some(choice) = choices.first() {
+                                                        if let Some(finish_reason) = choice.get("finish_reason").and_then(|f| f.as_str()) {
+                                                            last_finish_reason = Some(finish_reason.to_string());
+                                                        }
                                                         if let Some(delta) = choice.get("delta") {
                                                             if let Some(content) = delta.get("content").and_then(|c| c.as_str()) {
-                                                                content_to_send.push_str(content);
+                                                                streamed_content_len += content.len();
+                                                                match think_filter.as_mut() {
+                                                                    Some(filter) => content_to_send.push_str(&filter.push(content)),
+                                                                    None => content_to_send.push_str(content),
+                                                                }
                                                             }
                                                             if let Some(new_tool_calls) = delta.get("tool_calls").and_then(|tc| tc.as_array()) {
-                                                                if accumulated_tool_calls.is_none() {
-                                                                    accumulated_tool_calls = Some(Vec::new());
-                                                                }
+                                                                tool_call_args_bytes += tool_call_arguments_len(new_tool_calls);
                                                                 tool_calls_delta = Some(json!(new_tool_calls));
                                                             }
                                                         }
                                                     }
                                                 }
 
-                                                if !content_to_send.is_empty() || tool_calls_delta.is_some() {
+                                                let args_limit = runtime_config.max_tool_call_args_bytes;
+                                                if args_limit > 0 && tool_call_args_bytes > args_limit {
+                                                    log_warning(
+                                                        "Tool calls",
+                                                        &format!("Stream [{}] sent {} argument bytes (limit {}), stopping", stream_id, tool_call_args_bytes, args_limit),
+                                                    );
+                                                    flush_coalesced(&tx, &model_clone_for_task, &created_at, is_chat_endpoint, &mut coalescer, &mut chunk_count).await;
+                                                    send_error_and_close(&tx, &model_clone_for_task, &created_at, ERROR_TOOL_CALL_ARGS_TOO_LARGE, is_chat_endpoint).await;
+                                                    break 'stream_loop Err(ERROR_TOOL_CALL_ARGS_TOO_LARGE.to_string());
+                                                }
+
+                                                if coalescer.is_enabled() && tool_calls_delta.is_none() {
+                                                    if !content_to_send.is_empty()
+                                                        && coalescer.push(&content_to_send)
+                                                        && !flush_coalesced(&tx, &model_clone_for_task, &created_at, is_chat_endpoint, &mut coalescer, &mut chunk_count).await
+                                                    {
+                                                        break 'stream_loop Ok(());
+                                                    }
+                                                } else if !content_to_send.is_empty() || tool_calls_delta.is_some() {
+                                                    // Buffered text goes out ahead of the tool call delta
+                                                    let content_to_send = coalescer.take() + &content_to_send;
                                                     let ollama_chunk = create_ollama_streaming_chunk(
                                                         &model_clone_for_task,
+                                                        &created_at,
                                                         &content_to_send,
                                                         is_chat_endpoint,
                                                         false,
@@ -135,12 +249,14 @@ pub async fn handle_streaming_response(
                                     }
                                 }
                             } else {
-                                send_error_and_close(&tx, &model_clone_for_task, "Invalid UTF-8 in stream", is_chat_endpoint).await;
+                                flush_coalesced(&tx, &model_clone_for_task, &created_at, is_chat_endpoint, &mut coalescer, &mut chunk_count).await;
+                                send_error_and_close(&tx, &model_clone_for_task, &created_at, "Invalid UTF-8 in stream", is_chat_endpoint).await;
                                 break 'stream_loop Err("Invalid UTF-8".to_string());
                             }
                         }
                         Ok(Some(Err(e))) => {
-                            send_error_and_close(&tx, &model_clone_for_task, &format!("Streaming error: {}", e), is_chat_endpoint).await;
+                            flush_coalesced(&tx, &model_clone_for_task, &created_at, is_chat_endpoint, &mut coalescer, &mut chunk_count).await;
+                            send_error_and_close(&tx, &model_clone_for_task, &created_at, &format!("Streaming error: {}", e), is_chat_endpoint).await;
                             break 'stream_loop Err(format!("Network error: {}", e));
                         }
                         Ok(None) => {
@@ -148,7 +264,8 @@ pub async fn handle_streaming_response(
                             break 'stream_loop Ok(());
                         }
                         Err(_) => {
-                            send_error_and_close(&tx, &model_clone_for_task, ERROR_TIMEOUT, is_chat_endpoint).await;
+                            flush_coalesced(&tx, &model_clone_for_task, &created_at, is_chat_endpoint, &mut coalescer, &mut chunk_count).await;
+                            send_error_and_close(&tx, &model_clone_for_task, &created_at, ERROR_TIMEOUT, is_chat_endpoint).await;
                             break 'stream_loop Err(ERROR_TIMEOUT.to_string());
                         }
                     }
@@ -157,12 +274,26 @@ pub async fn handle_streaming_response(
         };
 
         if stream_result.is_ok() && !token_clone.is_cancelled() {
-            let final_chunk = create_final_chunk(
+            flush_coalesced(&tx, &model_clone_for_task, &created_at, is_chat_endpoint, &mut coalescer, &mut chunk_count).await;
+            // Text held back as a possible partial <think> tag
+            let held_back = think_filter.as_mut().map(ThinkTagFilter::finish).unwrap_or_default();
+            if !held_back.is_empty() {
+                let chunk = create_ollama_streaming_chunk(&model_clone_for_task, &created_at, &held_back, is_chat_endpoint, false, None);
+                chunk_count += 1;
+                send_ollama_chunk(&tx, &chunk).await;
+            }
+            let mut final_chunk = create_final_chunk(
                 &model_clone_for_task,
+                &created_at,
                 start_time.elapsed(),
                 chunk_count,
                 is_chat_endpoint,
+                last_finish_reason.as_deref(),
             );
+            attach_system_fingerprint(&mut final_chunk, last_system_fingerprint.as_deref());
+            if let (Some(backend_runtime), Some(last_runtime)) = (&backend_runtime, &last_runtime) {
+                backend_runtime.set(last_runtime);
+            }
             send_chunk_and_close_channel(&tx, final_chunk).await;
         }
 
@@ -178,6 +309,7 @@ pub async fn handle_passthrough_streaming_response(
     cancellation_token: CancellationToken,
     stream_timeout_seconds: u64,
 ) -> Result<warp::reply::Response, ProxyError> {
+    crate::metrics::mark_streamed();
     let (tx, rx) = mpsc::unbounded_channel::<Result<bytes::Bytes, std::io::Error>>();
     let stream_id = STREAM_COUNTER.fetch_add(1, Ordering::Relaxed) % 1_000_000;
     let start_time = Instant::now();
@@ -224,14 +356,60 @@ pub async fn handle_passthrough_streaming_response(
     create_passthrough_streaming_response_format(rx)
 }
 
+/// Payload of one SSE event: its `data:` lines joined with newlines, per the SSE spec
+/// (None when the event has no data, e.g. a comment or a bare `event:` line)
+pub fn sse_event_data(event: &str) -> Option<String> {
+    let mut data: Option<String> = None;
+    for line in event.lines() {
+        let Some(value) = line.strip_prefix(SSE_DATA_FIELD) else {
+            continue;
+        };
+        let value = value.strip_prefix(' ').unwrap_or(value);
+        match data.as_mut() {
+            Some(joined) => {
+                joined.push('\n');
+                joined.push_str(value);
+            }
+            None => data = Some(value.to_string()),
+        }
+    }
+    data
+}
+
+/// Frame a serialized Ollama chunk for the configured --ollama-stream-format
+pub fn frame_ollama_chunk(chunk_json: &str, format: OllamaStreamFormat) -> String {
+    match format {
+        OllamaStreamFormat::Ndjson => format!("{}\n", chunk_json),
+        OllamaStreamFormat::Sse => format!("{}{}{}", SSE_DATA_PREFIX, chunk_json, SSE_MESSAGE_BOUNDARY),
+    }
+}
+
 /// Send Ollama chunk to client
 async fn send_ollama_chunk(tx: &mpsc::UnboundedSender<Result<bytes::Bytes, std::io::Error>>, chunk: &Value) -> bool {
     let chunk_json = serde_json::to_string(chunk).unwrap_or_else(|e| {
         log_error("Chunk serialization", &format!("Failed to serialize: {}", e));
         String::from("{\"error\":\"Internal proxy error: failed to serialize chunk\"}")
     });
-    let chunk_with_newline = format!("{}\n", chunk_json);
-    tx.send(Ok(bytes::Bytes::from(chunk_with_newline))).is_ok()
+    let framed = frame_ollama_chunk(&chunk_json, get_runtime_config().ollama_stream_format);
+    tx.send(Ok(bytes::Bytes::from(framed))).is_ok()
+}
+
+/// Emit buffered deltas as one Ollama chunk (no-op when nothing is buffered)
+async fn flush_coalesced(
+    tx: &mpsc::UnboundedSender<Result<bytes::Bytes, std::io::Error>>,
+    model_ollama_name: &str,
+    created_at: &str,
+    is_chat_endpoint: bool,
+    coalescer: &mut DeltaCoalescer,
+    chunk_count: &mut u64,
+) -> bool {
+    let content = coalescer.take();
+    if content.is_empty() {
+        return true;
+    }
+    let chunk = create_ollama_streaming_chunk(model_ollama_name, created_at, &content, is_chat_endpoint, false, None);
+    *chunk_count += 1;
+    send_ollama_chunk(tx, &chunk).await
 }
 
 /// Send chunk and close channel
@@ -240,34 +418,70 @@ async fn send_chunk_and_close_channel(
     chunk: Value,
 ) {
     let chunk_json = serde_json::to_string(&chunk).unwrap_or_default();
-    let chunk_with_newline = format!("{}\n", chunk_json);
-    let _ = tx.send(Ok(bytes::Bytes::from(chunk_with_newline)));
+    let format = get_runtime_config().ollama_stream_format;
+    let mut framed = frame_ollama_chunk(&chunk_json, format);
+    if format == OllamaStreamFormat::Sse {
+        framed.push_str(&frame_ollama_chunk(SSE_DONE_MESSAGE, format));
+    }
+    let _ = tx.send(Ok(bytes::Bytes::from(framed)));
 }
 
 /// Send error and close stream
 async fn send_error_and_close(
     tx: &mpsc::UnboundedSender<Result<bytes::Bytes, std::io::Error>>,
     model_ollama_name: &str,
+    created_at: &str,
     error_message: &str,
     is_chat_endpoint: bool,
 ) {
-    let error_chunk = create_error_chunk(model_ollama_name, error_message, is_chat_endpoint);
+    let error_chunk = create_error_chunk(model_ollama_name, created_at, error_message, is_chat_endpoint);
     send_chunk_and_close_channel(tx, error_chunk).await;
 }
 
+/// Merge chunks that are already queued when the body is polled into one write, so a client that falls behind
+/// a fast model gets a few large writes instead of one per token. Never waits for more chunks.
+pub fn batch_ready_chunks(
+    rx: mpsc::UnboundedReceiver<Result<bytes::Bytes, std::io::Error>>,
+) -> impl futures_util::Stream<Item = Result<bytes::Bytes, std::io::Error>> {
+    tokio_stream::wrappers::UnboundedReceiverStream::new(rx)
+        .ready_chunks(STREAM_WRITE_BATCH_MAX_CHUNKS)
+        .flat_map(|batch| {
+            let mut items = Vec::with_capacity(1);
+            let mut merged = bytes::BytesMut::new();
+            for item in batch {
+                match item {
+                    Ok(chunk) => merged.extend_from_slice(&chunk),
+                    Err(e) => {
+                        // Keep everything sent before the error, then end the body with it
+                        if !merged.is_empty() {
+                            items.push(Ok(merged.split().freeze()));
+                        }
+                        items.push(Err(e));
+                        break;
+                    }
+                }
+            }
+            if !merged.is_empty() {
+                items.push(Ok(merged.freeze()));
+            }
+            futures_util::stream::iter(items)
+        })
+}
+
 /// Create generic streaming response
 fn create_generic_streaming_response(
     rx: mpsc::UnboundedReceiver<Result<bytes::Bytes, std::io::Error>>,
     content_type: &str,
     error_message_on_build_fail: &str,
 ) -> Result<warp::reply::Response, ProxyError> {
-    let stream = tokio_stream::wrappers::UnboundedReceiverStream::new(rx);
+    let stream = batch_ready_chunks(rx);
 
     warp::http::Response::builder()
         .status(warp::http::StatusCode::OK)
         .header("content-type", content_type)
         .header("cache-control", HEADER_CACHE_CONTROL)
         .header("connection", HEADER_CONNECTION)
+        .header("content-encoding", HEADER_CONTENT_ENCODING_STREAM)
         .header("access-control-allow-origin", HEADER_ACCESS_CONTROL_ALLOW_ORIGIN)
         .header("access-control-allow-methods", HEADER_ACCESS_CONTROL_ALLOW_METHODS)
         .header("access-control-allow-headers", HEADER_ACCESS_CONTROL_ALLOW_HEADERS)
@@ -279,7 +493,11 @@ fn create_generic_streaming_response(
 fn create_ollama_streaming_response_format(
     rx: mpsc::UnboundedReceiver<Result<bytes::Bytes, std::io::Error>>,
 ) -> Result<warp::reply::Response, ProxyError> {
-    create_generic_streaming_response(rx, "application/x-ndjson; charset=utf-8", "Failed to create Ollama streaming response")
+    let content_type = match get_runtime_config().ollama_stream_format {
+        OllamaStreamFormat::Ndjson => "application/x-ndjson; charset=utf-8",
+        OllamaStreamFormat::Sse => CONTENT_TYPE_SSE,
+    };
+    create_generic_streaming_response(rx, content_type, "Failed to create Ollama streaming response")
 }
 
 /// Create passthrough SSE streaming response
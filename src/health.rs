@@ -0,0 +1,116 @@
+/// src/health.rs - Background LM Studio health prober backing GET /health
+use serde_json::Value;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+use tokio_util::sync::CancellationToken;
+
+use crate::common::RequestContext;
+use crate::failover::Failover;
+use crate::handlers::probe_lmstudio_health;
+use crate::utils::{log_info, log_warning};
+
+/// Most recent background probe result
+#[derive(Debug, Clone, Default)]
+pub struct HealthStatus {
+    pub report: Option<Value>,
+    pub checked_at: Option<Instant>,
+}
+
+impl HealthStatus {
+    /// The last report annotated with its age, or `None` before the first probe finishes
+    pub fn snapshot(&self) -> Option<Value> {
+        let mut report = self.report.clone()?;
+        if let (Some(obj), Some(checked_at)) = (report.as_object_mut(), self.checked_at) {
+            obj.insert(
+                "checked_ms_ago".to_string(),
+                Value::from(checked_at.elapsed().as_millis() as u64),
+            );
+        }
+        Some(report)
+    }
+}
+
+pub type SharedHealthStatus = Arc<RwLock<HealthStatus>>;
+
+/// Inference engine (`runtime`) from the most recent native LM Studio response, for /health's `backend_info`
+#[derive(Debug, Default)]
+pub struct BackendRuntime(std::sync::RwLock<Option<Value>>);
+
+impl BackendRuntime {
+    /// Remember the `runtime` object a native response carries
+    pub fn record(&self, response: &Value) {
+        if let Some(runtime) = response.get("runtime") {
+            self.set(runtime);
+        }
+    }
+
+    /// Store a `runtime` object, only locking for writing when it changed
+    pub fn set(&self, runtime: &Value) {
+        if !runtime.is_object() || self.0.read().is_ok_and(|last| last.as_ref() == Some(runtime)) {
+            return;
+        }
+        if let Ok(mut last) = self.0.write() {
+            *last = Some(runtime.clone());
+        }
+    }
+
+    /// The last recorded runtime, if any native response carried one
+    pub fn get(&self) -> Option<Value> {
+        self.0.read().ok()?.clone()
+    }
+}
+
+/// Probe LM Studio every `interval`, logging only when the status changes
+pub async fn run_health_prober(
+    client: reqwest::Client,
+    lmstudio_url: String,
+    interval: Duration,
+    status: SharedHealthStatus,
+    uses_native_api: impl Fn() -> bool,
+    failover: Option<Arc<Failover>>,
+    backend_runtime: Arc<BackendRuntime>,
+) {
+    let mut ticker = tokio::time::interval(interval);
+    let mut last_state: Option<String> = None;
+    loop {
+        ticker.tick().await;
+        let context = RequestContext {
+            client: &client,
+            lmstudio_url: &lmstudio_url,
+            timeout_seconds: None,
+            forwarded_headers: reqwest::header::HeaderMap::new(),
+            failover: failover.as_deref(),
+            backend_runtime: Some(backend_runtime.clone()),
+            shadow: None,
+            load_triggers: None,
+        };
+        // A probe that outlives the interval is dropped; the stale report keeps aging
+        let probe = probe_lmstudio_health(context, uses_native_api(), CancellationToken::new());
+        let Ok(Ok(mut report)) = tokio::time::timeout(interval, probe).await else {
+            continue;
+        };
+
+        let state = report
+            .get("status")
+            .and_then(|s| s.as_str())
+            .unwrap_or("unknown")
+            .to_string();
+        if let Some(failover) = &failover {
+            failover.record_primary_reachable(state != "unreachable");
+            report["active_backend"] = Value::from(crate::server::redact_url(failover.active_backend()));
+        }
+        if last_state.as_deref() != Some(state.as_str()) {
+            if state == "healthy" {
+                log_info(&format!("LM Studio health: {}", state));
+            } else {
+                log_warning("Health prober", &format!("LM Studio is {}", state));
+            }
+            last_state = Some(state);
+        }
+
+        let mut current = status.write().await;
+        current.report = Some(report);
+        current.checked_at = Some(Instant::now());
+    }
+}
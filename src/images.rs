@@ -0,0 +1,184 @@
+/// src/images.rs - Validation and optional downscaling of base64 images in vision requests
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use image::{ImageFormat, ImageReader, Limits};
+use serde_json::Value;
+use std::io::Cursor;
+
+use crate::constants::*;
+use crate::utils::{log_info, ProxyError};
+
+/// Limits applied to each image before it is forwarded to LM Studio
+#[derive(Debug, Clone, Copy)]
+pub struct ImageLimits {
+    pub max_bytes: usize,
+    pub max_dimension: u32,
+    pub resize: bool,
+}
+
+impl ImageLimits {
+    pub fn from_config(config: &crate::server::Config) -> Self {
+        Self {
+            max_bytes: config.max_image_bytes,
+            max_dimension: config.max_image_dimension,
+            resize: config.resize_images,
+        }
+    }
+}
+
+/// Validate (and downscale if enabled) every image in `images` and `messages[].images`; decoding runs on the
+/// blocking pool so large images do not stall the runtime
+pub async fn prepare_request_images(mut body: Value, limits: ImageLimits) -> Result<Value, ProxyError> {
+    let has_images = body.get("images").is_some()
+        || body
+            .get("messages")
+            .and_then(|m| m.as_array())
+            .is_some_and(|messages| messages.iter().any(|message| message.get("images").is_some()));
+    if !has_images {
+        return Ok(body);
+    }
+    tokio::task::spawn_blocking(move || prepare_images_in_body(&mut body, &limits).map(|_| body))
+        .await
+        .map_err(|e| ProxyError::internal_server_error(&format!("Image processing failed: {}", e)))?
+}
+
+fn prepare_images_in_body(body: &mut Value, limits: &ImageLimits) -> Result<(), ProxyError> {
+    if let Some(images) = body.get_mut("images") {
+        prepare_image_array(images, limits)?;
+    }
+    if let Some(messages) = body.get_mut("messages").and_then(|m| m.as_array_mut()) {
+        for message in messages {
+            if let Some(images) = message.get_mut("images") {
+                prepare_image_array(images, limits)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn prepare_image_array(images: &mut Value, limits: &ImageLimits) -> Result<(), ProxyError> {
+    let Some(images) = images.as_array_mut() else {
+        return Ok(());
+    };
+    for (index, image) in images.iter_mut().enumerate() {
+        let Some(encoded) = image.as_str() else {
+            return Err(ProxyError::bad_request(&format!(
+                "{} (image {}): expected a base64 string",
+                ERROR_INVALID_IMAGE, index
+            )));
+        };
+        if let Some(replacement) = prepare_image(encoded, limits)
+            .map_err(|e| ProxyError::bad_request(&format!("{} (image {})", e, index)))?
+        {
+            *image = Value::String(replacement);
+        }
+    }
+    Ok(())
+}
+
+/// Check a single base64 image, returning a re-encoded replacement when it was downscaled
+pub fn prepare_image(encoded: &str, limits: &ImageLimits) -> Result<Option<String>, String> {
+    // Keep an optional data URL prefix so the forwarded value has the same shape
+    let (prefix, payload) = match encoded.split_once(";base64,") {
+        Some((head, data)) if head.starts_with("data:") => (Some(head), data),
+        _ => (None, encoded),
+    };
+
+    let bytes = BASE64
+        .decode(payload.trim())
+        .map_err(|e| format!("{}: invalid base64 ({})", ERROR_INVALID_IMAGE, e))?;
+    let too_many_bytes = limits.max_bytes > 0 && bytes.len() > limits.max_bytes;
+    // Only a dimension limit or an oversized image needs the header parsed; other formats pass through untouched
+    if limits.max_dimension == 0 && !too_many_bytes {
+        return Ok(None);
+    }
+
+    let mut reader = ImageReader::new(Cursor::new(&bytes))
+        .with_guessed_format()
+        .map_err(|e| format!("{}: {}", ERROR_INVALID_IMAGE, e))?;
+    reader.limits(decode_limits());
+    let format = reader
+        .format()
+        .ok_or_else(|| format!("{}: unrecognized image format", ERROR_INVALID_IMAGE))?;
+    let (width, height) = reader
+        .into_dimensions()
+        .map_err(|e| format!("{}: {}", ERROR_INVALID_IMAGE, e))?;
+
+    let too_large = limits.max_dimension > 0 && width.max(height) > limits.max_dimension;
+    if !too_many_bytes && !too_large {
+        return Ok(None);
+    }
+
+    if !limits.resize {
+        return Err(format!(
+            "{}: {}x{} pixels, {} bytes (limits: {} px, {} bytes; enable --resize-images to downscale)",
+            ERROR_IMAGE_TOO_LARGE, width, height, bytes.len(), limits.max_dimension, limits.max_bytes
+        ));
+    }
+
+    let resized = downscale_image(&bytes, format, width, height, limits)?;
+    log_info(&format!(
+        "Image downscaled: {}x{} ({} bytes) -> {} bytes",
+        width,
+        height,
+        bytes.len(),
+        resized.len()
+    ));
+
+    let payload = BASE64.encode(resized);
+    Ok(Some(match prefix {
+        Some(prefix) => format!("{};base64,{}", prefix, payload),
+        None => payload,
+    }))
+}
+
+/// Decoder limits, so a small file claiming huge dimensions cannot allocate unbounded memory
+fn decode_limits() -> Limits {
+    let mut limits = Limits::default();
+    limits.max_image_width = Some(IMAGE_DECODE_MAX_DIMENSION);
+    limits.max_image_height = Some(IMAGE_DECODE_MAX_DIMENSION);
+    limits.max_alloc = Some(IMAGE_DECODE_MAX_ALLOC_BYTES);
+    limits
+}
+
+/// Downscale until both the dimension and byte limits are satisfied
+fn downscale_image(
+    bytes: &[u8],
+    format: ImageFormat,
+    width: u32,
+    height: u32,
+    limits: &ImageLimits,
+) -> Result<Vec<u8>, String> {
+    let mut reader = ImageReader::with_format(Cursor::new(bytes), format);
+    reader.limits(decode_limits());
+    let decoded = reader
+        .decode()
+        .map_err(|e| format!("{}: {}", ERROR_INVALID_IMAGE, e))?;
+    let output_format = if format.writing_enabled() { format } else { ImageFormat::Png };
+
+    let mut target = width.max(height);
+    if limits.max_dimension > 0 {
+        target = target.min(limits.max_dimension);
+    }
+
+    for _ in 0..IMAGE_RESIZE_MAX_ATTEMPTS {
+        let resized = decoded.resize(target, target, image::imageops::FilterType::Lanczos3);
+        let mut output = Cursor::new(Vec::new());
+        resized
+            .write_to(&mut output, output_format)
+            .map_err(|e| format!("Failed to re-encode image: {}", e))?;
+        let output = output.into_inner();
+
+        if limits.max_bytes == 0 || output.len() <= limits.max_bytes {
+            return Ok(output);
+        }
+        // Byte size scales roughly with pixel count
+        let ratio = (limits.max_bytes as f64 / output.len() as f64).sqrt() * 0.9;
+        target = ((target as f64 * ratio) as u32).max(1);
+    }
+
+    Err(format!(
+        "{}: could not downscale below {} bytes",
+        ERROR_IMAGE_TOO_LARGE, limits.max_bytes
+    ))
+}
@@ -1,25 +1,38 @@
 /// src/lib.rs - Main library file with native and legacy API support
-
 // Core modules
 pub mod constants;
 pub mod model;           // Native LM Studio API support
 pub mod model_legacy;    // Legacy OpenAI-compatible API support
+pub mod model_metadata;  // Shared Ollama builders over native and legacy metadata
 pub mod server;
 pub mod utils;
 pub mod handlers;
 pub mod common;
+pub mod metrics;
+pub mod images;
+pub mod activity;
+pub mod health;
+pub mod failover;
+pub mod shadow;
+pub mod rate_limit;
+pub mod fair_queue;
+pub mod self_test;
+pub mod request_registry;
 
 // Public re-exports for easy access
 pub use common::RequestContext;
 
 // Native API exports (default)
-pub use model::{clean_model_name, ModelInfo, ModelResolver};
+pub use model::{clean_model_name, ModelIndex, ModelInfo, ModelResolver, ResolutionMethod, ResolvedModel};
 
 // Legacy API exports
 pub use model_legacy::{
     clean_model_name_legacy, ModelInfoLegacy, ModelResolverLegacy
 };
 
+// Model metadata exports
+pub use model_metadata::ModelMetadataProvider;
+
 // Server exports
 pub use server::{Config, ModelResolverType, ProxyServer};
 
@@ -193,6 +206,22 @@ impl ApiFeatureComparison {
     }
 }
 
+impl ApiFeatureComparison {
+    /// Features and limitations of both modes as JSON
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "native": {
+                "features": self.native_features,
+                "limitations": self.native_limitations
+            },
+            "legacy": {
+                "features": self.legacy_features,
+                "limitations": self.legacy_limitations
+            }
+        })
+    }
+}
+
 impl Default for ApiFeatureComparison {
     fn default() -> Self {
         Self::new()
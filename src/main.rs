@@ -1,12 +1,25 @@
 /// src/main.rs - Application entry point for the Ollama-LMStudio proxy server.
-
 use clap::Parser;
-use ollama_lmstudio_proxy_rust::{Config, ProxyServer};
+use ollama_lmstudio_proxy_rust::self_test::run_self_test;
+use ollama_lmstudio_proxy_rust::{validate_config, Config, ProxyServer};
 
-#[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
+fn main() -> Result<(), Box<dyn std::error::Error>> {
     let config = Config::parse();
-    let server = ProxyServer::new(config)?;
-    server.run().await?;
-    Ok(())
+    validate_config(&config)?;
+
+    // Built by hand so --worker-threads can cap the pool (default: one per CPU)
+    let mut runtime_builder = tokio::runtime::Builder::new_multi_thread();
+    runtime_builder.enable_all();
+    if let Some(worker_threads) = config.worker_threads {
+        runtime_builder.worker_threads(worker_threads);
+    }
+    let runtime = runtime_builder.build()?;
+
+    runtime.block_on(async {
+        let server = ProxyServer::new(config)?;
+        if server.config.self_test {
+            return run_self_test(&server).await;
+        }
+        server.run().await
+    })
 }
@@ -0,0 +1,344 @@
+/// src/metrics.rs - Request timing metrics separating upstream LM Studio time from proxy overhead
+use dashmap::DashMap;
+use serde_json::{json, Map, Value};
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::constants::HTTP_POOL_MAX_IDLE_PER_HOST;
+use crate::model::ResolvedModel;
+
+tokio::task_local! {
+    /// Per-request measurements collected while a handler runs
+    static REQUEST_SCOPE: Arc<RequestScope>;
+}
+
+/// Data accumulated inside a single measured handler
+#[derive(Default)]
+struct RequestScope {
+    upstream_time_ns: AtomicU64,
+    model: Mutex<Option<String>>,
+    resolution_method: Mutex<Option<&'static str>>,
+    streamed: AtomicBool,
+}
+
+/// Record time spent waiting on LM Studio for the current request (no-op outside a measured handler)
+pub fn record_upstream_time(duration: Duration) {
+    let _ = REQUEST_SCOPE.try_with(|scope| {
+        scope
+            .upstream_time_ns
+            .fetch_add(duration.as_nanos() as u64, Ordering::Relaxed);
+    });
+}
+
+/// Record the LM Studio model resolved for the current request (no-op outside a measured handler)
+pub fn record_request_model(model: &str) {
+    let _ = REQUEST_SCOPE.try_with(|scope| {
+        if let Ok(mut current) = scope.model.lock() {
+            *current = Some(model.to_string());
+        }
+    });
+}
+
+/// Record the resolved model along with how it was chosen (no-op outside a measured handler)
+pub fn record_resolved_model(resolved: &ResolvedModel) {
+    record_request_model(&resolved.id);
+    let _ = REQUEST_SCOPE.try_with(|scope| {
+        if let Ok(mut current) = scope.resolution_method.lock() {
+            *current = Some(resolved.method.as_str());
+        }
+    });
+}
+
+/// Mark the current request as streamed: its handler returns once LM Studio's headers arrive while the body
+/// keeps flowing, so it is left out of the upstream/overhead numbers (no-op outside a measured handler)
+pub fn mark_streamed() {
+    let _ = REQUEST_SCOPE.try_with(|scope| scope.streamed.store(true, Ordering::Relaxed));
+}
+
+/// Upstream requests being sent (from dispatch until LM Studio's response headers), with pool pressure counters
+static UPSTREAM_IN_FLIGHT: AtomicU64 = AtomicU64::new(0);
+static UPSTREAM_IN_FLIGHT_PEAK: AtomicU64 = AtomicU64::new(0);
+static UPSTREAM_REQUESTS_TOTAL: AtomicU64 = AtomicU64::new(0);
+static UPSTREAM_POOL_SATURATED_TOTAL: AtomicU64 = AtomicU64::new(0);
+
+/// Count an upstream request as in flight until the guard drops. Requests beyond the idle pool size
+/// (`HTTP_POOL_MAX_IDLE_PER_HOST`) cannot all reuse pooled connections; the first one over logs a warning.
+pub fn begin_upstream_request() -> UpstreamRequestGuard {
+    let in_flight = UPSTREAM_IN_FLIGHT.fetch_add(1, Ordering::AcqRel) + 1;
+    UPSTREAM_REQUESTS_TOTAL.fetch_add(1, Ordering::Relaxed);
+    UPSTREAM_IN_FLIGHT_PEAK.fetch_max(in_flight, Ordering::Relaxed);
+    let pool_size = HTTP_POOL_MAX_IDLE_PER_HOST as u64;
+    if in_flight > pool_size {
+        UPSTREAM_POOL_SATURATED_TOTAL.fetch_add(1, Ordering::Relaxed);
+        if in_flight == pool_size + 1 {
+            crate::utils::log_warning(
+                "Connection pool",
+                &format!("More than {} concurrent LM Studio requests; extra connections are being opened", pool_size),
+            );
+        }
+    }
+    UpstreamRequestGuard(())
+}
+
+/// Keeps an upstream request counted as in flight until dropped
+pub struct UpstreamRequestGuard(());
+
+impl Drop for UpstreamRequestGuard {
+    fn drop(&mut self) {
+        UPSTREAM_IN_FLIGHT.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+/// Connection pool pressure for /metrics
+pub fn upstream_pool_snapshot() -> Value {
+    json!({
+        "pool_max_idle_per_host": HTTP_POOL_MAX_IDLE_PER_HOST,
+        "in_flight": UPSTREAM_IN_FLIGHT.load(Ordering::Acquire),
+        "in_flight_peak": UPSTREAM_IN_FLIGHT_PEAK.load(Ordering::Relaxed),
+        "requests_total": UPSTREAM_REQUESTS_TOTAL.load(Ordering::Relaxed),
+        "saturated_total": UPSTREAM_POOL_SATURATED_TOTAL.load(Ordering::Relaxed)
+    })
+}
+
+/// Timing breakdown for a single handled request
+#[derive(Debug, Clone)]
+pub struct RequestTiming {
+    pub handler_time: Duration,
+    pub upstream_time: Duration,
+    pub model: Option<String>,
+    pub resolution_method: Option<&'static str>,
+    /// Streamed response; `handler_time` only covers the time to the response headers
+    pub streamed: bool,
+}
+
+impl RequestTiming {
+    /// Time attributable to the proxy itself
+    pub fn proxy_overhead(&self) -> Duration {
+        self.handler_time.saturating_sub(self.upstream_time)
+    }
+}
+
+/// Warning line for a request at least `threshold` slow (--slow-request-threshold-ms), with its timing breakdown
+pub fn describe_slow_request(endpoint: &str, status: u16, timing: &RequestTiming, threshold: Duration) -> Option<String> {
+    if timing.handler_time < threshold {
+        return None;
+    }
+    let mut line = format!("{} | {} | total {}", endpoint, status, crate::utils::format_duration(timing.handler_time));
+    if timing.streamed {
+        line.push_str(" | streamed");
+    } else {
+        line.push_str(&format!(
+            " | upstream {} | proxy {}",
+            crate::utils::format_duration(timing.upstream_time),
+            crate::utils::format_duration(timing.proxy_overhead())
+        ));
+    }
+    if let Some(model) = &timing.model {
+        line.push_str(&format!(" | model {}", model));
+    }
+    Some(line)
+}
+
+/// Run a handler future while measuring total and upstream time
+pub async fn measure<F, T>(future: F) -> (T, RequestTiming)
+where
+    F: Future<Output = T>,
+{
+    let scope = Arc::new(RequestScope::default());
+    let start = Instant::now();
+    let output = REQUEST_SCOPE.scope(scope.clone(), future).await;
+    let timing = RequestTiming {
+        handler_time: start.elapsed(),
+        upstream_time: Duration::from_nanos(scope.upstream_time_ns.load(Ordering::Relaxed)),
+        model: scope.model.lock().ok().and_then(|m| m.clone()),
+        resolution_method: scope.resolution_method.lock().ok().and_then(|m| *m),
+        streamed: scope.streamed.load(Ordering::Relaxed),
+    };
+    (output, timing)
+}
+
+/// Lock-free counters for a single endpoint or model
+#[derive(Debug, Default)]
+pub struct StatsEntry {
+    requests: AtomicU64,
+    errors: AtomicU64,
+    time_ns_total: AtomicU64,
+}
+
+impl StatsEntry {
+    fn record(&self, duration: Duration, is_error: bool) {
+        self.requests.fetch_add(1, Ordering::Relaxed);
+        if is_error {
+            self.errors.fetch_add(1, Ordering::Relaxed);
+        }
+        self.time_ns_total
+            .fetch_add(duration.as_nanos() as u64, Ordering::Relaxed);
+    }
+
+    /// Number of recorded requests
+    pub fn requests(&self) -> u64 {
+        self.requests.load(Ordering::Relaxed)
+    }
+
+    /// Number of recorded errors
+    pub fn errors(&self) -> u64 {
+        self.errors.load(Ordering::Relaxed)
+    }
+
+    fn to_json(&self) -> Value {
+        let requests = self.requests();
+        let time_ms_total = self.time_ns_total.load(Ordering::Relaxed) as f64 / 1_000_000.0;
+        json!({
+            "requests": requests,
+            "errors": self.errors(),
+            "avg_time_ms": time_ms_total / requests.max(1) as f64
+        })
+    }
+}
+
+/// Aggregated proxy metrics (sharded maps and atomics, no global lock)
+#[derive(Debug, Default)]
+pub struct ProxyMetrics {
+    requests_total: AtomicU64,
+    errors_total: AtomicU64,
+    timed_requests_total: AtomicU64,
+    handler_time_ns_total: AtomicU64,
+    upstream_time_ns_total: AtomicU64,
+    endpoint_stats: DashMap<String, StatsEntry>,
+    model_usage: DashMap<String, StatsEntry>,
+    shadow: StatsEntry,
+    shadow_statuses: DashMap<String, StatsEntry>,
+    shadow_dropped_total: AtomicU64,
+}
+
+impl ProxyMetrics {
+    /// Create empty metrics
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a completed request
+    pub fn record_request(&self, endpoint: &str, timing: &RequestTiming, is_error: bool) {
+        self.requests_total.fetch_add(1, Ordering::Relaxed);
+        if is_error {
+            self.errors_total.fetch_add(1, Ordering::Relaxed);
+        }
+        // Streamed bodies are still flowing when the handler returns, so only complete responses feed the overhead totals
+        if !timing.streamed {
+            self.timed_requests_total.fetch_add(1, Ordering::Relaxed);
+            self.handler_time_ns_total
+                .fetch_add(timing.handler_time.as_nanos() as u64, Ordering::Relaxed);
+            self.upstream_time_ns_total
+                .fetch_add(timing.upstream_time.as_nanos() as u64, Ordering::Relaxed);
+        }
+
+        self.update_endpoint_stats(endpoint, timing.handler_time, is_error);
+        if let Some(model) = &timing.model {
+            self.record_model_usage(model, timing.handler_time, is_error);
+        }
+    }
+
+    /// Record a request against an endpoint
+    pub fn update_endpoint_stats(&self, endpoint: &str, duration: Duration, is_error: bool) {
+        record_entry(&self.endpoint_stats, endpoint, duration, is_error);
+    }
+
+    /// Record a request against a resolved model
+    pub fn record_model_usage(&self, model: &str, duration: Duration, is_error: bool) {
+        record_entry(&self.model_usage, model, duration, is_error);
+    }
+
+    /// Record a mirrored request to the shadow backend (`status` is `None` when it failed to complete)
+    pub fn record_shadow(&self, duration: Duration, status: Option<u16>) {
+        let is_error = status.is_none_or(|code| code >= 400);
+        self.shadow.record(duration, is_error);
+        let key = status.map_or_else(|| "error".to_string(), |code| code.to_string());
+        record_entry(&self.shadow_statuses, &key, duration, is_error);
+    }
+
+    /// Record a mirror skipped because too many shadow requests were already in flight
+    pub fn record_shadow_dropped(&self) {
+        self.shadow_dropped_total.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Shadow requests skipped at the in-flight cap
+    pub fn shadow_dropped(&self) -> u64 {
+        self.shadow_dropped_total.load(Ordering::Relaxed)
+    }
+
+    /// Shadow requests as (requests, errors)
+    pub fn shadow_counts(&self) -> (u64, u64) {
+        (self.shadow.requests(), self.shadow.errors())
+    }
+
+    /// Total recorded requests
+    pub fn requests_total(&self) -> u64 {
+        self.requests_total.load(Ordering::Relaxed)
+    }
+
+    /// Stats for a single endpoint as (requests, errors)
+    pub fn endpoint_counts(&self, endpoint: &str) -> Option<(u64, u64)> {
+        self.endpoint_stats
+            .get(endpoint)
+            .map(|entry| (entry.requests(), entry.errors()))
+    }
+
+    /// Stats for a single model as (requests, errors)
+    pub fn model_counts(&self, model: &str) -> Option<(u64, u64)> {
+        self.model_usage
+            .get(model)
+            .map(|entry| (entry.requests(), entry.errors()))
+    }
+
+    /// Snapshot metrics as JSON
+    pub fn snapshot(&self) -> Value {
+        let requests_total = self.requests_total();
+        let timed_requests = self.timed_requests_total.load(Ordering::Relaxed);
+        let requests = timed_requests.max(1) as f64;
+        let handler_ms_total = self.handler_time_ns_total.load(Ordering::Relaxed) as f64 / 1_000_000.0;
+        let upstream_ms_total = self.upstream_time_ns_total.load(Ordering::Relaxed) as f64 / 1_000_000.0;
+        let overhead_ms_total = (handler_ms_total - upstream_ms_total).max(0.0);
+
+        json!({
+            "requests_total": requests_total,
+            "errors_total": self.errors_total.load(Ordering::Relaxed),
+            "timed_requests_total": timed_requests,
+            "handler_time_ms_total": handler_ms_total,
+            "upstream_time_ms_total": upstream_ms_total,
+            "proxy_overhead_ms_total": overhead_ms_total,
+            "avg_handler_time_ms": handler_ms_total / requests,
+            "avg_upstream_time_ms": upstream_ms_total / requests,
+            "proxy_overhead_ms": overhead_ms_total / requests,
+            "endpoints": stats_to_json(&self.endpoint_stats),
+            "models": stats_to_json(&self.model_usage),
+            "shadow_requests_total": self.shadow.requests(),
+            "shadow_errors_total": self.shadow.errors(),
+            "shadow_avg_time_ms": self.shadow.to_json()["avg_time_ms"],
+            "shadow_statuses": stats_to_json(&self.shadow_statuses),
+            "shadow_dropped_total": self.shadow_dropped(),
+            "upstream_connections": upstream_pool_snapshot(),
+            "timestamp": chrono::Utc::now().to_rfc3339()
+        })
+    }
+}
+
+/// Record into a sharded map, only taking a shard write lock when the key is new
+fn record_entry(map: &DashMap<String, StatsEntry>, key: &str, duration: Duration, is_error: bool) {
+    if let Some(entry) = map.get(key) {
+        entry.record(duration, is_error);
+        return;
+    }
+    map.entry(key.to_string())
+        .or_default()
+        .record(duration, is_error);
+}
+
+fn stats_to_json(map: &DashMap<String, StatsEntry>) -> Value {
+    let mut stats = Map::new();
+    for entry in map.iter() {
+        stats.insert(entry.key().clone(), entry.value().to_json());
+    }
+    Value::Object(stats)
+}
@@ -2,11 +2,18 @@
 use moka::future::Cache;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
-use std::time::Instant;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio_util::sync::CancellationToken;
 
 use crate::common::CancellableRequest;
+use crate::failover::Failover;
 use crate::constants::*;
+use crate::handlers::retry::{trigger_model_loading, LoadMethod, LoadTriggers};
+use crate::metrics::record_resolved_model;
+use crate::model_metadata::{ollama_ps_entry, ollama_show_response, ollama_tags_entry, ModelMetadataProvider};
 use crate::utils::{log_timed, log_warning, ProxyError};
 
 /// Native LM Studio model data from /api/v0/models
@@ -22,6 +29,9 @@ pub struct NativeModelData {
     pub quantization: String,
     pub state: String,
     pub max_context_length: u64,
+    /// Chat template (Jinja) when LM Studio reports one
+    #[serde(default, alias = "prompt_template", skip_serializing_if = "Option::is_none")]
+    pub chat_template: Option<String>,
 }
 
 /// Native LM Studio models response
@@ -44,6 +54,9 @@ pub struct ModelInfo {
     pub state: String,
     pub max_context_length: u64,
     pub is_loaded: bool,
+    pub chat_template: Option<String>,
+    /// Output vector size of an embeddings model, probed for /api/show
+    pub embedding_length: Option<u64>,
 }
 
 impl ModelInfo {
@@ -63,10 +76,19 @@ impl ModelInfo {
             publisher: native_data.publisher.clone().unwrap_or_else(|| "unknown".to_string()),
             arch: native_data.arch.clone(),
             compatibility_type: native_data.compatibility_type.clone(),
-            quantization: native_data.quantization.clone(),
+            quantization: if native_data.quantization.trim().is_empty() {
+                get_runtime_config().default_quantization.clone()
+            } else {
+                native_data.quantization.clone()
+            },
             state: native_data.state.clone(),
             max_context_length: native_data.max_context_length,
             is_loaded,
+            chat_template: native_data
+                .chat_template
+                .clone()
+                .filter(|template| !template.trim().is_empty()),
+            embedding_length: None,
         }
     }
 
@@ -101,6 +123,23 @@ impl ModelInfo {
         caps
     }
 
+    /// Determine Ollama families: primary arch plus secondary families (vision projector, MoE base)
+    fn determine_families(&self) -> Vec<String> {
+        let mut families = vec![self.arch.clone()];
+
+        if let Some(base) = moe_base_family(&self.arch, &self.id) {
+            if !families.contains(&base) {
+                families.push(base);
+            }
+        }
+
+        if self.model_type == "vlm" && !families.iter().any(|f| f == "clip") {
+            families.push("clip".to_string());
+        }
+
+        families
+    }
+
     /// Calculate estimated file size based on architecture and quantization
     fn calculate_estimated_size(&self) -> u64 {
         // Extract parameter count from model ID if possible
@@ -143,83 +182,17 @@ impl ModelInfo {
 
     /// Generate Ollama-compatible model entry for /api/tags
     pub fn to_ollama_tags_model(&self) -> Value {
-        let estimated_size = self.calculate_estimated_size();
-
-        json!({
-            "name": self.ollama_name,
-            "model": self.ollama_name,
-            "modified_at": chrono::Utc::now().to_rfc3339(),
-            "size": estimated_size,
-            "digest": format!("{:x}", md5::compute(self.ollama_name.as_bytes())),
-            "details": {
-                "parent_model": "",
-                "format": self.compatibility_type,
-                "family": self.arch,
-                "families": [self.arch],
-                "parameter_size": self.extract_parameter_size_string(),
-                "quantization_level": self.quantization
-            }
-        })
+        ollama_tags_entry(self)
     }
 
     /// Generate Ollama-compatible model entry for /api/ps (running models)
     pub fn to_ollama_ps_model(&self) -> Value {
-        let estimated_size = self.calculate_estimated_size();
-
-        json!({
-            "name": self.ollama_name,
-            "model": self.ollama_name,
-            "size": estimated_size,
-            "digest": format!("{:x}", md5::compute(self.ollama_name.as_bytes())),
-            "details": {
-                "parent_model": "",
-                "format": self.compatibility_type,
-                "family": self.arch,
-                "families": [self.arch],
-                "parameter_size": self.extract_parameter_size_string(),
-                "quantization_level": self.quantization
-            },
-            "expires_at": (chrono::Utc::now() + chrono::Duration::minutes(DEFAULT_KEEP_ALIVE_MINUTES)).to_rfc3339(),
-            "size_vram": estimated_size
-        })
+        ollama_ps_entry(self)
     }
 
     /// Generate model show response for /api/show
     pub fn to_show_response(&self) -> Value {
-        let estimated_size = self.calculate_estimated_size();
-        let capabilities = self.determine_capabilities();
-        let param_size_str = self.extract_parameter_size_string();
-
-        json!({
-            "modelfile": format!("# Modelfile for {}\nFROM {} # (Real data from LM Studio)\n\nPARAMETER temperature {}\nPARAMETER top_p {}\nPARAMETER top_k {}\n\nTEMPLATE \"\"\"{{ if .System }}{{ .System }} {{ end }}{{ .Prompt }}\"\"\"",
-                self.ollama_name, self.ollama_name, DEFAULT_TEMPERATURE, DEFAULT_TOP_P, DEFAULT_TOP_K
-            ),
-            "parameters": format!("temperature {}\ntop_p {}\ntop_k {}\nrepeat_penalty {}",
-                DEFAULT_TEMPERATURE, DEFAULT_TOP_P, DEFAULT_TOP_K, DEFAULT_REPEAT_PENALTY),
-            "template": "{{ if .System }}{{ .System }}\\n{{ end }}{{ .Prompt }}",
-            "details": {
-                "parent_model": "",
-                "format": self.compatibility_type,
-                "family": self.arch,
-                "families": [self.arch],
-                "parameter_size": param_size_str,
-                "quantization_level": self.quantization
-            },
-            "model_info": {
-                "general.architecture": self.arch,
-                "general.file_type": 2,
-                "general.quantization_version": 2,
-                "lmstudio.publisher": self.publisher,
-                "lmstudio.model_type": self.model_type,
-                "lmstudio.state": self.state,
-                "lmstudio.max_context_length": self.max_context_length,
-                "lmstudio.compatibility_type": self.compatibility_type
-            },
-            "capabilities": capabilities,
-            "digest": format!("{:x}", md5::compute(self.ollama_name.as_bytes())),
-            "size": estimated_size,
-            "modified_at": chrono::Utc::now().to_rfc3339()
-        })
+        ollama_show_response(self)
     }
 
     /// Extract parameter size string from model ID
@@ -248,40 +221,476 @@ impl ModelInfo {
     }
 }
 
-/// Optimized model name cleaning
+impl ModelMetadataProvider for ModelInfo {
+    fn ollama_name(&self) -> &str {
+        &self.ollama_name
+    }
+
+    fn family(&self) -> String {
+        self.arch.clone()
+    }
+
+    fn parameter_size(&self) -> String {
+        self.extract_parameter_size_string()
+    }
+
+    fn estimated_size(&self) -> u64 {
+        self.calculate_estimated_size()
+    }
+
+    fn architecture(&self) -> String {
+        self.arch.clone()
+    }
+
+    fn capabilities(&self) -> Vec<String> {
+        self.determine_capabilities()
+    }
+
+    fn quantization_level(&self) -> String {
+        self.quantization.clone()
+    }
+
+    fn model_info(&self) -> Value {
+        let mut model_info = json!({
+            "general.architecture": self.arch,
+            "general.file_type": 2,
+            "general.quantization_version": 2,
+            "lmstudio.publisher": self.publisher,
+            "lmstudio.model_type": self.model_type,
+            "lmstudio.state": self.state,
+            "lmstudio.max_context_length": self.max_context_length,
+            "lmstudio.compatibility_type": self.compatibility_type
+        });
+        if let (Some(embedding_length), Some(info)) = (self.embedding_length, model_info.as_object_mut()) {
+            info.insert(format!("{}.embedding_length", self.arch), json!(embedding_length));
+        }
+        model_info
+    }
+
+    fn source_note(&self) -> &'static str {
+        "Real data from LM Studio"
+    }
+
+    fn families(&self) -> Vec<String> {
+        self.determine_families()
+    }
+
+    fn format(&self) -> String {
+        self.compatibility_type.clone()
+    }
+
+    fn chat_template(&self) -> Option<String> {
+        self.chat_template.clone()
+    }
+}
+
+/// Check if the part before a tag looks like a plain model name (no further colons)
+fn looks_like_model_name(base: &str) -> bool {
+    base.chars().next().is_some_and(|c| c.is_ascii_alphanumeric())
+        && base
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '/' | '@'))
+}
+
+/// Base family for mixture-of-experts architectures (`qwen2moe` -> `qwen2`, `mixtral` -> `llama`)
+pub fn moe_base_family(arch: &str, model_id: &str) -> Option<String> {
+    let lower_arch = arch.to_lowercase();
+    if lower_arch == "mixtral" || model_id.to_lowercase().contains("mixtral") {
+        return Some("llama".to_string());
+    }
+    let base = lower_arch
+        .strip_suffix("moe")?
+        .trim_end_matches(['_', '-']);
+    (!base.is_empty()).then(|| base.to_string())
+}
+
+/// How a model id was chosen, reported in the `X-Resolution-Method` header under --dev-mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResolutionMethod {
+    /// Answered by the model resolution cache
+    Cache,
+    /// Same id as the requested name
+    Exact,
+    /// The requested name is a prefix or substring of the id
+    Substring,
+    /// Best part-by-part match score
+    Scored,
+    /// Pinned with --pin-model
+    Alias,
+    /// No match; the cleaned name is sent as-is (legacy mode)
+    Fallback,
+}
+
+impl ResolutionMethod {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ResolutionMethod::Cache => "cache",
+            ResolutionMethod::Exact => "exact",
+            ResolutionMethod::Substring => "substring",
+            ResolutionMethod::Scored => "scored",
+            ResolutionMethod::Alias => "alias",
+            ResolutionMethod::Fallback => "fallback",
+        }
+    }
+}
+
+/// Outcome of resolving an Ollama model name to an LM Studio model id
+#[derive(Debug, Clone)]
+pub struct ResolvedModel {
+    pub id: String,
+    pub method: ResolutionMethod,
+}
+
+impl ResolvedModel {
+    pub fn new(id: String, method: ResolutionMethod) -> Self {
+        Self { id, method }
+    }
+
+    pub fn cache_hit(&self) -> bool {
+        self.method == ResolutionMethod::Cache
+    }
+}
+
+/// Parse `--pin-model <ollama-name>=<exact-lm-studio-id>` entries (keys are matched case-insensitively)
+pub fn parse_model_pins(specs: &[String]) -> Result<HashMap<String, String>, String> {
+    let mut pins = HashMap::with_capacity(specs.len());
+    for spec in specs {
+        let (name, id) = spec
+            .split_once('=')
+            .map(|(name, id)| (name.trim(), id.trim()))
+            .filter(|(name, id)| !name.is_empty() && !id.is_empty())
+            .ok_or_else(|| format!("Invalid --pin-model '{}': expected <ollama-name>=<lm-studio-id>", spec))?;
+        pins.insert(name.to_lowercase(), id.to_string());
+    }
+    Ok(pins)
+}
+
+/// Look up a pinned LM Studio id by the requested name, then by its cleaned form
+pub fn find_pinned_model<'a>(pins: &'a HashMap<String, String>, requested: &str) -> Option<&'a str> {
+    if pins.is_empty() {
+        return None;
+    }
+    pins.get(&requested.to_lowercase())
+        .or_else(|| pins.get(&clean_model_name(requested).to_lowercase()))
+        .map(String::as_str)
+}
+
+/// Conservative model name cleaning: strips a trailing `:latest` or purely numeric tag
 pub fn clean_model_name(name: &str) -> &str {
-    if name.is_empty() {
+    if name.is_empty() || !get_runtime_config().enable_tag_stripping {
         return name;
     }
-    let after_latest = if let Some(pos) = name.rfind(":latest") {
-        &name[..pos]
+    let Some(colon_pos) = name.rfind(':') else {
+        return name;
+    };
+    let (base, tag) = (&name[..colon_pos], &name[colon_pos + 1..]);
+    let is_strippable_tag =
+        tag == "latest" || (!tag.is_empty() && tag.chars().all(|c| c.is_ascii_digit()));
+    if is_strippable_tag && looks_like_model_name(base) {
+        base
     } else {
         name
-    };
-    if let Some(colon_pos) = after_latest.rfind(':') {
-        let suffix = &after_latest[colon_pos + 1..];
-        if !suffix.is_empty() && suffix.chars().all(|c| c.is_ascii_digit()) && colon_pos > 0 {
-            return &after_latest[..colon_pos];
+    }
+}
+
+/// Separators used to split model names into comparable parts
+const MODEL_NAME_SEPARATORS: &[char] = &['-', '_', ':', '.', '/', ' '];
+
+/// Split a lowercase model name into parts longer than one character
+fn split_model_name_parts(name: &str) -> Vec<&str> {
+    name.split(MODEL_NAME_SEPARATORS)
+        .filter(|s| s.len() > 1)
+        .collect()
+}
+
+/// Precomputed lowercase data for a single indexed model
+#[derive(Debug, Clone)]
+struct IndexedModel {
+    lower_id: String,
+    lower_arch: String,
+    parts: Vec<String>,
+}
+
+/// Lowercase-normalized lookup index built once per model list fetch
+#[derive(Debug, Clone)]
+pub struct ModelIndex {
+    models: Vec<ModelInfo>,
+    entries: Vec<IndexedModel>,
+    exact: HashMap<String, usize>,
+    /// Original-case ids, for the exact pass under --case-sensitive-match
+    exact_case: HashMap<String, usize>,
+    sorted_ids: Vec<(String, usize)>,
+}
+
+impl ModelIndex {
+    /// Build index from the LM Studio model list
+    pub fn new(models: Vec<ModelInfo>) -> Self {
+        let entries: Vec<IndexedModel> = models
+            .iter()
+            .map(|model| {
+                let lower_id = model.id.to_lowercase();
+                let parts = split_model_name_parts(&lower_id)
+                    .into_iter()
+                    .map(str::to_string)
+                    .collect();
+                IndexedModel {
+                    lower_arch: model.arch.to_lowercase(),
+                    lower_id,
+                    parts,
+                }
+            })
+            .collect();
+
+        let mut exact = HashMap::with_capacity(entries.len());
+        let mut exact_case = HashMap::with_capacity(entries.len());
+        for (idx, entry) in entries.iter().enumerate() {
+            exact.entry(entry.lower_id.clone()).or_insert(idx);
+            exact_case.entry(models[idx].id.clone()).or_insert(idx);
+        }
+
+        let mut sorted_ids: Vec<(String, usize)> = entries
+            .iter()
+            .enumerate()
+            .map(|(idx, entry)| (entry.lower_id.clone(), idx))
+            .collect();
+        sorted_ids.sort();
+
+        Self {
+            models,
+            entries,
+            exact,
+            exact_case,
+            sorted_ids,
+        }
+    }
+
+    /// Number of indexed models
+    pub fn len(&self) -> usize {
+        self.models.len()
+    }
+
+    /// Check if index has no models
+    pub fn is_empty(&self) -> bool {
+        self.models.is_empty()
+    }
+
+    /// All indexed models in their original order
+    pub fn models(&self) -> &[ModelInfo] {
+        &self.models
+    }
+
+    /// Sorted entries whose lowercase id starts with the given lowercase prefix
+    fn prefix_matches(&self, lower_prefix: &str) -> &[(String, usize)] {
+        let start = self
+            .sorted_ids
+            .partition_point(|(id, _)| id.as_str() < lower_prefix);
+        let len = self.sorted_ids[start..].partition_point(|(id, _)| id.starts_with(lower_prefix));
+        &self.sorted_ids[start..start + len]
+    }
+
+    /// Deterministic tie-break: prefer loaded models, then the shortest id, then id order
+    fn is_preferred(&self, candidate: usize, current: usize) -> bool {
+        let (a, b) = (&self.models[candidate], &self.models[current]);
+        (b.is_loaded, a.id.len(), &self.entries[candidate].lower_id)
+            < (a.is_loaded, b.id.len(), &self.entries[current].lower_id)
+    }
+
+    /// Pick the preferred model among candidates
+    fn pick_preferred(&self, candidates: impl Iterator<Item = usize>) -> Option<usize> {
+        candidates.fold(None, |best, idx| match best {
+            Some(current) if !self.is_preferred(idx, current) => Some(current),
+            _ => Some(idx),
+        })
+    }
+
+    /// Find best matching model for a cleaned Ollama name
+    pub fn find_best_match(&self, ollama_name_cleaned: &str) -> Option<&ModelInfo> {
+        self.find_best_match_with_method(ollama_name_cleaned).map(|(model, _)| model)
+    }
+
+    /// Like `find_best_match`, also reporting which kind of match picked the model
+    pub fn find_best_match_with_method(&self, ollama_name_cleaned: &str) -> Option<(&ModelInfo, ResolutionMethod)> {
+        let lower_ollama = ollama_name_cleaned.to_lowercase();
+        let is_close_enough = |idx: &usize| {
+            let id_len = self.models[*idx].id.len();
+            lower_ollama.len() > id_len / 2 || lower_ollama.len() > 10
+        };
+
+        // Exact match first
+        let exact_match = if get_runtime_config().case_sensitive_match {
+            self.exact_case.get(ollama_name_cleaned)
+        } else {
+            self.exact.get(&lower_ollama)
+        };
+        if let Some(&idx) = exact_match {
+            return Some((&self.models[idx], ResolutionMethod::Exact));
+        }
+
+        // Prefix match via sorted index, then general substring match
+        let prefix_match = self.pick_preferred(
+            self.prefix_matches(&lower_ollama)
+                .iter()
+                .map(|(_, idx)| *idx)
+                .filter(is_close_enough),
+        );
+        let substring_match = prefix_match.or_else(|| {
+            self.pick_preferred(
+                (0..self.entries.len())
+                    .filter(|idx| self.entries[*idx].lower_id.contains(&lower_ollama))
+                    .filter(is_close_enough),
+            )
+        });
+        if let Some(idx) = substring_match {
+            return Some((&self.models[idx], ResolutionMethod::Substring));
+        }
+
+        // Enhanced scoring match
+        let ollama_parts = split_model_name_parts(&lower_ollama);
+        let mut best: Option<(usize, usize)> = None;
+        for idx in 0..self.entries.len() {
+            let score = self.calculate_match_score(&lower_ollama, &ollama_parts, idx);
+            if score < 3 {
+                continue;
+            }
+            best = match best {
+                Some((best_score, best_idx))
+                    if score < best_score
+                        || (score == best_score && !self.is_preferred(idx, best_idx)) =>
+                {
+                    Some((best_score, best_idx))
+                }
+                _ => Some((score, idx)),
+            };
+        }
+
+        best.map(|(_, idx)| (&self.models[idx], ResolutionMethod::Scored))
+    }
+
+    /// Calculate match score using precomputed model data
+    fn calculate_match_score(&self, ollama_name: &str, ollama_parts: &[&str], idx: usize) -> usize {
+        let model = &self.models[idx];
+        let entry = &self.entries[idx];
+        let mut score = 0;
+
+        // Part matching
+        for ollama_part in ollama_parts {
+            for model_part in &entry.parts {
+                if ollama_part == model_part {
+                    score += ollama_part.len() * 2; // Exact part match
+                } else if model_part.contains(ollama_part) || ollama_part.contains(model_part.as_str()) {
+                    score += ollama_part.len().min(model_part.len()); // Partial match
+                }
+            }
+        }
+
+        // Architecture matching bonus
+        if entry.lower_arch.contains(ollama_name) {
+            score += 5;
+        }
+
+        // Model type matching bonus
+        if model.model_type == "llm" && (ollama_name.contains("chat") || ollama_name.contains("instruct")) {
+            score += 3;
+        }
+        if model.model_type == "vlm" && (ollama_name.contains("vision") || ollama_name.contains("llava")) {
+            score += 3;
+        }
+        if model.model_type == "embeddings" && ollama_name.contains("embed") {
+            score += 3;
+        }
+
+        // Loaded model bonus (prefer loaded models)
+        if model.is_loaded {
+            score += 2;
+        }
+
+        // Prefix matching bonus
+        if entry.lower_id.starts_with(ollama_name) {
+            score += ollama_name.len();
         }
+
+        score
     }
-    after_latest
 }
 
 /// ModelResolver for handling model resolution with native LM Studio API
 pub struct ModelResolver {
     lmstudio_url: String,
     cache: Cache<String, String>,
+    pins: HashMap<String, String>,
+    /// Load unloaded matches before returning them, waiting up to this long (--auto-load-on-resolve)
+    auto_load_timeout: Option<Duration>,
+    /// Index of the last fetched model list, expiring with the resolution cache
+    model_index: Cache<(), Arc<ModelIndex>>,
+    /// Probed embedding vector sizes by LM Studio model id, expiring with the resolution cache
+    embedding_lengths: Cache<String, u64>,
+    failover: Option<Arc<Failover>>,
+    load_triggers: Option<Arc<LoadTriggers>>,
+    /// Set once /api/v0/models returns 404 (an LM Studio build without the native API)
+    native_api_not_found: AtomicBool,
 }
 
 impl ModelResolver {
     /// Create new model resolver for native API
     pub fn new(lmstudio_url: String, cache: Cache<String, String>) -> Self {
+        let mut index_cache = Cache::builder().max_capacity(1);
+        let mut embedding_lengths = Cache::builder().max_capacity(EMBEDDING_LENGTH_CACHE_CAPACITY);
+        if let Some(ttl) = cache.policy().time_to_live() {
+            index_cache = index_cache.time_to_live(ttl);
+            embedding_lengths = embedding_lengths.time_to_live(ttl);
+        }
         Self {
             lmstudio_url,
             cache,
+            pins: HashMap::new(),
+            auto_load_timeout: None,
+            model_index: index_cache.build(),
+            embedding_lengths: embedding_lengths.build(),
+            failover: None,
+            load_triggers: None,
+            native_api_not_found: AtomicBool::new(false),
         }
     }
 
+    /// Force exact resolution for pinned Ollama names (see `parse_model_pins`)
+    pub fn with_pins(mut self, pins: HashMap<String, String>) -> Self {
+        self.pins = pins;
+        self
+    }
+
+    /// Whether LM Studio has answered the native model list with 404
+    pub fn native_api_not_found(&self) -> bool {
+        self.native_api_not_found.load(Ordering::Relaxed)
+    }
+
+    /// Send model lookups through the primary/fallback backend state (--lmstudio-url-fallback)
+    pub fn with_failover(mut self, failover: Option<Arc<Failover>>) -> Self {
+        self.failover = failover;
+        self
+    }
+
+    /// Share load triggers for --auto-load-on-resolve with the request handlers
+    pub fn with_load_triggers(mut self, load_triggers: Option<Arc<LoadTriggers>>) -> Self {
+        self.load_triggers = load_triggers;
+        self
+    }
+
+    /// Load a matched model that is not loaded before resolving to it, waiting up to `timeout`
+    pub fn with_auto_load(mut self, timeout: Option<Duration>) -> Self {
+        self.auto_load_timeout = timeout;
+        self
+    }
+
+    /// List the ids of all models LM Studio offers
+    pub async fn available_model_ids(
+        &self,
+        client: &reqwest::Client,
+        cancellation_token: CancellationToken,
+    ) -> Result<Vec<String>, ProxyError> {
+        let models = self.get_available_lm_studio_models_native(client, cancellation_token).await?;
+        Ok(models.into_iter().map(|m| m.id).collect())
+    }
+
     /// Direct model resolution using native API with strict error handling
     pub async fn resolve_model_name(
         &self,
@@ -289,28 +698,76 @@ impl ModelResolver {
         client: &reqwest::Client,
         cancellation_token: CancellationToken,
     ) -> Result<String, ProxyError> {
+        self.resolve_model(ollama_model_name_requested, client, cancellation_token)
+            .await
+            .map(|resolved| resolved.id)
+    }
+
+    /// Resolve from pins or the cache only, without contacting LM Studio
+    pub async fn peek_resolution(&self, ollama_model_name_requested: &str) -> Option<ResolvedModel> {
+        if let Some(pinned_id) = find_pinned_model(&self.pins, ollama_model_name_requested) {
+            return Some(ResolvedModel::new(pinned_id.to_string(), ResolutionMethod::Alias));
+        }
+        let cleaned_ollama_request = clean_model_name(ollama_model_name_requested);
+        self.cache
+            .get(cleaned_ollama_request)
+            .await
+            .map(|id| ResolvedModel::new(id, ResolutionMethod::Cache))
+    }
+
+    /// Resolve an Ollama model name, also reporting whether the cache answered
+    pub async fn resolve_model(
+        &self,
+        ollama_model_name_requested: &str,
+        client: &reqwest::Client,
+        cancellation_token: CancellationToken,
+    ) -> Result<ResolvedModel, ProxyError> {
         let start_time = Instant::now();
+        if let Some(pinned_id) = find_pinned_model(&self.pins, ollama_model_name_requested) {
+            log_timed(LOG_PREFIX_SUCCESS, &format!("Pinned: '{}' -> '{}'", ollama_model_name_requested, pinned_id), start_time);
+            let resolved = ResolvedModel::new(pinned_id.to_string(), ResolutionMethod::Alias);
+            record_resolved_model(&resolved);
+            return Ok(resolved);
+        }
         let cleaned_ollama_request = clean_model_name(ollama_model_name_requested).to_string();
 
         // Check cache first
         if let Some(cached_lm_studio_id) = self.cache.get(&cleaned_ollama_request).await {
             log_timed(LOG_PREFIX_SUCCESS, &format!("Cache hit: '{}' -> '{}'", cleaned_ollama_request, cached_lm_studio_id), start_time);
-            return Ok(cached_lm_studio_id);
+            let resolved = ResolvedModel::new(cached_lm_studio_id, ResolutionMethod::Cache);
+            record_resolved_model(&resolved);
+            return Ok(resolved);
         }
 
-        log_warning("Cache miss", &format!("Fetching '{}' from LM Studio", cleaned_ollama_request));
+        log_warning("Cache miss", &format!("Matching '{}' against LM Studio's models", cleaned_ollama_request));
 
-        match self.get_available_lm_studio_models_native(client, cancellation_token).await {
-            Ok(available_models) => {
-                if let Some(matched_model) = self.find_best_match_native(&cleaned_ollama_request, &available_models) {
+        let model_index = match self.model_index.get(&()).await {
+            Some(index) if index.find_best_match(&cleaned_ollama_request).is_some() => Ok(index),
+            // Not in the cached list (or none cached): the model may have been added since, so fetch again
+            _ => self.fetch_model_index(client, cancellation_token.clone()).await,
+        };
+        match model_index {
+            Ok(model_index) if model_index.models().is_empty() => {
+                Err(ProxyError::not_found(ERROR_NO_MODELS_AVAILABLE))
+            }
+            Ok(model_index) => {
+                if let Some((matched_model, method)) = model_index
+                    .find_best_match_with_method(&cleaned_ollama_request)
+                    .map(|(model, method)| (model.clone(), method))
+                {
                     // Check if model is loaded for strict error handling
                     if !matched_model.is_loaded {
                         log_warning("Model state", &format!("'{}' found but not loaded (state: {})", matched_model.id, matched_model.state));
+                        if let Some(timeout) = self.auto_load_timeout {
+                            self.load_and_wait(&matched_model.id, timeout, client, cancellation_token).await?;
+                        }
                     }
 
                     self.cache.insert(cleaned_ollama_request.clone(), matched_model.id.clone()).await;
                     log_timed(LOG_PREFIX_SUCCESS, &format!("Resolved: '{}' -> '{}' ({})", cleaned_ollama_request, matched_model.id, matched_model.state), start_time);
-                    Ok(matched_model.id)
+                    let resolved = ResolvedModel::new(matched_model.id, method);
+                    record_resolved_model(&resolved);
+                    Ok(resolved)
                 } else {
                     // Strict error handling - don't allow unknown models
                     Err(ProxyError::not_found(&format!(
@@ -336,25 +793,183 @@ impl ModelResolver {
         }
     }
 
+    /// Look up LM Studio's data for an Ollama model name (pins honoured); `None` if unknown or unreachable
+    pub async fn find_model_info(
+        &self,
+        ollama_model_name_requested: &str,
+        client: &reqwest::Client,
+        cancellation_token: CancellationToken,
+    ) -> Option<ModelInfo> {
+        let model_index = match self.model_index.get(&()).await {
+            Some(index) => index,
+            None => self.fetch_model_index(client, cancellation_token).await.ok()?,
+        };
+        if let Some(pinned_id) = find_pinned_model(&self.pins, ollama_model_name_requested) {
+            return model_index.models().iter().find(|model| model.id == pinned_id).cloned();
+        }
+        model_index
+            .find_best_match(clean_model_name(ollama_model_name_requested))
+            .cloned()
+    }
+
+    /// Chat template from LM Studio's single-model endpoint (`/api/v0/models/{id}`), if it reports one
+    pub async fn fetch_chat_template(
+        &self,
+        model_id: &str,
+        client: &reqwest::Client,
+        cancellation_token: CancellationToken,
+    ) -> Option<String> {
+        let url = format!("{}{}/{}", self.lmstudio_url, LM_STUDIO_NATIVE_MODELS, model_id);
+        let context = crate::common::RequestContext {
+            client,
+            lmstudio_url: &self.lmstudio_url,
+            timeout_seconds: None,
+            forwarded_headers: reqwest::header::HeaderMap::new(),
+            failover: self.failover.as_deref(),
+            backend_runtime: None,
+            shadow: None,
+            load_triggers: None,
+        };
+        let response = CancellableRequest::new(context, cancellation_token)
+            .make_request(reqwest::Method::GET, &url, None::<Value>)
+            .await
+            .ok()
+            .filter(|response| response.status().is_success())?;
+        response
+            .json::<NativeModelData>()
+            .await
+            .ok()?
+            .chat_template
+            .filter(|template| !template.trim().is_empty())
+    }
+
+    /// Vector size of an embeddings model, from one probe embed per model id (cached for the process lifetime)
+    pub async fn fetch_embedding_length(
+        &self,
+        model_id: &str,
+        client: &reqwest::Client,
+        cancellation_token: CancellationToken,
+    ) -> Option<u64> {
+        if let Some(length) = self.embedding_lengths.get(model_id).await {
+            return Some(length);
+        }
+
+        let url = format!("{}{}", self.lmstudio_url, LM_STUDIO_NATIVE_EMBEDDINGS);
+        let context = crate::common::RequestContext {
+            client,
+            lmstudio_url: &self.lmstudio_url,
+            timeout_seconds: None,
+            forwarded_headers: reqwest::header::HeaderMap::new(),
+            failover: self.failover.as_deref(),
+            backend_runtime: None,
+            shadow: None,
+            load_triggers: None,
+        };
+        let body = json!({"model": model_id, "input": EMBEDDING_PROBE_INPUT});
+        let response = CancellableRequest::new(context, cancellation_token)
+            .make_request(reqwest::Method::POST, &url, Some(body))
+            .await
+            .ok()
+            .filter(|response| response.status().is_success())?;
+        let length = response
+            .json::<Value>()
+            .await
+            .ok()?
+            .pointer("/data/0/embedding")?
+            .as_array()
+            .map(|embedding| embedding.len() as u64)
+            .filter(|length| *length > 0)?;
+        self.embedding_lengths.insert(model_id.to_string(), length).await;
+        Some(length)
+    }
+
+    /// Trigger a load of `model_id` and poll the model list until LM Studio reports it loaded.
+    /// Gives up quietly after `timeout`; the request then falls back to the usual load-and-retry path.
+    async fn load_and_wait(
+        &self,
+        model_id: &str,
+        timeout: Duration,
+        client: &reqwest::Client,
+        cancellation_token: CancellationToken,
+    ) -> Result<(), ProxyError> {
+        let start_time = Instant::now();
+        let context = crate::common::RequestContext {
+            client,
+            lmstudio_url: &self.lmstudio_url,
+            timeout_seconds: None,
+            forwarded_headers: reqwest::header::HeaderMap::new(),
+            failover: self.failover.as_deref(),
+            backend_runtime: None,
+            shadow: None,
+            load_triggers: self.load_triggers.as_deref(),
+        };
+        let load_method = LoadMethod::Native { ttl_seconds: None };
+        if !trigger_model_loading(&context, model_id, load_method, cancellation_token.clone()).await? {
+            log_warning("Auto-load", &format!("Trigger for '{}' failed, proceeding", model_id));
+            return Ok(());
+        }
+
+        let deadline = start_time + timeout;
+        loop {
+            let loaded = self
+                .get_available_lm_studio_models_native(client, cancellation_token.clone())
+                .await?
+                .iter()
+                .any(|model| model.id == model_id && model.is_loaded);
+            if loaded {
+                log_timed(LOG_PREFIX_SUCCESS, &format!("Auto-loaded '{}'", model_id), start_time);
+                return Ok(());
+            }
+            if Instant::now() >= deadline {
+                log_warning("Auto-load", &format!("'{}' not loaded after {}s, proceeding", model_id, timeout.as_secs()));
+                return Ok(());
+            }
+            tokio::select! {
+                _ = tokio::time::sleep(Duration::from_millis(AUTO_LOAD_POLL_INTERVAL_MS)) => {}
+                _ = cancellation_token.cancelled() => return Err(ProxyError::request_cancelled()),
+            }
+        }
+    }
+
     /// Get available models from LM Studio native API
     async fn get_available_lm_studio_models_native(
         &self,
         client: &reqwest::Client,
         cancellation_token: CancellationToken,
     ) -> Result<Vec<ModelInfo>, ProxyError> {
+        let model_index = self.fetch_model_index(client, cancellation_token).await?;
+        Ok(model_index.models().to_vec())
+    }
+
+    /// Fetch the model list and index it once, replacing the cached index
+    async fn fetch_model_index(
+        &self,
+        client: &reqwest::Client,
+        cancellation_token: CancellationToken,
+    ) -> Result<Arc<ModelIndex>, ProxyError> {
         let url = format!("{}/api/v0/models", self.lmstudio_url);
 
         let temp_context = crate::common::RequestContext {
             client,
             lmstudio_url: &self.lmstudio_url,
+            timeout_seconds: crate::common::model_list_timeout_seconds(),
+            forwarded_headers: reqwest::header::HeaderMap::new(),
+            failover: self.failover.as_deref(),
+            backend_runtime: None,
+            shadow: None,
+            load_triggers: None,
         };
         let request = CancellableRequest::new(temp_context, cancellation_token);
 
         let response = request
             .make_request(reqwest::Method::GET, &url, None::<Value>)
-            .await?;
+            .await
+            .map_err(crate::common::model_list_timeout_error)?;
 
         if !response.status().is_success() {
+            if response.status() == reqwest::StatusCode::NOT_FOUND {
+                self.native_api_not_found.store(true, Ordering::Relaxed);
+            }
             return Err(ProxyError::new(
                 format!(
                     "Native API error ({}): {}. Try --legacy flag for older versions",
@@ -377,102 +992,12 @@ impl ModelResolver {
         let models = native_response
             .data
             .iter()
-            .map(|native_data| ModelInfo::from_native_data(native_data))
-            .collect();
-
-        Ok(models)
-    }
-
-    /// Find best matching model using native model data
-    fn find_best_match_native(
-        &self,
-        ollama_name_cleaned: &str,
-        available_models: &[ModelInfo],
-    ) -> Option<ModelInfo> {
-        let lower_ollama = ollama_name_cleaned.to_lowercase();
-
-        // Exact match first
-        for model in available_models {
-            if model.id.to_lowercase() == lower_ollama {
-                return Some(model.clone());
-            }
-        }
-
-        // Substring match
-        for model in available_models {
-            if model.id.to_lowercase().contains(&lower_ollama) {
-                if lower_ollama.len() > model.id.len() / 2 || lower_ollama.len() > 10 {
-                    return Some(model.clone());
-                }
-            }
-        }
-
-        // Enhanced scoring match
-        let mut best_match = None;
-        let mut best_score = 0;
-        for model in available_models {
-            let score = self.calculate_match_score_native(&lower_ollama, model);
-            if score > best_score && score >= 3 {
-                best_score = score;
-                best_match = Some(model.clone());
-            }
-        }
-
-        best_match
-    }
-
-    /// Calculate match score using native model data
-    fn calculate_match_score_native(&self, ollama_name: &str, model: &ModelInfo) -> usize {
-        let model_name_lower = model.id.to_lowercase();
-        let ollama_parts: Vec<&str> = ollama_name
-            .split(&['-', '_', ':', '.', '/', ' '])
-            .filter(|s| !s.is_empty() && s.len() > 1)
-            .collect();
-        let model_parts: Vec<&str> = model_name_lower
-            .split(&['-', '_', ':', '.', '/', ' '])
-            .filter(|s| !s.is_empty() && s.len() > 1)
+            .map(ModelInfo::from_native_data)
             .collect();
 
-        let mut score = 0;
-
-        // Part matching
-        for ollama_part in &ollama_parts {
-            for model_part in &model_parts {
-                if ollama_part == model_part {
-                    score += ollama_part.len() * 2; // Exact part match
-                } else if model_part.contains(ollama_part) || ollama_part.contains(model_part) {
-                    score += ollama_part.len().min(model_part.len()); // Partial match
-                }
-            }
-        }
-
-        // Architecture matching bonus
-        if model.arch.to_lowercase().contains(&ollama_name.to_lowercase()) {
-            score += 5;
-        }
-
-        // Model type matching bonus
-        if model.model_type == "llm" && (ollama_name.contains("chat") || ollama_name.contains("instruct")) {
-            score += 3;
-        }
-        if model.model_type == "vlm" && (ollama_name.contains("vision") || ollama_name.contains("llava")) {
-            score += 3;
-        }
-        if model.model_type == "embeddings" && ollama_name.contains("embed") {
-            score += 3;
-        }
-
-        // Loaded model bonus (prefer loaded models)
-        if model.is_loaded {
-            score += 2;
-        }
-
-        // Prefix matching bonus
-        if model_name_lower.starts_with(ollama_name) {
-            score += ollama_name.len();
-        }
-
-        score
+        let model_index = Arc::new(ModelIndex::new(models));
+        self.model_index.insert((), model_index.clone()).await;
+        Ok(model_index)
     }
 
     /// Get all available models (for /api/tags and /api/ps)
@@ -1,11 +1,17 @@
 /// src/model-legacy.rs - Legacy model handling with programmatic calculations (OpenAI-compatible endpoints)
 use moka::future::Cache;
 use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::Arc;
 use std::time::Instant;
 use tokio_util::sync::CancellationToken;
 
 use crate::common::CancellableRequest;
+use crate::failover::Failover;
 use crate::constants::*;
+use crate::metrics::record_resolved_model;
+use crate::model::{find_pinned_model, ResolutionMethod, ResolvedModel};
+use crate::model_metadata::{ollama_ps_entry, ollama_show_response, ollama_tags_entry, ModelMetadataProvider};
 use crate::utils::{log_timed, log_warning, ProxyError};
 
 /// Legacy model information with calculated estimates
@@ -55,32 +61,30 @@ impl ModelInfoLegacy {
 
         caps.push("completion".to_string());
 
-        if lower_name.contains("instruct")
+        if (lower_name.contains("instruct")
             || lower_name.contains("chat")
             || lower_family.contains("instruct")
-            || lower_family.contains("chat")
+            || lower_family.contains("chat"))
+            && !caps.contains(&"chat".to_string())
         {
-            if !caps.contains(&"chat".to_string()) {
-                caps.push("chat".to_string());
-            }
+            caps.push("chat".to_string());
         }
 
-        if lower_name.contains("llava")
+        if (lower_name.contains("llava")
             || lower_name.contains("vision")
             || lower_name.contains("bakllava")
             || lower_family.contains("llava")
             || lower_family.contains("vision")
-            || lower_family.contains("bakllava")
+            || lower_family.contains("bakllava"))
+            && !caps.contains(&"vision".to_string())
         {
-            if !caps.contains(&"vision".to_string()) {
-                caps.push("vision".to_string());
-            }
+            caps.push("vision".to_string());
         }
 
-        if lower_family == "embedding" || lower_name.contains("embed") {
-            if !caps.contains(&"embedding".to_string()) {
-                caps.push("embedding".to_string());
-            }
+        if (lower_family == "embedding" || lower_name.contains("embed"))
+            && !caps.contains(&"embedding".to_string())
+        {
+            caps.push("embedding".to_string());
         }
 
         if caps.contains(&"chat".to_string()) && !caps.contains(&"completion".to_string()) {
@@ -94,71 +98,39 @@ impl ModelInfoLegacy {
         caps
     }
 
+    /// Determine Ollama families: primary family plus secondary families (vision projector, MoE base)
+    fn determine_families_legacy(&self) -> Vec<String> {
+        if self.family.is_empty() {
+            return Vec::new();
+        }
+        let mut families = vec![self.family.clone()];
+
+        if let Some(base) = crate::model::moe_base_family(&self.family, &self.id_from_lm_studio) {
+            if !families.contains(&base) {
+                families.push(base);
+            }
+        }
+
+        if self.determine_capabilities_legacy().iter().any(|c| c == "vision") {
+            families.push("clip".to_string());
+        }
+
+        families
+    }
+
     /// Generate Ollama-compatible model entry for /api/tags
     pub fn to_ollama_tags_model_legacy(&self) -> Value {
-        json!({
-            "name": self.ollama_name,
-            "model": self.ollama_name,
-            "modified_at": chrono::Utc::now().to_rfc3339(),
-            "size": self.size_bytes,
-            "digest": format!("{:x}", md5::compute(self.ollama_name.as_bytes())),
-            "details": {
-                "parent_model": "",
-                "format": "gguf",
-                "family": self.family,
-                "families": if self.family.is_empty() { json!([]) } else { json!([self.family]) },
-                "parameter_size": self.parameter_size_str,
-                "quantization_level": self.quantization_level
-            }
-        })
+        ollama_tags_entry(self)
     }
 
     /// Generate Ollama-compatible model entry for /api/ps
     pub fn to_ollama_ps_model_legacy(&self) -> Value {
-        json!({
-            "name": self.ollama_name,
-            "model": self.ollama_name,
-            "size": self.size_bytes,
-            "digest": format!("{:x}", md5::compute(self.ollama_name.as_bytes())),
-            "details": {
-                "parent_model": "",
-                "format": "gguf",
-                "family": self.family,
-                "families": if self.family.is_empty() { json!([]) } else { json!([self.family]) },
-                "parameter_size": self.parameter_size_str,
-                "quantization_level": self.quantization_level
-            },
-            "expires_at": (chrono::Utc::now() + chrono::Duration::minutes(DEFAULT_KEEP_ALIVE_MINUTES)).to_rfc3339(),
-            "size_vram": self.size_bytes
-        })
+        ollama_ps_entry(self)
     }
 
     /// Generate model show response for /api/show
     pub fn to_show_response_legacy(&self) -> Value {
-        let model_info_details = self.create_fabricated_model_info_details_legacy();
-        let capabilities = self.determine_capabilities_legacy();
-
-        json!({
-            "modelfile": format!("# Modelfile for {}\nFROM {} # (Fabricated by proxy)\n\nPARAMETER temperature {}\nPARAMETER top_p {}\nPARAMETER top_k {}\n\nTEMPLATE \"\"\"{{ if .System }}{{ .System }} {{ end }}{{ .Prompt }}\"\"\"",
-                self.ollama_name, self.ollama_name, DEFAULT_TEMPERATURE, DEFAULT_TOP_P, DEFAULT_TOP_K
-            ),
-            "parameters": format!("temperature {}\ntop_p {}\ntop_k {}\nrepeat_penalty {}",
-                DEFAULT_TEMPERATURE, DEFAULT_TOP_P, DEFAULT_TOP_K, DEFAULT_REPEAT_PENALTY),
-            "template": "{{ if .System }}{{ .System }}\\n{{ end }}{{ .Prompt }}",
-            "details": {
-                "parent_model": "",
-                "format": "gguf",
-                "family": self.family,
-                "families": if self.family.is_empty() { json!([]) } else { json!([self.family]) },
-                "parameter_size": self.parameter_size_str,
-                "quantization_level": self.quantization_level
-            },
-            "model_info": model_info_details,
-            "capabilities": capabilities,
-            "digest": format!("{:x}", md5::compute(self.ollama_name.as_bytes())),
-            "size": self.size_bytes,
-            "modified_at": chrono::Utc::now().to_rfc3339()
-        })
+        ollama_show_response(self)
     }
 
     /// Create the nested "model_info" object for /api/show with fabricated details
@@ -254,6 +226,48 @@ impl ModelInfoLegacy {
     }
 }
 
+impl ModelMetadataProvider for ModelInfoLegacy {
+    fn ollama_name(&self) -> &str {
+        &self.ollama_name
+    }
+
+    fn family(&self) -> String {
+        self.family.clone()
+    }
+
+    fn parameter_size(&self) -> String {
+        self.parameter_size_str.clone()
+    }
+
+    fn estimated_size(&self) -> u64 {
+        self.size_bytes
+    }
+
+    fn architecture(&self) -> String {
+        self.architecture.clone()
+    }
+
+    fn capabilities(&self) -> Vec<String> {
+        self.determine_capabilities_legacy()
+    }
+
+    fn quantization_level(&self) -> String {
+        self.quantization_level.clone()
+    }
+
+    fn model_info(&self) -> Value {
+        self.create_fabricated_model_info_details_legacy()
+    }
+
+    fn source_note(&self) -> &'static str {
+        "Fabricated by proxy"
+    }
+
+    fn families(&self) -> Vec<String> {
+        self.determine_families_legacy()
+    }
+}
+
 /// Helper to estimate bytes per parameter based on quantization
 fn estimate_bytes_per_parameter_legacy(quant_level: &str) -> u64 {
     let q_lower = quant_level.to_lowercase();
@@ -453,7 +467,6 @@ fn extract_quantization_level_legacy(name: &str) -> String {
         ("fp16", "F16"),
         ("f32", "F32"),
         ("fp32", "F32"),
-        ("gguf", "GGUF"),
     ];
 
     for (pattern, quant) in QUANT_PATTERNS {
@@ -462,35 +475,23 @@ fn extract_quantization_level_legacy(name: &str) -> String {
         }
     }
     if name.contains("gguf") {
-        "Q4_K_M".to_string()
+        get_runtime_config().default_quantization.clone()
     } else {
         "unknown".to_string()
     }
 }
 
-/// Optimized model name cleaning
+/// Model name cleaning (shares the conservative tag rules of the native resolver)
 pub fn clean_model_name_legacy(name: &str) -> &str {
-    if name.is_empty() {
-        return name;
-    }
-    let after_latest = if let Some(pos) = name.rfind(":latest") {
-        &name[..pos]
-    } else {
-        name
-    };
-    if let Some(colon_pos) = after_latest.rfind(':') {
-        let suffix = &after_latest[colon_pos + 1..];
-        if !suffix.is_empty() && suffix.chars().all(|c| c.is_ascii_digit()) && colon_pos > 0 {
-            return &after_latest[..colon_pos];
-        }
-    }
-    after_latest
+    crate::model::clean_model_name(name)
 }
 
 /// Legacy ModelResolver for handling model resolution with OpenAI-compatible endpoints
 pub struct ModelResolverLegacy {
     lmstudio_url: String,
     cache: Cache<String, String>,
+    pins: HashMap<String, String>,
+    failover: Option<Arc<Failover>>,
 }
 
 impl ModelResolverLegacy {
@@ -499,9 +500,32 @@ impl ModelResolverLegacy {
         Self {
             lmstudio_url,
             cache,
+            pins: HashMap::new(),
+            failover: None,
         }
     }
 
+    /// Force exact resolution for pinned Ollama names (see `parse_model_pins`)
+    pub fn with_pins(mut self, pins: HashMap<String, String>) -> Self {
+        self.pins = pins;
+        self
+    }
+
+    /// Send model lookups through the primary/fallback backend state (--lmstudio-url-fallback)
+    pub fn with_failover(mut self, failover: Option<Arc<Failover>>) -> Self {
+        self.failover = failover;
+        self
+    }
+
+    /// List the ids of all models LM Studio offers
+    pub async fn available_model_ids_legacy(
+        &self,
+        client: &reqwest::Client,
+        cancellation_token: CancellationToken,
+    ) -> Result<Vec<String>, ProxyError> {
+        self.get_available_lm_studio_models_legacy(client, cancellation_token).await
+    }
+
     /// Direct model resolution with fail-fast approach and caching
     pub async fn resolve_model_name_legacy(
         &self,
@@ -509,12 +533,44 @@ impl ModelResolverLegacy {
         client: &reqwest::Client,
         cancellation_token: CancellationToken,
     ) -> Result<String, ProxyError> {
+        self.resolve_model_legacy(ollama_model_name_requested, client, cancellation_token)
+            .await
+            .map(|resolved| resolved.id)
+    }
+
+    /// Resolve from pins or the cache only, without contacting LM Studio
+    pub async fn peek_resolution_legacy(&self, ollama_model_name_requested: &str) -> Option<ResolvedModel> {
+        if let Some(pinned_id) = find_pinned_model(&self.pins, ollama_model_name_requested) {
+            return Some(ResolvedModel::new(pinned_id.to_string(), ResolutionMethod::Alias));
+        }
+        let cleaned_ollama_request = clean_model_name_legacy(ollama_model_name_requested);
+        self.cache
+            .get(cleaned_ollama_request)
+            .await
+            .map(|id| ResolvedModel::new(id, ResolutionMethod::Cache))
+    }
+
+    /// Resolve an Ollama model name, also reporting whether the cache answered
+    pub async fn resolve_model_legacy(
+        &self,
+        ollama_model_name_requested: &str,
+        client: &reqwest::Client,
+        cancellation_token: CancellationToken,
+    ) -> Result<ResolvedModel, ProxyError> {
         let start_time = Instant::now();
+        if let Some(pinned_id) = find_pinned_model(&self.pins, ollama_model_name_requested) {
+            log_timed(LOG_PREFIX_SUCCESS, &format!("Pinned (legacy): '{}' -> '{}'", ollama_model_name_requested, pinned_id), start_time);
+            let resolved = ResolvedModel::new(pinned_id.to_string(), ResolutionMethod::Alias);
+            record_resolved_model(&resolved);
+            return Ok(resolved);
+        }
         let cleaned_ollama_request = clean_model_name_legacy(ollama_model_name_requested).to_string();
 
         if let Some(cached_lm_studio_id) = self.cache.get(&cleaned_ollama_request).await {
             log_timed(LOG_PREFIX_SUCCESS, &format!("Cache hit (legacy): '{}' -> '{}'", cleaned_ollama_request, cached_lm_studio_id), start_time);
-            return Ok(cached_lm_studio_id);
+            let resolved = ResolvedModel::new(cached_lm_studio_id, ResolutionMethod::Cache);
+            record_resolved_model(&resolved);
+            return Ok(resolved);
         }
 
         log_warning("Cache miss", &format!("Fetching '{}' from LM Studio (legacy)", cleaned_ollama_request));
@@ -523,17 +579,24 @@ impl ModelResolverLegacy {
             .get_available_lm_studio_models_legacy(client, cancellation_token)
             .await
         {
+            Ok(available_lm_studio_ids) if available_lm_studio_ids.is_empty() => {
+                Err(ProxyError::not_found(ERROR_NO_MODELS_AVAILABLE))
+            }
             Ok(available_lm_studio_ids) => {
-                if let Some(matched_lm_studio_id) =
+                if let Some((matched_lm_studio_id, method)) =
                     self.find_best_match_legacy(&cleaned_ollama_request, &available_lm_studio_ids)
                 {
                     self.cache
                         .insert(cleaned_ollama_request.clone(), matched_lm_studio_id.clone())
                         .await;
                     log_timed(LOG_PREFIX_SUCCESS, &format!("Resolved (legacy): '{}' -> '{}'", cleaned_ollama_request, matched_lm_studio_id), start_time);
-                    Ok(matched_lm_studio_id)
+                    let resolved = ResolvedModel::new(matched_lm_studio_id, method);
+                    record_resolved_model(&resolved);
+                    Ok(resolved)
                 } else {
-                    Ok(cleaned_ollama_request)
+                    let resolved = ResolvedModel::new(cleaned_ollama_request, ResolutionMethod::Fallback);
+                    record_resolved_model(&resolved);
+                    Ok(resolved)
                 }
             }
             Err(e) => Err(e),
@@ -551,12 +614,19 @@ impl ModelResolverLegacy {
         let temp_context = crate::common::RequestContext {
             client,
             lmstudio_url: &self.lmstudio_url,
+            timeout_seconds: crate::common::model_list_timeout_seconds(),
+            forwarded_headers: reqwest::header::HeaderMap::new(),
+            failover: self.failover.as_deref(),
+            backend_runtime: None,
+            shadow: None,
+            load_triggers: None,
         };
         let request = CancellableRequest::new(temp_context, cancellation_token);
 
         let response = request
             .make_request(reqwest::Method::GET, &url, None::<Value>)
-            .await?;
+            .await
+            .map_err(crate::common::model_list_timeout_error)?;
 
         if !response.status().is_success() {
             return Err(ProxyError::new(
@@ -595,20 +665,26 @@ impl ModelResolverLegacy {
         &self,
         ollama_name_cleaned: &str,
         available_lm_studio_ids: &[String],
-    ) -> Option<String> {
+    ) -> Option<(String, ResolutionMethod)> {
         let lower_ollama = ollama_name_cleaned.to_lowercase();
+        let case_sensitive = get_runtime_config().case_sensitive_match;
 
         for lm_id in available_lm_studio_ids {
-            if lm_id.to_lowercase() == lower_ollama {
-                return Some(lm_id.clone());
+            let is_exact = if case_sensitive {
+                lm_id == ollama_name_cleaned
+            } else {
+                lm_id.to_lowercase() == lower_ollama
+            };
+            if is_exact {
+                return Some((lm_id.clone(), ResolutionMethod::Exact));
             }
         }
 
         for lm_id in available_lm_studio_ids {
-            if lm_id.to_lowercase().contains(&lower_ollama) {
-                if lower_ollama.len() > lm_id.len() / 2 || lower_ollama.len() > 10 {
-                    return Some(lm_id.clone());
-                }
+            if lm_id.to_lowercase().contains(&lower_ollama)
+                && (lower_ollama.len() > lm_id.len() / 2 || lower_ollama.len() > 10)
+            {
+                return Some((lm_id.clone(), ResolutionMethod::Substring));
             }
         }
 
@@ -626,9 +702,9 @@ impl ModelResolverLegacy {
             && ollama_name_cleaned.contains('/')
             && ollama_name_cleaned.to_lowercase().ends_with(".gguf")
         {
-            return Some(ollama_name_cleaned.to_string());
+            return Some((ollama_name_cleaned.to_string(), ResolutionMethod::Fallback));
         }
-        best_match
+        best_match.map(|id| (id, ResolutionMethod::Scored))
     }
 
     /// Enhanced match scoring algorithm
@@ -666,7 +742,7 @@ impl ModelResolverLegacy {
             score += 3;
         }
 
-        let cleaned_lm_name = lm_name.split('/').last().unwrap_or(lm_name);
+        let cleaned_lm_name = lm_name.split('/').next_back().unwrap_or(lm_name);
         if cleaned_lm_name.starts_with(ollama_name) {
             score += ollama_name.len();
         }
@@ -0,0 +1,144 @@
+/// src/model_metadata.rs - Shared Ollama response builders over native and legacy model metadata
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::sync::OnceLock;
+
+use crate::constants::*;
+
+/// Model metadata source used to build Ollama /api/tags, /api/ps and /api/show entries
+pub trait ModelMetadataProvider {
+    /// Ollama-facing name (always tagged, e.g. `model:latest`)
+    fn ollama_name(&self) -> &str;
+    /// Primary Ollama family
+    fn family(&self) -> String;
+    /// Human-readable parameter count such as `7B`
+    fn parameter_size(&self) -> String;
+    /// Estimated model size in bytes
+    fn estimated_size(&self) -> u64;
+    /// Architecture reported as `general.architecture`
+    fn architecture(&self) -> String;
+    /// Ollama capabilities (`completion`, `chat`, `vision`, `embedding`, ...)
+    fn capabilities(&self) -> Vec<String>;
+    /// Quantization level string
+    fn quantization_level(&self) -> String;
+    /// Backend-specific `model_info` object for /api/show
+    fn model_info(&self) -> Value;
+    /// Where the metadata came from, shown in the generated Modelfile
+    fn source_note(&self) -> &'static str;
+
+    /// All Ollama families, primary first
+    fn families(&self) -> Vec<String> {
+        vec![self.family()]
+    }
+
+    /// Model file format
+    fn format(&self) -> String {
+        "gguf".to_string()
+    }
+
+    /// Real chat template reported by the backend, if any
+    fn chat_template(&self) -> Option<String> {
+        None
+    }
+}
+
+/// Generic template used when the backend reports none
+const FALLBACK_TEMPLATE: &str = "{{ if .System }}{{ .System }}\\n{{ end }}{{ .Prompt }}";
+
+fn details<P: ModelMetadataProvider + ?Sized>(provider: &P) -> Value {
+    json!({
+        "parent_model": "",
+        "format": provider.format(),
+        "family": provider.family(),
+        "families": provider.families(),
+        "parameter_size": provider.parameter_size(),
+        "quantization_level": provider.quantization_level()
+    })
+}
+
+/// Stable sha256 digest for a model variant: same name, quantization and size always hash the same
+pub fn model_digest(name: &str, quantization_level: &str, parameter_size: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(name.as_bytes());
+    hasher.update(b"\0");
+    hasher.update(quantization_level.to_lowercase().as_bytes());
+    hasher.update(b"\0");
+    hasher.update(parameter_size.to_lowercase().as_bytes());
+    format!("{:x}", hasher.finalize())
+}
+
+/// When the proxy first listed each model; LM Studio exposes no file times
+static FIRST_SEEN: OnceLock<DashMap<String, DateTime<Utc>>> = OnceLock::new();
+
+/// Stable `modified_at` for a model: the first time this process saw it
+pub fn model_modified_at(name: &str) -> String {
+    let first_seen = FIRST_SEEN.get_or_init(DashMap::new);
+    if let Some(seen) = first_seen.get(name) {
+        return seen.to_rfc3339();
+    }
+    first_seen
+        .entry(name.to_string())
+        .or_insert_with(Utc::now)
+        .to_rfc3339()
+}
+
+fn digest<P: ModelMetadataProvider + ?Sized>(provider: &P) -> String {
+    model_digest(
+        provider.ollama_name(),
+        &provider.quantization_level(),
+        &provider.parameter_size(),
+    )
+}
+
+/// Build an Ollama /api/tags model entry
+pub fn ollama_tags_entry<P: ModelMetadataProvider + ?Sized>(provider: &P) -> Value {
+    json!({
+        "name": provider.ollama_name(),
+        "model": provider.ollama_name(),
+        "modified_at": model_modified_at(provider.ollama_name()),
+        "size": provider.estimated_size(),
+        "digest": digest(provider),
+        "details": details(provider)
+    })
+}
+
+/// Build an Ollama /api/ps running model entry
+pub fn ollama_ps_entry<P: ModelMetadataProvider + ?Sized>(provider: &P) -> Value {
+    let size = provider.estimated_size();
+    json!({
+        "name": provider.ollama_name(),
+        "model": provider.ollama_name(),
+        "size": size,
+        "digest": digest(provider),
+        "details": details(provider),
+        "expires_at": (chrono::Utc::now() + chrono::Duration::minutes(DEFAULT_KEEP_ALIVE_MINUTES)).to_rfc3339(),
+        "size_vram": size
+    })
+}
+
+/// Build an Ollama /api/show response
+pub fn ollama_show_response<P: ModelMetadataProvider + ?Sized>(provider: &P) -> Value {
+    let name = provider.ollama_name();
+    let chat_template = provider.chat_template();
+    let template = chat_template.as_deref().unwrap_or(FALLBACK_TEMPLATE);
+    let mut model_info = provider.model_info();
+    if let (Some(chat_template), Some(info)) = (&chat_template, model_info.as_object_mut()) {
+        info.insert("tokenizer.chat_template".to_string(), json!(chat_template));
+    }
+    json!({
+        "modelfile": format!("# Modelfile for {}\nFROM {} # ({})\n\nPARAMETER temperature {}\nPARAMETER top_p {}\nPARAMETER top_k {}\n\nTEMPLATE \"\"\"{}\"\"\"",
+            name, name, provider.source_note(), DEFAULT_TEMPERATURE, DEFAULT_TOP_P, DEFAULT_TOP_K, template
+        ),
+        "parameters": format!("temperature {}\ntop_p {}\ntop_k {}\nrepeat_penalty {}",
+            DEFAULT_TEMPERATURE, DEFAULT_TOP_P, DEFAULT_TOP_K, DEFAULT_REPEAT_PENALTY),
+        "template": template,
+        "details": details(provider),
+        "model_info": model_info,
+        "capabilities": provider.capabilities(),
+        "digest": digest(provider),
+        "size": provider.estimated_size(),
+        "modified_at": model_modified_at(provider.ollama_name())
+    })
+}
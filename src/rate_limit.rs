@@ -0,0 +1,63 @@
+/// src/rate_limit.rs - Per-model request rate limits (--model-rate-limit)
+use governor::{DefaultDirectRateLimiter, Quota, RateLimiter};
+use std::collections::HashMap;
+use std::num::NonZeroU32;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use crate::constants::{MODEL_RATE_LIMIT_MAX_RPS, MODEL_RATE_LIMIT_MIN_RPS};
+use crate::model::clean_model_name;
+use crate::utils::ProxyError;
+
+static MODEL_LIMITS: OnceLock<HashMap<String, (f64, DefaultDirectRateLimiter)>> = OnceLock::new();
+
+/// Parse `--model-rate-limit <model>=<rps>` values into (lowercased model, requests per second)
+pub fn parse_model_rate_limits(specs: &[String]) -> Result<Vec<(String, f64)>, String> {
+    specs
+        .iter()
+        .map(|spec| {
+            spec.split_once('=')
+                .map(|(name, rps)| (name.trim(), rps.trim().parse::<f64>()))
+                .and_then(|(name, rps)| match rps {
+                    // Outside this range the limiter period under- or overflows
+                    Ok(rps) if !name.is_empty() && (MODEL_RATE_LIMIT_MIN_RPS..=MODEL_RATE_LIMIT_MAX_RPS).contains(&rps) => {
+                        Some((name.to_lowercase(), rps))
+                    }
+                    _ => None,
+                })
+                .ok_or_else(|| {
+                    format!(
+                        "Invalid --model-rate-limit '{}': expected <model>=<requests-per-second> ({} to {})",
+                        spec, MODEL_RATE_LIMIT_MIN_RPS, MODEL_RATE_LIMIT_MAX_RPS
+                    )
+                })
+        })
+        .collect()
+}
+
+/// Install the per-model limiters (once per process); a burst of up to `ceil(rps)` requests is allowed
+pub fn init_model_rate_limits(limits: Vec<(String, f64)>) {
+    let limiters = limits
+        .into_iter()
+        .filter_map(|(name, rps)| {
+            let burst = NonZeroU32::new(rps.ceil().min(u32::MAX as f64) as u32)?;
+            let quota = Quota::with_period(Duration::from_secs_f64(1.0 / rps))?.allow_burst(burst);
+            Some((name, (rps, RateLimiter::direct(quota))))
+        })
+        .collect();
+    let _ = MODEL_LIMITS.set(limiters);
+}
+
+/// Count one request against the limit for this model, matched by requested name, cleaned name or LM Studio id
+pub fn check_model_rate_limit(requested: &str, lm_studio_id: &str) -> Result<(), ProxyError> {
+    let Some(limits) = MODEL_LIMITS.get().filter(|l| !l.is_empty()) else {
+        return Ok(());
+    };
+    let candidates = [requested, clean_model_name(requested), lm_studio_id];
+    let Some((rps, limiter)) = candidates.iter().find_map(|name| limits.get(&name.to_lowercase())) else {
+        return Ok(());
+    };
+    limiter.check().map_err(|_| {
+        ProxyError::too_many_requests(&format!("Rate limit for model '{}' exceeded ({} requests/s)", requested, rps))
+    })
+}
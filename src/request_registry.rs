@@ -0,0 +1,74 @@
+/// src/request_registry.rs - Active request ids for client-initiated cancellation (DELETE /api/requests/{id})
+use dashmap::DashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use tokio_util::sync::CancellationToken;
+
+/// Cancellation tokens of in-flight requests that were sent with an `X-Request-Id` header
+#[derive(Debug, Default)]
+pub struct RequestRegistry {
+    active: DashMap<String, (u64, CancellationToken)>,
+    next_generation: AtomicU64,
+}
+
+impl RequestRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Token for a new request, registered under `request_id` until the returned guard drops.
+    /// A repeated id replaces the older request's entry.
+    pub fn register(self: &Arc<Self>, request_id: Option<&str>) -> (CancellationToken, RequestRegistration) {
+        let token = CancellationToken::new();
+        let entry = request_id
+            .map(str::trim)
+            .filter(|id| !id.is_empty())
+            .map(|id| {
+                let generation = self.next_generation.fetch_add(1, Ordering::Relaxed);
+                self.active.insert(id.to_string(), (generation, token.clone()));
+                (id.to_string(), generation)
+            });
+        let registration = RequestRegistration {
+            registry: self.clone(),
+            entry,
+        };
+        (token, registration)
+    }
+
+    /// Cancel the active request with this id; false if none is running
+    pub fn cancel(&self, request_id: &str) -> bool {
+        match self.active.remove(request_id) {
+            Some((_, (_, token))) => {
+                token.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Number of requests that can currently be cancelled by id
+    pub fn len(&self) -> usize {
+        self.active.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.active.is_empty()
+    }
+}
+
+/// Keeps a request cancellable by id until dropped (after its response body has been sent)
+#[derive(Debug)]
+pub struct RequestRegistration {
+    registry: Arc<RequestRegistry>,
+    entry: Option<(String, u64)>,
+}
+
+impl Drop for RequestRegistration {
+    fn drop(&mut self) {
+        if let Some((id, generation)) = &self.entry {
+            self.registry
+                .active
+                .remove_if(id, |_, (registered, _)| registered == generation);
+        }
+    }
+}
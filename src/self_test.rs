@@ -0,0 +1,215 @@
+/// src/self_test.rs - One-shot end-to-end check of proxy translation against LM Studio (--self-test)
+use serde_json::{json, Value};
+use std::future::Future;
+use std::time::Instant;
+use tokio_util::sync::CancellationToken;
+
+use crate::common::RequestContext;
+use crate::handlers::ollama::{handle_ollama_chat, handle_ollama_embeddings, handle_ollama_tags};
+use crate::handlers::EmbeddingsEndpoint;
+use crate::server::{ModelResolverType, ProxyServer};
+use crate::utils::{format_duration, ProxyError};
+
+/// Result of one self-test step
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StepOutcome {
+    Passed(String),
+    Failed(String),
+    Skipped(String),
+}
+
+/// A finished self-test step with its timing
+#[derive(Debug, Clone)]
+pub struct StepReport {
+    pub name: &'static str,
+    pub outcome: StepOutcome,
+    pub elapsed: std::time::Duration,
+}
+
+impl StepReport {
+    pub fn failed(&self) -> bool {
+        matches!(self.outcome, StepOutcome::Failed(_))
+    }
+}
+
+/// Run list models, resolve, chat and embed through the proxy handlers, printing each result.
+/// Returns an error if any step failed.
+pub async fn run_self_test(server: &ProxyServer) -> Result<(), Box<dyn std::error::Error>> {
+    println!("🧪 | Self-test against {}", server.config.lmstudio_url);
+    let reports = self_test_steps(server, |report| {
+        match &report.outcome {
+            StepOutcome::Passed(detail) => {
+                println!("✅ | {} | {} | {}", report.name, format_duration(report.elapsed), detail)
+            }
+            StepOutcome::Failed(detail) => {
+                println!("❌ | {} | {} | {}", report.name, format_duration(report.elapsed), detail)
+            }
+            StepOutcome::Skipped(detail) => println!("⏭️ | {} | {}", report.name, detail),
+        }
+    })
+        .await;
+
+    let failed = reports.iter().filter(|report| report.failed()).count();
+    if failed > 0 {
+        return Err(format!("Self-test failed: {} of {} steps failed", failed, reports.len()).into());
+    }
+    println!("✅ | Self-test passed");
+    Ok(())
+}
+
+/// Run the self-test steps, calling `on_step` as each one finishes
+pub async fn self_test_steps(server: &ProxyServer, mut on_step: impl FnMut(&StepReport)) -> Vec<StepReport> {
+    let mut reports = Vec::new();
+    let mut record = |report: StepReport| {
+        on_step(&report);
+        reports.push(report);
+    };
+    let resolver = server.resolver().await;
+
+    let (report, models) = timed("List models", async {
+        let body = response_json(handle_ollama_tags(context(server), resolver.clone(), CancellationToken::new(), false).await?).await?;
+        let names: Vec<String> = body["models"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|model| model["name"].as_str().map(str::to_string))
+            .collect();
+        if names.is_empty() {
+            // /api/tags answers an empty list when LM Studio is down; ask directly for the real error
+            resolver.available_model_ids(&server.client, CancellationToken::new()).await?;
+            return Err(ProxyError::not_found("LM Studio lists no models"));
+        }
+        Ok((format!("{} models", names.len()), names))
+    })
+        .await;
+    record(report);
+    let models = models.unwrap_or_default();
+
+    let chat_model = server
+        .config
+        .self_test_model
+        .clone()
+        .or_else(|| models.iter().find(|name| !is_embedding_name(name)).cloned());
+    let embedding_model = server
+        .config
+        .self_test_embedding_model
+        .clone()
+        .or_else(|| models.iter().find(|name| is_embedding_name(name)).cloned());
+
+    match chat_model {
+        Some(chat_model) => {
+            let (report, _) = timed("Resolve model", async {
+                let resolved = resolve(&resolver, &chat_model, &server.client).await?;
+                Ok((format!("{} -> {}", chat_model, resolved), ()))
+            })
+                .await;
+            record(report);
+            record(chat_step(server, &resolver, &chat_model).await);
+        }
+        None => {
+            for name in ["Resolve model", "Chat"] {
+                record(skipped(name, "no chat model available (use --self-test-model)"));
+            }
+        }
+    }
+
+    match embedding_model {
+        Some(embedding_model) => record(embed_step(server, &resolver, &embedding_model).await),
+        None => record(skipped("Embed", "no embedding model found (use --self-test-embedding-model)")),
+    }
+
+    reports
+}
+
+async fn chat_step(server: &ProxyServer, resolver: &ModelResolverType, chat_model: &str) -> StepReport {
+    let (report, _) = timed("Chat", async {
+        let body = json!({
+            "model": chat_model,
+            "messages": [{"role": "user", "content": "Reply with the single word OK."}],
+            "stream": false,
+            "options": {"num_predict": 16}
+        });
+        let response = handle_ollama_chat(context(server), resolver.clone(), body, CancellationToken::new(), &server.config).await?;
+        let body = response_json(response).await?;
+        match body["message"]["content"].as_str() {
+            Some(content) => Ok((format!("{:?}", content.trim()), ())),
+            None => Err(ProxyError::internal_server_error("Chat response has no message content")),
+        }
+    })
+        .await;
+    report
+}
+
+async fn embed_step(server: &ProxyServer, resolver: &ModelResolverType, embedding_model: &str) -> StepReport {
+    let (report, _) = timed("Embed", async {
+        let body = json!({ "model": embedding_model, "input": "self-test" });
+        let response = handle_ollama_embeddings(context(server), resolver.clone(), body, EmbeddingsEndpoint::Embed, CancellationToken::new(), &server.config).await?;
+        let body = response_json(response).await?;
+        match body["embeddings"][0].as_array().map(Vec::len) {
+            Some(dimensions) if dimensions > 0 => Ok((format!("{} ({} dimensions)", embedding_model, dimensions), ())),
+            _ => Err(ProxyError::internal_server_error("Embedding response has no vectors")),
+        }
+    })
+        .await;
+    report
+}
+
+fn context(server: &ProxyServer) -> RequestContext<'_> {
+    RequestContext {
+        client: &server.client,
+        lmstudio_url: &server.config.lmstudio_url,
+        timeout_seconds: None,
+        forwarded_headers: reqwest::header::HeaderMap::new(),
+        failover: server.failover.as_deref(),
+        backend_runtime: Some(server.backend_runtime.clone()),
+        shadow: None,
+        load_triggers: None,
+    }
+}
+
+/// Embedding models are picked by name when none is configured
+fn is_embedding_name(name: &str) -> bool {
+    name.to_lowercase().contains("embed")
+}
+
+async fn resolve(resolver: &ModelResolverType, name: &str, client: &reqwest::Client) -> Result<String, ProxyError> {
+    let resolved = match resolver {
+        ModelResolverType::Native(resolver) => resolver.resolve_model(name, client, CancellationToken::new()).await?,
+        ModelResolverType::Legacy(resolver) => resolver.resolve_model_legacy(name, client, CancellationToken::new()).await?,
+    };
+    Ok(resolved.id)
+}
+
+async fn response_json(response: warp::reply::Response) -> Result<Value, ProxyError> {
+    let body = warp::hyper::body::to_bytes(response.into_body())
+        .await
+        .map_err(|e| ProxyError::internal_server_error(&format!("Failed to read response: {}", e)))?;
+    serde_json::from_slice(&body)
+        .map_err(|e| ProxyError::internal_server_error(&format!("Invalid JSON response: {}", e)))
+}
+
+async fn timed<T>(
+    name: &'static str,
+    step: impl Future<Output = Result<(String, T), ProxyError>>,
+) -> (StepReport, Option<T>) {
+    let start_time = Instant::now();
+    let result = step.await;
+    let (outcome, value) = match result {
+        Ok((detail, value)) => (StepOutcome::Passed(detail), Some(value)),
+        Err(e) => (StepOutcome::Failed(e.message), None),
+    };
+    let report = StepReport {
+        name,
+        outcome,
+        elapsed: start_time.elapsed(),
+    };
+    (report, value)
+}
+
+fn skipped(name: &'static str, reason: &str) -> StepReport {
+    StepReport {
+        name,
+        outcome: StepOutcome::Skipped(format!("skipped: {}", reason)),
+        elapsed: std::time::Duration::ZERO,
+    }
+}
@@ -0,0 +1,63 @@
+/// src/shadow.rs - Fire-and-forget mirroring of chat requests to a shadow backend (--shadow-url)
+use serde_json::Value;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Semaphore;
+
+use crate::constants::*;
+use crate::metrics::ProxyMetrics;
+
+/// Shadow backend that mirrored requests go to, with a cap on how many are in flight
+pub struct Shadow {
+    url: String,
+    client: reqwest::Client,
+    metrics: Arc<ProxyMetrics>,
+    in_flight: Arc<Semaphore>,
+}
+
+impl Shadow {
+    /// Mirror to `url`, recording results in `metrics`
+    pub fn new(url: String, client: reqwest::Client, metrics: Arc<ProxyMetrics>) -> Self {
+        Self::with_max_in_flight(url, client, metrics, SHADOW_MAX_IN_FLIGHT)
+    }
+
+    /// Mirror with at most `max_in_flight` requests outstanding; more are dropped and counted
+    pub fn with_max_in_flight(url: String, client: reqwest::Client, metrics: Arc<ProxyMetrics>, max_in_flight: usize) -> Self {
+        Self {
+            url,
+            client,
+            metrics,
+            in_flight: Arc::new(Semaphore::new(max_in_flight)),
+        }
+    }
+
+    /// Send a copy of an already translated LM Studio request to the shadow backend.
+    /// Returns immediately; the outcome only shows up in the `shadow_*` metrics.
+    pub fn mirror_request(&self, path: &str, body: &Value) {
+        // A slow shadow must not pile up tasks and connections behind primary traffic
+        let Ok(permit) = self.in_flight.clone().try_acquire_owned() else {
+            self.metrics.record_shadow_dropped();
+            return;
+        };
+        let request = self
+            .client
+            .post(format!("{}{}", self.url, path))
+            .timeout(Duration::from_secs(SHADOW_REQUEST_TIMEOUT_SECONDS))
+            .json(body);
+        let metrics = self.metrics.clone();
+
+        tokio::spawn(async move {
+            let start = Instant::now();
+            let status = match request.send().await {
+                // Read the whole body (including streams) so latency covers the full generation
+                Ok(response) => {
+                    let status = response.status().as_u16();
+                    response.bytes().await.ok().map(|_| status)
+                }
+                Err(_) => None,
+            };
+            metrics.record_shadow(start.elapsed(), status);
+            drop(permit);
+        });
+    }
+}
@@ -1,17 +1,24 @@
 /// src/utils.rs - Enhanced centralized logging and utilities with model loading detection
-
 use std::cell::RefCell;
 use std::error::Error;
 use std::fmt::{self, Write};
+use std::fs::{File, OpenOptions};
+use std::io::Write as IoWrite;
+use std::path::PathBuf;
 use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
 use std::time::{Duration, Instant};
 use warp::reject::Reject;
 
 use crate::constants::*;
+use crate::model::ResolvedModel;
 
 // Global logging state
 static LOGGING_ENABLED: AtomicBool = AtomicBool::new(true);
 
+// Optional rotating log file (stdout is used when unset)
+static LOG_FILE: OnceLock<Mutex<RotatingLogFile>> = OnceLock::new();
+
 // Thread-local string buffer for reuse
 thread_local! {
     pub static STRING_BUFFER: RefCell<String> = RefCell::new(String::with_capacity(get_runtime_config().string_buffer_size));
@@ -28,15 +35,133 @@ pub fn is_logging_enabled() -> bool {
     LOGGING_ENABLED.load(Ordering::Relaxed)
 }
 
-/// Centralized logging functions - use these throughout the application
+/// Size-based rotating log file writer
+struct RotatingLogFile {
+    path: PathBuf,
+    file: File,
+    written_bytes: u64,
+    max_bytes: u64,
+    max_files: usize,
+}
+
+impl RotatingLogFile {
+    /// Open (or append to) the log file at the given path
+    fn open(path: PathBuf, max_bytes: u64, max_files: usize) -> std::io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let written_bytes = file.metadata().map(|m| m.len()).unwrap_or(0);
+        Ok(Self {
+            path,
+            file,
+            written_bytes,
+            max_bytes,
+            max_files,
+        })
+    }
+
+    /// Path of the n-th rotated file (`proxy.log.1`, `proxy.log.2`, ...)
+    fn rotated_path(&self, index: usize) -> PathBuf {
+        let mut name = self.path.clone().into_os_string();
+        name.push(format!(".{}", index));
+        PathBuf::from(name)
+    }
+
+    /// Shift rotated files up by one and start a fresh log file
+    fn rotate(&mut self) -> std::io::Result<()> {
+        self.file.flush()?;
+        if self.max_files > 0 {
+            let _ = std::fs::remove_file(self.rotated_path(self.max_files));
+            for index in (1..self.max_files).rev() {
+                let _ = std::fs::rename(self.rotated_path(index), self.rotated_path(index + 1));
+            }
+            std::fs::rename(&self.path, self.rotated_path(1))?;
+        }
+        self.file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .open(&self.path)?;
+        self.written_bytes = 0;
+        Ok(())
+    }
+
+    /// Write a single line, rotating first if it would exceed the size limit
+    fn write_line(&mut self, line: &str) -> std::io::Result<()> {
+        let line_len = line.len() as u64 + 1;
+        if self.written_bytes > 0 && self.written_bytes + line_len > self.max_bytes {
+            self.rotate()?;
+        }
+        writeln!(self.file, "{}", line)?;
+        self.written_bytes += line_len;
+        Ok(())
+    }
+}
+
+/// Route log output to a size-rotated file instead of stdout
+pub fn init_log_file(path: &str, max_size_mb: u64, max_files: usize) -> std::io::Result<()> {
+    let writer = RotatingLogFile::open(
+        PathBuf::from(path),
+        max_size_mb.max(1) * 1024 * 1024,
+        max_files,
+    )?;
+    LOG_FILE.set(Mutex::new(writer)).ok();
+    Ok(())
+}
+
+// Thread-local `HH:MM:SS` for log lines and the Unix second it was formatted for
+thread_local! {
+    static LOG_TIMESTAMP: RefCell<(u64, String)> = const { RefCell::new((u64::MAX, String::new())) };
+}
+
+/// Local `HH:MM:SS` for log lines; the local-time lookup and formatting run at most once per second per thread
+pub fn log_timestamp() -> String {
+    let now_secs = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs());
+    LOG_TIMESTAMP.with_borrow_mut(|(formatted_for, timestamp)| {
+        if *formatted_for != now_secs {
+            *timestamp = chrono::Local::now().format("%H:%M:%S").to_string();
+            *formatted_for = now_secs;
+        }
+        timestamp.clone()
+    })
+}
+
+/// Emit a timestamped log line to the configured log sink
+pub fn write_log_line(message: &str) {
+    let timestamp = log_timestamp();
+    match LOG_FILE.get() {
+        Some(log_file) => {
+            let line = format!("[{}] {}", timestamp, message);
+            match log_file.lock() {
+                Ok(mut writer) => {
+                    if writer.write_line(&line).is_err() {
+                        println!("{}", line);
+                    }
+                }
+                Err(_) => println!("{}", line),
+            }
+        }
+        None => println!("[{}] {}", timestamp, message),
+    }
+}
+
+// Centralized logging functions - use these throughout the application
 
 /// Log informational message
 pub fn log_info(message: &str) {
     if is_logging_enabled() {
-        println!("[{}] ℹ️ {}", chrono::Local::now().format("%H:%M:%S"), sanitize_log_message(message));
+        write_log_line(&format!("ℹ️ {}", sanitize_log_message(message)));
     }
 }
 
+/// Log one line summarizing how an inference request's model was resolved
+pub fn log_model_resolution(endpoint: &str, requested: &str, resolved: &ResolvedModel, api_mode: &str) {
+    log_info(&format!(
+        "Model resolution | endpoint={} requested='{}' resolved='{}' method={} api={}",
+        endpoint, requested, resolved.id, resolved.method.as_str(), api_mode
+    ));
+}
+
 /// Log warning message
 pub fn log_warning(operation: &str, warning: &str) {
     if is_logging_enabled() {
@@ -44,7 +169,7 @@ pub fn log_warning(operation: &str, warning: &str) {
             let mut buffer = buf.borrow_mut();
             buffer.clear();
             write!(buffer, "{} {}: {}", LOG_PREFIX_WARNING, sanitize_log_message(operation), sanitize_log_message(warning)).unwrap();
-            println!("[{}] {}", chrono::Local::now().format("%H:%M:%S"), buffer);
+            write_log_line(&buffer);
         });
     }
 }
@@ -56,14 +181,15 @@ pub fn log_error(operation: &str, error: &str) {
             let mut buffer = buf.borrow_mut();
             buffer.clear();
             write!(buffer, "{} {} failed: {}", LOG_PREFIX_ERROR, sanitize_log_message(operation), sanitize_log_message(error)).unwrap();
-            println!("[{}] {}", chrono::Local::now().format("%H:%M:%S"), buffer);
+            write_log_line(&buffer);
         });
     }
 }
 
 /// Log request with optional model
 pub fn log_request(method: &str, path: &str, model: Option<&str>) {
-    if is_logging_enabled() {
+    // With --slow-request-threshold-ms, requests are logged once they finish, and only if slow
+    if is_logging_enabled() && get_runtime_config().slow_request_threshold_ms == 0 {
         STRING_BUFFER.with(|buf| {
             let mut buffer = buf.borrow_mut();
             buffer.clear();
@@ -71,7 +197,7 @@ pub fn log_request(method: &str, path: &str, model: Option<&str>) {
                 Some(m) => write!(buffer, "{} {} {} (model: {})", LOG_PREFIX_REQUEST, method, sanitize_log_message(path), sanitize_log_message(m)).unwrap(),
                 None => write!(buffer, "{} {} {}", LOG_PREFIX_REQUEST, method, sanitize_log_message(path)).unwrap(),
             }
-            println!("[{}] {}", chrono::Local::now().format("%H:%M:%S"), buffer);
+            write_log_line(&buffer);
         });
     }
 }
@@ -80,11 +206,15 @@ pub fn log_request(method: &str, path: &str, model: Option<&str>) {
 pub fn log_timed(prefix: &str, operation: &str, start: Instant) {
     if is_logging_enabled() {
         let duration = start.elapsed();
+        let threshold_ms = get_runtime_config().slow_request_threshold_ms;
+        if prefix == LOG_PREFIX_SUCCESS && threshold_ms > 0 && duration < Duration::from_millis(threshold_ms) {
+            return;
+        }
         STRING_BUFFER.with(|buf| {
             let mut buffer = buf.borrow_mut();
             buffer.clear();
             write!(buffer, "{} {} | {}", prefix, operation, format_duration(duration)).unwrap();
-            println!("[{}] {}", chrono::Local::now().format("%H:%M:%S"), buffer);
+            write_log_line(&buffer);
         });
     }
 }
@@ -129,6 +259,9 @@ enum ProxyErrorKind {
     BadRequest,
     NotFound,
     NotImplemented,
+    Forbidden,
+    GatewayTimeout,
+    TooManyRequests,
     LMStudioUnavailable,
     ModelLoading,
     Custom,
@@ -180,6 +313,33 @@ impl ProxyError {
         }
     }
 
+    /// Create forbidden error
+    pub fn forbidden(message: &str) -> Self {
+        Self {
+            message: message.to_string(),
+            status_code: 403,
+            kind: ProxyErrorKind::Forbidden,
+        }
+    }
+
+    /// Create rate limit error
+    pub fn too_many_requests(message: &str) -> Self {
+        Self {
+            message: message.to_string(),
+            status_code: 429,
+            kind: ProxyErrorKind::TooManyRequests,
+        }
+    }
+
+    /// Create gateway timeout error
+    pub fn gateway_timeout(message: &str) -> Self {
+        Self {
+            message: message.to_string(),
+            status_code: 504,
+            kind: ProxyErrorKind::GatewayTimeout,
+        }
+    }
+
     /// Create request cancelled error
     pub fn request_cancelled() -> Self {
         Self {
@@ -318,21 +478,70 @@ pub fn format_duration(duration: Duration) -> String {
     }
 }
 
+fn validate_backend_url(label: &str, url: &str) -> Result<(), String> {
+    if !url.starts_with("http://") && !url.starts_with("https://") {
+        return Err(format!("Invalid {} (must start with http:// or https://): {}", label, url));
+    }
+    if let Err(e) = url::Url::parse(url) {
+        return Err(format!("Invalid {} format: {}", label, e));
+    }
+    Ok(())
+}
+
 /// Enhanced config validation
 pub fn validate_config(config: &crate::server::Config) -> Result<(), String> {
     if config.listen.parse::<std::net::SocketAddr>().is_err() {
         return Err(format!("Invalid listen address: {}", config.listen));
     }
-    if !config.lmstudio_url.starts_with("http://") && !config.lmstudio_url.starts_with("https://") {
-        return Err(format!("Invalid LM Studio URL (must start with http:// or https://): {}", config.lmstudio_url));
+    validate_backend_url("LM Studio URL", &config.lmstudio_url)?;
+    if let Some(fallback) = &config.lmstudio_url_fallback {
+        validate_backend_url("fallback LM Studio URL", fallback)?;
+    }
+    if let Some(shadow) = &config.shadow_url {
+        validate_backend_url("shadow URL", shadow)?;
+    }
+    if let Some(ca_cert) = &config.ca_cert {
+        crate::server::load_ca_certificate(ca_cert)?;
+    }
+    if config.worker_threads == Some(0) {
+        return Err("--worker-threads must be at least 1".to_string());
+    }
+    if config.simulate_latency_ms > 0 && !config.dev_mode {
+        return Err("--simulate-latency-ms requires --dev-mode".to_string());
     }
-    if let Err(e) = url::Url::parse(&config.lmstudio_url) {
-        return Err(format!("Invalid LM Studio URL format: {}", e));
+    crate::model::parse_model_pins(&config.pin_model)?;
+    crate::rate_limit::parse_model_rate_limits(&config.model_rate_limit)?;
+    crate::fair_queue::parse_fair_queue_weights(&config.fair_queue_weight)?;
+    crate::fair_queue::parse_trusted_proxies(&config.fair_queue_trusted_proxy)?;
+    if config.fair_queue && config.max_concurrent_requests == 0 {
+        return Err("--fair-queue requires --max-concurrent-requests".to_string());
+    }
+    for name in &config.forward_header {
+        if reqwest::header::HeaderName::from_bytes(name.trim().as_bytes()).is_err() {
+            return Err(format!("Invalid --forward-header name '{}'", name));
+        }
+    }
+    if config.report_ollama_version.trim().is_empty() {
+        return Err("Reported Ollama version must not be empty".to_string());
     }
 
     Ok(())
 }
 
+/// Normalize a request path onto a known Ollama endpoint (case-insensitive, trailing slash tolerated)
+pub fn normalize_endpoint_path(path: &str) -> Option<String> {
+    let trimmed = path.trim_end_matches('/');
+    if trimmed.is_empty() {
+        return None;
+    }
+    let lower = trimmed.to_ascii_lowercase();
+    if lower != path && OLLAMA_ENDPOINTS.contains(&lower.as_str()) {
+        Some(lower)
+    } else {
+        None
+    }
+}
+
 /// Check if endpoint requires authentication
 pub fn is_protected_endpoint(path: &str) -> bool {
     matches!(path, "/admin/*" | "/config/*")
@@ -0,0 +1,32 @@
+mod common;
+
+use clap::Parser;
+use tokio_util::sync::CancellationToken;
+
+use ollama_lmstudio_proxy_rust::server::ApiMode;
+use ollama_lmstudio_proxy_rust::{Config, ModelResolverType, ProxyServer};
+
+use common::serve_legacy_only;
+
+#[tokio::test]
+async fn auto_mode_detects_legacy_before_the_first_model_list() {
+    let url = serve_legacy_only().await;
+    let config = Config::parse_from(["proxy", "--lmstudio-url", &url, "--api-mode", "auto", "--no-log"]);
+    let server = ProxyServer::new(config).unwrap();
+
+    let resolver = server.resolver().await;
+    assert!(matches!(resolver, ModelResolverType::Legacy(_)));
+    assert!(server.legacy_fallback_active());
+    let ids = resolver.available_model_ids(&server.client, CancellationToken::new()).await.unwrap();
+    assert_eq!(ids, vec!["llama-3.2-3b-instruct".to_string()]);
+}
+
+#[test]
+fn legacy_flag_is_the_legacy_api_mode() {
+    let server = ProxyServer::new(Config::parse_from(["proxy", "--legacy", "--no-log"])).unwrap();
+    assert_eq!(server.config.api_mode, ApiMode::Legacy);
+
+    let server = ProxyServer::new(Config::parse_from(["proxy", "--api-mode", "legacy", "--no-log"])).unwrap();
+    assert!(server.config.legacy);
+    assert!(matches!(server.current_resolver(), ModelResolverType::Legacy(_)));
+}
@@ -0,0 +1,38 @@
+mod common;
+
+use std::time::{Duration, Instant};
+use tokio::net::TcpListener;
+
+use ollama_lmstudio_proxy_rust::ModelResolverType;
+
+use common::server;
+
+/// Accept connections but never answer, like an LM Studio stuck loading a model
+async fn serve_hanging() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    tokio::spawn(async move {
+        let mut sockets = Vec::new();
+        loop {
+            let (socket, _) = listener.accept().await.unwrap();
+            sockets.push(socket);
+        }
+    });
+
+    format!("http://{}", addr)
+}
+
+#[tokio::test]
+async fn auto_mode_probe_gives_up_after_the_model_list_timeout() {
+    let url = serve_hanging().await;
+    let server = server(&url, &["--api-mode", "auto", "--model-list-timeout-seconds", "1"]);
+
+    let start = Instant::now();
+    let resolver = tokio::time::timeout(Duration::from_secs(10), server.resolver())
+        .await
+        .expect("API mode probe ignored --model-list-timeout-seconds");
+    assert!(start.elapsed() < Duration::from_secs(5));
+    assert!(matches!(resolver, ModelResolverType::Native(_)));
+    assert!(!server.legacy_fallback_active());
+}
@@ -0,0 +1,25 @@
+mod common;
+
+use clap::Parser;
+use tokio_util::sync::CancellationToken;
+
+use ollama_lmstudio_proxy_rust::{Config, ModelResolverType, ProxyServer};
+
+use common::serve_legacy_only;
+
+#[tokio::test]
+async fn native_404_switches_to_legacy() {
+    let url = serve_legacy_only().await;
+    let config = Config::parse_from(["proxy", "--lmstudio-url", &url, "--auto-legacy-fallback", "--no-log"]);
+    let server = ProxyServer::new(config).unwrap();
+
+    let resolver = server.current_resolver();
+    assert!(matches!(resolver, ModelResolverType::Native(_)));
+    assert!(resolver.available_model_ids(&server.client, CancellationToken::new()).await.is_err());
+
+    let resolver = server.current_resolver();
+    assert!(matches!(resolver, ModelResolverType::Legacy(_)));
+    assert!(server.legacy_fallback_active());
+    let ids = resolver.available_model_ids(&server.client, CancellationToken::new()).await.unwrap();
+    assert_eq!(ids, vec!["llama-3.2-3b-instruct".to_string()]);
+}
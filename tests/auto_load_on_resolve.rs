@@ -0,0 +1,66 @@
+mod common;
+
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use moka::future::Cache;
+use tokio_util::sync::CancellationToken;
+
+use ollama_lmstudio_proxy_rust::model::ModelResolver;
+use ollama_lmstudio_proxy_rust::server::build_http_client;
+
+use common::{serve, Reply};
+
+/// Native LM Studio whose only model reports `not-loaded` until a chat request loads it
+async fn serve_unloaded_model(loaded: Arc<AtomicBool>, triggers: Arc<AtomicUsize>) -> String {
+    serve(move |request| {
+        if request.starts_with("GET /api/v0/models ") {
+            let state = if loaded.load(Ordering::SeqCst) { "loaded" } else { "not-loaded" };
+            Reply::json(format!(
+                r#"{{"object":"list","data":[{{"id":"phi-4","object":"model","type":"llm","publisher":"microsoft","arch":"phi3","compatibility_type":"gguf","quantization":"Q4_K_M","state":"{}","max_context_length":16384}}]}}"#,
+                state
+            ))
+        } else {
+            triggers.fetch_add(1, Ordering::SeqCst);
+            // LM Studio answers the trigger once loading finished; report it loaded a little later
+            let loaded = loaded.clone();
+            tokio::spawn(async move {
+                tokio::time::sleep(Duration::from_millis(300)).await;
+                loaded.store(true, Ordering::SeqCst);
+            });
+            Reply::json("{}")
+        }
+    })
+    .await
+}
+
+#[tokio::test]
+async fn unloaded_match_is_loaded_before_resolving() {
+    let loaded = Arc::new(AtomicBool::new(false));
+    let triggers = Arc::new(AtomicUsize::new(0));
+    let url = serve_unloaded_model(loaded.clone(), triggers.clone()).await;
+    let resolver = ModelResolver::new(url, Cache::new(10)).with_auto_load(Some(Duration::from_secs(5)));
+
+    let client = build_http_client().unwrap();
+    let resolved = resolver.resolve_model("phi-4:latest", &client, CancellationToken::new()).await.unwrap();
+
+    assert_eq!(resolved.id, "phi-4");
+    assert_eq!(triggers.load(Ordering::SeqCst), 1);
+    assert!(loaded.load(Ordering::SeqCst));
+}
+
+#[tokio::test]
+async fn without_auto_load_resolution_does_not_load() {
+    let loaded = Arc::new(AtomicBool::new(false));
+    let triggers = Arc::new(AtomicUsize::new(0));
+    let url = serve_unloaded_model(loaded.clone(), triggers.clone()).await;
+    let resolver = ModelResolver::new(url, Cache::new(10));
+
+    let client = build_http_client().unwrap();
+    let resolved = resolver.resolve_model("phi-4", &client, CancellationToken::new()).await.unwrap();
+
+    assert_eq!(resolved.id, "phi-4");
+    assert_eq!(triggers.load(Ordering::SeqCst), 0);
+    assert!(!loaded.load(Ordering::SeqCst));
+}
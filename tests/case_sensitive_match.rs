@@ -0,0 +1,75 @@
+mod common;
+
+use moka::future::Cache;
+use tokio_util::sync::CancellationToken;
+
+use ollama_lmstudio_proxy_rust::model::NativeModelData;
+use ollama_lmstudio_proxy_rust::server::build_http_client;
+use ollama_lmstudio_proxy_rust::{init_runtime_config, ModelIndex, ModelInfo, ModelResolverLegacy, RuntimeConfig};
+
+use common::{serve, Reply};
+
+fn enable_case_sensitive_match() {
+    init_runtime_config(RuntimeConfig {
+        case_sensitive_match: true,
+        ..RuntimeConfig::default()
+    });
+}
+
+fn model(id: &str) -> ModelInfo {
+    ModelInfo::from_native_data(&NativeModelData {
+        id: id.to_string(),
+        object: "model".to_string(),
+        model_type: "llm".to_string(),
+        publisher: Some("test".to_string()),
+        arch: "qwen2".to_string(),
+        compatibility_type: "gguf".to_string(),
+        quantization: "Q4_K_M".to_string(),
+        state: "loaded".to_string(),
+        max_context_length: 4096,
+        chat_template: None,
+    })
+}
+
+#[test]
+fn exact_match_distinguishes_case() {
+    enable_case_sensitive_match();
+    let index = ModelIndex::new(vec![model("qwen-7b-Custom"), model("qwen-7b-custom")]);
+
+    assert_eq!(index.find_best_match("qwen-7b-Custom").unwrap().id, "qwen-7b-Custom");
+    assert_eq!(index.find_best_match("qwen-7b-custom").unwrap().id, "qwen-7b-custom");
+}
+
+#[test]
+fn fuzzy_fallback_stays_case_insensitive() {
+    enable_case_sensitive_match();
+    let index = ModelIndex::new(vec![model("Qwen2.5-7B-Instruct"), model("llama-3.2-3b")]);
+
+    assert_eq!(index.find_best_match("qwen2.5-7b-instruct").unwrap().id, "Qwen2.5-7B-Instruct");
+    assert_eq!(index.find_best_match("QWEN2.5-7B").unwrap().id, "Qwen2.5-7B-Instruct");
+    assert_eq!(index.find_best_match("LLAMA-3.2-3B").unwrap().id, "llama-3.2-3b");
+}
+
+/// Serve `/v1/models` listing two ids that differ only in case
+async fn serve_case_distinct_models() -> String {
+    serve(|_| {
+        Reply::json(r#"{"object":"list","data":[{"id":"mistral-7b-Tuned","object":"model"},{"id":"mistral-7b-tuned","object":"model"}]}"#)
+    })
+    .await
+}
+
+#[tokio::test]
+async fn legacy_exact_match_distinguishes_case() {
+    enable_case_sensitive_match();
+    let url = serve_case_distinct_models().await;
+    let client = build_http_client().unwrap();
+    let resolver = ModelResolverLegacy::new_legacy(url, Cache::new(10));
+
+    for id in ["mistral-7b-Tuned", "mistral-7b-tuned"] {
+        let resolved = resolver
+            .resolve_model_legacy(&format!("{}:latest", id), &client, CancellationToken::new())
+            .await
+            .unwrap();
+        assert_eq!(resolved.id, id);
+    }
+}
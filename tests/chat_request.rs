@@ -0,0 +1,37 @@
+use serde_json::json;
+
+use ollama_lmstudio_proxy_rust::handlers::{build_lm_studio_request, check_single_choice, LMStudioRequestType};
+
+#[test]
+fn assistant_prefill_is_forwarded_intact() {
+    let messages = json!([
+        {"role": "system", "content": "Answer in JSON."},
+        {"role": "user", "content": "List three colors."},
+        {"role": "assistant", "content": "{\"colors\": ["}
+    ]);
+
+    let request = build_lm_studio_request(
+        "qwen2.5-7b-instruct",
+        LMStudioRequestType::Chat { messages: &messages, stream: false },
+        Some(&json!({"temperature": 0.1})),
+        None,
+        false,
+    );
+
+    assert_eq!(request["messages"], messages);
+    assert_eq!(request["messages"][2]["role"], "assistant");
+    assert_eq!(request["messages"][2]["content"], "{\"colors\": [");
+}
+
+#[test]
+fn multiple_choices_are_rejected() {
+    let error = check_single_choice(&json!({"model": "m", "n": 2})).unwrap_err();
+    assert_eq!(error.status_code, 400);
+    assert!(check_single_choice(&json!({"model": "m", "options": {"n": 3}})).is_err());
+}
+
+#[test]
+fn single_choice_requests_are_accepted() {
+    assert!(check_single_choice(&json!({"model": "m"})).is_ok());
+    assert!(check_single_choice(&json!({"model": "m", "n": 1, "options": {"n": 1}})).is_ok());
+}
@@ -0,0 +1,94 @@
+use ollama_lmstudio_proxy_rust::{clean_model_name, clean_model_name_legacy};
+
+#[test]
+fn strips_latest_tag() {
+    assert_eq!(clean_model_name("llama3:latest"), "llama3");
+    assert_eq!(clean_model_name("user/repo:latest"), "user/repo");
+}
+
+#[test]
+fn strips_numeric_tag() {
+    assert_eq!(clean_model_name("qwen2.5-7b-instruct:2"), "qwen2.5-7b-instruct");
+}
+
+#[test]
+fn keeps_non_tag_colons() {
+    assert_eq!(clean_model_name("org:model:q4"), "org:model:q4");
+    assert_eq!(clean_model_name("org:model:latest"), "org:model:latest");
+    assert_eq!(clean_model_name("org:model:3"), "org:model:3");
+    assert_eq!(clean_model_name("llama3:8b"), "llama3:8b");
+}
+
+#[test]
+fn keeps_latest_that_is_not_a_suffix() {
+    assert_eq!(clean_model_name("model:latest-v2"), "model:latest-v2");
+}
+
+#[test]
+fn legacy_cleaning_matches_native() {
+    for name in ["user/repo:latest", "org:model:q4", "phi3:3", "mistral"] {
+        assert_eq!(clean_model_name_legacy(name), clean_model_name(name));
+    }
+}
+
+#[test]
+fn org_prefixed_ids() {
+    assert_eq!(clean_model_name("org/model:latest"), "org/model");
+    assert_eq!(clean_model_name("org/model:7"), "org/model");
+    assert_eq!(clean_model_name("org/model:q4_0"), "org/model:q4_0");
+    assert_eq!(clean_model_name("lmstudio-community/qwen2.5-7b-instruct@q4_k_m:latest"), "lmstudio-community/qwen2.5-7b-instruct@q4_k_m");
+}
+
+#[test]
+fn numeric_tags() {
+    assert_eq!(clean_model_name("model:7"), "model");
+    assert_eq!(clean_model_name("model:007"), "model");
+    assert_eq!(clean_model_name("model:7b"), "model:7b");
+    assert_eq!(clean_model_name("model:1.5"), "model:1.5");
+    assert_eq!(clean_model_name("model:-1"), "model:-1");
+    // Only ASCII digits count as a numeric tag
+    assert_eq!(clean_model_name("model:٣"), "model:٣");
+}
+
+#[test]
+fn degenerate_names_are_left_alone() {
+    assert_eq!(clean_model_name(""), "");
+    assert_eq!(clean_model_name(":"), ":");
+    assert_eq!(clean_model_name(":latest"), ":latest");
+    assert_eq!(clean_model_name(":7"), ":7");
+    assert_eq!(clean_model_name("model:"), "model:");
+    assert_eq!(clean_model_name("model::latest"), "model::latest");
+    assert_eq!(clean_model_name("model::7"), "model::7");
+    assert_eq!(clean_model_name("-model:latest"), "-model:latest");
+}
+
+#[test]
+fn tag_matching_is_exact() {
+    assert_eq!(clean_model_name("model:LATEST"), "model:LATEST");
+    assert_eq!(clean_model_name("model: latest"), "model: latest");
+    assert_eq!(clean_model_name("model:latest "), "model:latest ");
+}
+
+#[test]
+fn unicode_names_are_not_stripped() {
+    assert_eq!(clean_model_name("模型:latest"), "模型:latest");
+    assert_eq!(clean_model_name("café-7b:latest"), "café-7b:latest");
+    assert_eq!(clean_model_name("llama3:最新"), "llama3:最新");
+    assert_eq!(clean_model_name("modèle"), "modèle");
+}
+
+#[test]
+fn legacy_cleaning_matches_native_on_edge_cases() {
+    for name in [
+        "",
+        ":latest",
+        "model:7",
+        "model::latest",
+        "org/model:q4_0",
+        "org/model:latest",
+        "模型:latest",
+        "café-7b:3",
+    ] {
+        assert_eq!(clean_model_name_legacy(name), clean_model_name(name), "{:?}", name);
+    }
+}
@@ -0,0 +1,181 @@
+//! Helpers shared by the integration tests: mock LM Studio servers, proxy servers and request contexts
+// Each test binary only uses some of these
+#![allow(dead_code)]
+
+use std::sync::Arc;
+
+use clap::Parser;
+use serde_json::Value;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+use ollama_lmstudio_proxy_rust::{Config, ProxyServer, RequestContext};
+
+/// Body of the mock servers' 404 for endpoints a test does not expect
+pub const UNEXPECTED_ENDPOINT: &str = r#"{"error":"Unexpected endpoint"}"#;
+
+/// One response from a mock LM Studio
+pub struct Reply {
+    status: &'static str,
+    content_type: &'static str,
+    body: String,
+}
+
+impl Reply {
+    /// 200 with a JSON body
+    pub fn json(body: impl ToString) -> Self {
+        Self {
+            status: "200 OK",
+            content_type: "application/json",
+            body: body.to_string(),
+        }
+    }
+
+    /// 200 with an SSE body
+    pub fn sse(body: impl Into<String>) -> Self {
+        Self {
+            status: "200 OK",
+            content_type: "text/event-stream",
+            body: body.into(),
+        }
+    }
+
+    /// 404 for an endpoint the test does not expect
+    pub fn not_found() -> Self {
+        Self::json(UNEXPECTED_ENDPOINT).with_status("404 Not Found")
+    }
+
+    /// Same body with another status line, e.g. `"500 Internal Server Error"`
+    pub fn with_status(mut self, status: &'static str) -> Self {
+        self.status = status;
+        self
+    }
+
+    /// The full HTTP/1.1 response, closing the connection
+    pub fn to_http(&self) -> String {
+        format!(
+            "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            self.status,
+            self.content_type,
+            self.body.len(),
+            self.body
+        )
+    }
+}
+
+/// Read a whole request (head and the announced body) as text
+pub async fn read_request(socket: &mut TcpStream) -> String {
+    let mut request = Vec::new();
+    let mut buffer = [0u8; 4096];
+    loop {
+        let read = socket.read(&mut buffer).await.unwrap_or(0);
+        request.extend_from_slice(&buffer[..read]);
+        let text = String::from_utf8_lossy(&request).to_string();
+        if let Some((head, body)) = text.split_once("\r\n\r\n") {
+            let content_length = head
+                .lines()
+                .find_map(|line| line.to_lowercase().strip_prefix("content-length:").map(|v| v.trim().parse().unwrap_or(0)))
+                .unwrap_or(0);
+            if body.len() >= content_length {
+                return text;
+            }
+        }
+        if read == 0 {
+            return text;
+        }
+    }
+}
+
+/// Request line of a raw request, e.g. `POST /api/v0/chat/completions HTTP/1.1`
+pub fn request_line(request: &str) -> &str {
+    request.lines().next().unwrap_or_default()
+}
+
+/// JSON body of a raw request (`Null` when there is none)
+pub fn request_body(request: &str) -> Value {
+    request
+        .split_once("\r\n\r\n")
+        .and_then(|(_, body)| serde_json::from_str(body).ok())
+        .unwrap_or(Value::Null)
+}
+
+/// Mock LM Studio answering every request with `respond(raw request)`; returns the base URL
+pub async fn serve(respond: impl Fn(&str) -> Reply + Send + Sync + 'static) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let respond = Arc::new(respond);
+
+    tokio::spawn(async move {
+        loop {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let respond = respond.clone();
+            tokio::spawn(async move {
+                let request = read_request(&mut socket).await;
+                let _ = socket.write_all(respond(&request).to_http().as_bytes()).await;
+            });
+        }
+    });
+
+    format!("http://{}", addr)
+}
+
+/// Serve one SSE body and return the base URL
+pub async fn serve_sse_once(body: impl Into<String>) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let reply = Reply::sse(body);
+
+    tokio::spawn(async move {
+        let (mut socket, _) = listener.accept().await.unwrap();
+        read_request(&mut socket).await;
+        let _ = socket.write_all(reply.to_http().as_bytes()).await;
+    });
+
+    format!("http://{}", addr)
+}
+
+/// LM Studio without the native API: only `/v1/models` answers, listing `llama-3.2-3b-instruct`
+pub async fn serve_legacy_only() -> String {
+    serve(|request| {
+        if request.starts_with("GET /v1/models ") {
+            Reply::json(r#"{"object":"list","data":[{"id":"llama-3.2-3b-instruct","object":"model"}]}"#)
+        } else {
+            Reply::not_found()
+        }
+    })
+    .await
+}
+
+/// Base URL of a port nothing listens on
+pub async fn unreachable_url() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    drop(listener);
+    format!("http://{}", addr)
+}
+
+/// Proxy for the LM Studio at `url`, without logging, with `args` added to the command line
+pub fn server(url: &str, args: &[&str]) -> ProxyServer {
+    let mut argv = vec!["proxy", "--lmstudio-url", url, "--no-log"];
+    argv.extend_from_slice(args);
+    ProxyServer::new(Config::parse_from(argv)).unwrap()
+}
+
+/// Request context as the server's routes build it, without client headers or timeout
+pub fn context(server: &ProxyServer) -> RequestContext<'_> {
+    server.request_context(None, reqwest::header::HeaderMap::new())
+}
+
+/// Request context with no server state, for handlers called without a `ProxyServer`
+pub fn bare_context<'a>(client: &'a reqwest::Client, lmstudio_url: &'a str) -> RequestContext<'a> {
+    RequestContext {
+        client,
+        lmstudio_url,
+        timeout_seconds: None,
+        forwarded_headers: reqwest::header::HeaderMap::new(),
+        failover: None,
+        backend_runtime: None,
+        shadow: None,
+        load_triggers: None,
+    }
+}
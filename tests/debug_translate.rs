@@ -0,0 +1,58 @@
+mod common;
+
+use serde_json::json;
+
+use ollama_lmstudio_proxy_rust::handlers::{handle_debug_translate, prepare_inference_body};
+use ollama_lmstudio_proxy_rust::ProxyServer;
+
+use common::{server, unreachable_url};
+
+/// Dev-mode proxy whose LM Studio is unreachable: translation never talks to it
+async fn dev_server(args: &[&str]) -> ProxyServer {
+    let mut argv = vec!["--dev-mode"];
+    argv.extend_from_slice(args);
+    server(&unreachable_url().await, &argv)
+}
+
+#[tokio::test]
+async fn translation_runs_the_same_preparation_as_a_real_request() {
+    let server = dev_server(&["--draft-model", "qwen2.5:latest"]).await;
+    let mut body = json!({
+        "model": "llama3.2:3b",
+        "messages": [{"role": "user", "content": "hi"}],
+        "top_p": 3.0,
+        "options": {"num_predict": 4096}
+    });
+    prepare_inference_body(&mut body, Some("256"), server.config.strict_params).unwrap();
+
+    let translated = handle_debug_translate(server.current_resolver(), body, &server.config).await.unwrap();
+    assert_eq!(translated["ollama_endpoint"], "/api/chat");
+    assert_eq!(translated["resolution"], "unresolved");
+    let request = &translated["request"];
+    // Top-level top_p merged and clamped, X-Max-Tokens applied, configured draft model added
+    assert_eq!(request["top_p"], 1.0);
+    assert_eq!(request["max_completion_tokens"], 256);
+    assert_eq!(request["draft_model"], "qwen2.5");
+}
+
+#[tokio::test]
+async fn strict_params_and_multiple_choices_are_rejected_like_real_requests() {
+    let server = dev_server(&["--strict-params"]).await;
+    let mut body = json!({"model": "llama3", "prompt": "hi", "options": {"top_p": 3.0}});
+    let error = prepare_inference_body(&mut body, None, server.config.strict_params).unwrap_err();
+    assert_eq!(error.status_code, 400);
+
+    let body = json!({"model": "llama3", "prompt": "hi", "n": 2});
+    let error = handle_debug_translate(server.current_resolver(), body, &server.config).await.unwrap_err();
+    assert_eq!(error.status_code, 400);
+}
+
+#[tokio::test]
+async fn generate_with_images_goes_to_the_chat_endpoint() {
+    let server = dev_server(&[]).await;
+    let body = json!({"model": "llava", "prompt": "describe", "images": ["aGk="]});
+    let translated = handle_debug_translate(server.current_resolver(), body, &server.config).await.unwrap();
+    assert_eq!(translated["ollama_endpoint"], "/api/generate");
+    assert!(translated["url"].as_str().unwrap().ends_with("/chat/completions"));
+    assert_eq!(translated["request"]["messages"][0]["content"], "describe");
+}
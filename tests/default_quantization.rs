@@ -0,0 +1,58 @@
+mod common;
+
+use serde_json::{json, Value};
+use tokio_util::sync::CancellationToken;
+
+use ollama_lmstudio_proxy_rust::handlers::handle_ollama_show;
+use ollama_lmstudio_proxy_rust::model_legacy::ModelInfoLegacy;
+use ollama_lmstudio_proxy_rust::{ModelResolverType, ProxyServer};
+
+use common::{context, serve, server, Reply};
+
+const NATIVE_MODELS: &str = r#"{"object":"list","data":[
+    {"id":"qwen2.5-7b-instruct","object":"model","type":"llm","publisher":"qwen","arch":"qwen2","compatibility_type":"gguf","quantization":"Q8_0","state":"loaded","max_context_length":32768},
+    {"id":"phi-4","object":"model","type":"llm","publisher":"microsoft","arch":"phi3","compatibility_type":"gguf","quantization":"","state":"not-loaded","max_context_length":16384}
+]}"#;
+
+/// LM Studio answering `/api/v0/models` with one quantized model and one without a quantization
+async fn serve_native_models() -> String {
+    serve(|request| {
+        if request.starts_with("GET /api/v0/models ") {
+            Reply::json(NATIVE_MODELS)
+        } else {
+            Reply::not_found()
+        }
+    })
+    .await
+}
+
+async fn show_quantization(server: &ProxyServer, model: &str) -> String {
+    let context = context(server);
+    let response = handle_ollama_show(context, json!({ "model": model }), server.current_resolver(), CancellationToken::new())
+        .await
+        .unwrap();
+    let body = warp::hyper::body::to_bytes(response.into_body()).await.unwrap();
+    let body: Value = serde_json::from_slice(&body).unwrap();
+    body["details"]["quantization_level"].as_str().unwrap().to_string()
+}
+
+#[tokio::test]
+async fn native_show_prefers_lm_studio_quantization() {
+    let url = serve_native_models().await;
+    let server = server(&url, &["--default-quantization", "F16"]);
+    assert!(matches!(server.current_resolver(), ModelResolverType::Native(_)));
+
+    assert_eq!(show_quantization(&server, "qwen2.5-7b-instruct:latest").await, "Q8_0");
+    assert_eq!(show_quantization(&server, "phi-4").await, "F16");
+    assert_eq!(show_quantization(&server, "totally-unknown-model").await, "F16");
+}
+
+#[tokio::test]
+async fn legacy_uses_default_only_for_unlabelled_gguf() {
+    let url = serve_native_models().await;
+    let _server = server(&url, &["--default-quantization", "F16", "--legacy"]);
+
+    assert_eq!(ModelInfoLegacy::from_lm_studio_id_legacy("llama-3-8b-gguf").quantization_level, "F16");
+    assert_eq!(ModelInfoLegacy::from_lm_studio_id_legacy("llama-3-8b-q5_k_m-gguf").quantization_level, "Q5_K_M");
+    assert_eq!(ModelInfoLegacy::from_lm_studio_id_legacy("mistral-7b-mlx").quantization_level, "unknown");
+}
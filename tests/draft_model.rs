@@ -0,0 +1,84 @@
+mod common;
+
+use std::sync::{Arc, Mutex};
+
+use serde_json::{json, Value};
+use tokio_util::sync::CancellationToken;
+
+use ollama_lmstudio_proxy_rust::handlers::ollama::{handle_ollama_chat, handle_ollama_generate};
+use ollama_lmstudio_proxy_rust::handlers::requested_draft_model;
+
+use common::{context, request_body, serve, server, Reply};
+
+const NATIVE_MODELS: &str = r#"{"object":"list","data":[
+    {"id":"qwen2.5-7b-instruct","object":"model","type":"llm","publisher":"qwen","arch":"qwen2","compatibility_type":"gguf","quantization":"Q4_K_M","state":"loaded","max_context_length":32768},
+    {"id":"qwen2.5-0.5b-instruct","object":"model","type":"llm","publisher":"qwen","arch":"qwen2","compatibility_type":"gguf","quantization":"Q8_0","state":"loaded","max_context_length":32768}
+]}"#;
+
+/// Native LM Studio that records inference request bodies
+async fn serve_recording(requests: Arc<Mutex<Vec<Value>>>) -> String {
+    serve(move |request| {
+        if request.starts_with("GET /api/v0/models ") {
+            return Reply::json(NATIVE_MODELS);
+        }
+        requests.lock().unwrap().push(request_body(request));
+        Reply::json(json!({"choices": [{"message": {"role": "assistant", "content": "hi"}, "text": "hi", "finish_reason": "stop"}]}))
+    })
+    .await
+}
+
+#[test]
+fn request_draft_model_overrides_the_configured_one() {
+    let options = json!({"draft_model": "qwen2.5:0.5b"});
+    assert_eq!(requested_draft_model(Some(&options), Some("fallback")), Some("qwen2.5:0.5b"));
+    assert_eq!(requested_draft_model(Some(&json!({})), Some("fallback")), Some("fallback"));
+    assert_eq!(requested_draft_model(Some(&json!({"draft_model": " "})), None), None);
+    assert_eq!(requested_draft_model(None, None), None);
+}
+
+#[tokio::test]
+async fn draft_model_is_resolved_and_forwarded() {
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let url = serve_recording(requests.clone()).await;
+    let server = server(&url, &[]);
+
+    let body = json!({
+        "model": "qwen2.5-7b-instruct",
+        "stream": false,
+        "options": {"draft_model": "qwen2.5-0.5b-instruct:latest"},
+        "messages": [{"role": "user", "content": "hi"}]
+    });
+    handle_ollama_chat(context(&server), server.current_resolver(), body, CancellationToken::new(), &server.config)
+        .await
+        .unwrap();
+
+    let requests = requests.lock().unwrap();
+    assert_eq!(requests[0]["model"], "qwen2.5-7b-instruct");
+    assert_eq!(requests[0]["draft_model"], "qwen2.5-0.5b-instruct");
+}
+
+#[tokio::test]
+async fn configured_draft_model_applies_and_unknown_ones_are_skipped() {
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let url = serve_recording(requests.clone()).await;
+    let server = server(&url, &["--draft-model", "qwen2.5-0.5b-instruct"]);
+
+    let body = json!({"model": "qwen2.5-7b-instruct", "stream": false, "prompt": "hi"});
+    handle_ollama_generate(context(&server), server.current_resolver(), body, CancellationToken::new(), &server.config)
+        .await
+        .unwrap();
+
+    let body = json!({
+        "model": "qwen2.5-7b-instruct",
+        "stream": false,
+        "prompt": "hi",
+        "options": {"draft_model": "no-such-draft"}
+    });
+    handle_ollama_generate(context(&server), server.current_resolver(), body, CancellationToken::new(), &server.config)
+        .await
+        .unwrap();
+
+    let requests = requests.lock().unwrap();
+    assert_eq!(requests[0]["draft_model"], "qwen2.5-0.5b-instruct");
+    assert!(requests[1].get("draft_model").is_none(), "{}", requests[1]);
+}
@@ -0,0 +1,83 @@
+mod common;
+
+use std::time::Instant;
+
+use serde_json::{json, Value};
+use tokio_util::sync::CancellationToken;
+
+use ollama_lmstudio_proxy_rust::handlers::{
+    handle_streaming_response, strip_think_tags, ResponseTransformer, ThinkTagFilter,
+};
+use ollama_lmstudio_proxy_rust::server::build_http_client;
+use ollama_lmstudio_proxy_rust::{init_runtime_config, RuntimeConfig};
+
+use common::serve_sse_once;
+
+fn enable_drop_reasoning() {
+    init_runtime_config(RuntimeConfig {
+        drop_reasoning: true,
+        ..RuntimeConfig::default()
+    });
+}
+
+#[test]
+fn filter_handles_tags_split_across_deltas() {
+    let mut filter = ThinkTagFilter::new();
+    let mut output = String::new();
+    for piece in ["<th", "ink>let me ", "reason</thi", "nk>\n\nThe ", "answer <", "b>"] {
+        output.push_str(&filter.push(piece));
+    }
+    output.push_str(&filter.finish());
+    assert_eq!(output, "The answer <b>");
+}
+
+#[test]
+fn text_without_tags_is_unchanged() {
+    assert_eq!(strip_think_tags("plain answer < 3"), "plain answer < 3");
+    assert_eq!(strip_think_tags("<think>unterminated"), "");
+}
+
+#[test]
+fn non_streaming_chat_has_no_reasoning() {
+    enable_drop_reasoning();
+    let lm_response = json!({
+        "choices": [{"message": {
+            "content": "<think>hidden</think>\nParis",
+            "reasoning_content": "The capital of France..."
+        }}]
+    });
+    let ollama = ResponseTransformer::convert_to_ollama_chat(&lm_response, "m:latest", 1, Instant::now(), false);
+    assert_eq!(ollama["message"]["content"], "Paris");
+}
+
+#[tokio::test]
+async fn streaming_chat_has_no_reasoning() {
+    enable_drop_reasoning();
+    let deltas = [
+        json!({"reasoning_content": "The capital"}),
+        json!({"content": "<think>of France"}),
+        json!({"content": " is...</think>"}),
+        json!({"content": "\n\nPar"}),
+        json!({"content": "is"}),
+    ];
+    let mut body = String::new();
+    for delta in deltas {
+        body.push_str(&format!("data: {}\n\n", json!({"choices": [{"delta": delta}]})));
+    }
+    body.push_str("data: [DONE]\n\n");
+    let url = serve_sse_once(body).await;
+
+    let response = build_http_client().unwrap().get(&url).send().await.unwrap();
+    let reply = handle_streaming_response(response, true, "m:latest", "now", Instant::now(), CancellationToken::new(), 5, None)
+        .await
+        .unwrap();
+    let bytes = warp::hyper::body::to_bytes(reply.into_body()).await.unwrap();
+
+    let content: String = String::from_utf8(bytes.to_vec())
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str::<Value>(line).unwrap())
+        .filter_map(|chunk| chunk["message"]["content"].as_str().map(str::to_string))
+        .collect();
+    assert_eq!(content, "Paris");
+}
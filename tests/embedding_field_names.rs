@@ -0,0 +1,55 @@
+mod common;
+
+use serde_json::{json, Value};
+use tokio_util::sync::CancellationToken;
+
+use ollama_lmstudio_proxy_rust::handlers::{handle_ollama_embeddings, EmbeddingsEndpoint};
+use ollama_lmstudio_proxy_rust::ProxyServer;
+
+use common::{context, serve, server, Reply};
+
+const NATIVE_MODELS: &str = r#"{"object":"list","data":[
+    {"id":"text-embedding-nomic-embed-text-v1.5","object":"model","type":"embeddings","publisher":"nomic-ai","arch":"nomic-bert","compatibility_type":"gguf","quantization":"Q4_K_M","state":"loaded","max_context_length":2048}
+]}"#;
+
+/// Native LM Studio whose embeddings model returns one 3-dimensional vector
+async fn serve_native_embeddings() -> String {
+    serve(|request| {
+        if request.starts_with("GET /api/v0/models ") {
+            Reply::json(NATIVE_MODELS)
+        } else if request.starts_with("POST /api/v0/embeddings ") {
+            Reply::json(json!({"object": "list", "data": [{"object": "embedding", "embedding": [0.1, 0.2, 0.3], "index": 0}]}))
+        } else {
+            Reply::not_found()
+        }
+    })
+    .await
+}
+
+async fn embed(server: &ProxyServer, endpoint: EmbeddingsEndpoint, body: Value) -> Value {
+    let context = context(server);
+    let response = handle_ollama_embeddings(context, server.current_resolver(), body, endpoint, CancellationToken::new(), &server.config)
+        .await
+        .unwrap();
+    let body = warp::hyper::body::to_bytes(response.into_body()).await.unwrap();
+    serde_json::from_slice(&body).unwrap()
+}
+
+#[tokio::test]
+async fn each_endpoint_gets_its_own_field_name() {
+    let url = serve_native_embeddings().await;
+    let server = server(&url, &[]);
+    let model = "text-embedding-nomic-embed-text-v1.5";
+
+    let body = embed(&server, EmbeddingsEndpoint::Embed, json!({"model": model, "input": "hello"})).await;
+    assert_eq!(body["embeddings"], json!([[0.1, 0.2, 0.3]]));
+    assert!(body.get("embedding").is_none());
+
+    let body = embed(&server, EmbeddingsEndpoint::Embeddings, json!({"model": model, "prompt": "hello"})).await;
+    assert_eq!(body["embedding"], json!([0.1, 0.2, 0.3]));
+    assert!(body.get("embeddings").is_none());
+
+    // An array input keeps the array form even on the older endpoint
+    let body = embed(&server, EmbeddingsEndpoint::Embeddings, json!({"model": model, "input": ["hello"]})).await;
+    assert_eq!(body["embeddings"], json!([[0.1, 0.2, 0.3]]));
+}
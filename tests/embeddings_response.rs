@@ -0,0 +1,74 @@
+mod common;
+
+use std::time::Instant;
+
+use ollama_lmstudio_proxy_rust::handlers::{handle_ollama_embeddings, EmbeddingsEndpoint, ResponseTransformer};
+use serde_json::json;
+use tokio_util::sync::CancellationToken;
+
+use common::{context, server};
+
+#[test]
+fn empty_data_for_non_empty_input_is_bad_gateway() {
+    let lm_response = json!({ "object": "list", "data": [] });
+    for input in [json!("hello"), json!(["a", "b"])] {
+        let error = ResponseTransformer::convert_to_ollama_embeddings(
+            &lm_response,
+            "nomic-embed-text",
+            &input,
+            Instant::now(),
+            false,
+        )
+        .expect_err("empty embeddings must not be reported as success");
+        assert_eq!(error.status_code, 502);
+    }
+}
+
+#[test]
+fn empty_data_for_empty_input_is_allowed() {
+    let lm_response = json!({ "object": "list", "data": [] });
+    let response = ResponseTransformer::convert_to_ollama_embeddings(
+        &lm_response,
+        "nomic-embed-text",
+        &json!([]),
+        Instant::now(),
+        false,
+    )
+    .unwrap();
+    assert_eq!(response["embeddings"], json!([]));
+}
+
+#[test]
+fn embeddings_are_returned_in_order() {
+    let lm_response = json!({
+        "object": "list",
+        "data": [
+            { "object": "embedding", "index": 0, "embedding": [0.1, 0.2] },
+            { "object": "embedding", "index": 1, "embedding": [0.3, 0.4] }
+        ]
+    });
+    let response = ResponseTransformer::convert_to_ollama_embeddings(
+        &lm_response,
+        "nomic-embed-text",
+        &json!(["a", "b"]),
+        Instant::now(),
+        true,
+    )
+    .unwrap();
+    assert_eq!(response["embeddings"], json!([[0.1, 0.2], [0.3, 0.4]]));
+}
+
+#[tokio::test]
+async fn streaming_embeddings_request_is_rejected() {
+    // Nothing listens here: the request must be rejected before LM Studio is contacted
+    let server = server("http://127.0.0.1:1", &[]);
+    let context = context(&server);
+    let body = json!({"model": "nomic-embed-text", "input": "hello", "stream": true});
+
+    let error = handle_ollama_embeddings(context, server.current_resolver(), body, EmbeddingsEndpoint::Embed, CancellationToken::new(), &server.config)
+        .await
+        .unwrap_err();
+
+    assert_eq!(error.status_code, 400);
+    assert!(error.message.contains("cannot be streamed"));
+}
@@ -0,0 +1,71 @@
+mod common;
+
+use std::time::{Duration, Instant};
+
+use moka::future::Cache;
+use serde_json::json;
+use tokio_util::sync::CancellationToken;
+
+use ollama_lmstudio_proxy_rust::handlers::handle_ollama_chat;
+use ollama_lmstudio_proxy_rust::server::build_http_client;
+use ollama_lmstudio_proxy_rust::{ModelResolver, ModelResolverLegacy};
+
+use common::{context, serve, server, Reply};
+
+const NO_MODELS: &str = "LM Studio has no models available; load a model in LM Studio first";
+
+/// LM Studio answering both model list endpoints with an empty `data` array
+async fn serve_empty_model_list() -> String {
+    serve(|request| {
+        if request.starts_with("GET /api/v0/models ") || request.starts_with("GET /v1/models ") {
+            Reply::json(r#"{"object":"list","data":[]}"#)
+        } else {
+            Reply::not_found()
+        }
+    })
+    .await
+}
+
+#[tokio::test]
+async fn native_resolution_reports_empty_model_list() {
+    let url = serve_empty_model_list().await;
+    let resolver = ModelResolver::new(url, Cache::new(10));
+
+    let error = resolver
+        .resolve_model("qwen2.5-7b-instruct:latest", &build_http_client().unwrap(), CancellationToken::new())
+        .await
+        .unwrap_err();
+
+    assert_eq!(error.status_code, 404);
+    assert_eq!(error.message, NO_MODELS);
+}
+
+#[tokio::test]
+async fn legacy_resolution_reports_empty_model_list() {
+    let url = serve_empty_model_list().await;
+    let resolver = ModelResolverLegacy::new_legacy(url, Cache::new(10));
+
+    let error = resolver
+        .resolve_model_legacy("qwen2.5-7b-instruct:latest", &build_http_client().unwrap(), CancellationToken::new())
+        .await
+        .unwrap_err();
+
+    assert_eq!(error.status_code, 404);
+    assert_eq!(error.message, NO_MODELS);
+}
+
+#[tokio::test]
+async fn chat_fails_fast_without_waiting_for_a_load() {
+    let url = serve_empty_model_list().await;
+    let server = server(&url, &[]);
+    let context = context(&server);
+    let body = json!({"model": "qwen2.5-7b-instruct", "stream": false, "messages": [{"role": "user", "content": "hi"}]});
+
+    let start = Instant::now();
+    let error = handle_ollama_chat(context, server.current_resolver(), body, CancellationToken::new(), &server.config)
+        .await
+        .unwrap_err();
+
+    assert_eq!(error.message, NO_MODELS);
+    assert!(start.elapsed() < Duration::from_secs(2));
+}
@@ -0,0 +1,80 @@
+mod common;
+
+use serde_json::json;
+use tokio_util::sync::CancellationToken;
+
+use ollama_lmstudio_proxy_rust::handlers::ollama::{handle_ollama_chat, handle_ollama_embeddings};
+use ollama_lmstudio_proxy_rust::handlers::EmbeddingsEndpoint;
+use ollama_lmstudio_proxy_rust::{
+    LM_STUDIO_LEGACY_CHAT, LM_STUDIO_LEGACY_COMPLETIONS, LM_STUDIO_LEGACY_EMBEDDINGS, LM_STUDIO_LEGACY_MODELS,
+    LM_STUDIO_NATIVE_CHAT, LM_STUDIO_NATIVE_COMPLETIONS, LM_STUDIO_NATIVE_EMBEDDINGS, LM_STUDIO_NATIVE_MODELS,
+};
+
+use common::{context, request_line, serve, server, Reply};
+
+const NATIVE_MODELS: &str = r#"{"object":"list","data":[
+    {"id":"qwen2.5-7b-instruct","object":"model","type":"llm","publisher":"qwen","arch":"qwen2","compatibility_type":"gguf","quantization":"Q4_K_M","state":"loaded","max_context_length":32768},
+    {"id":"text-embedding-nomic-embed-text-v1.5","object":"model","type":"embeddings","publisher":"nomic-ai","arch":"nomic-bert","compatibility_type":"gguf","quantization":"Q4_K_M","state":"loaded","max_context_length":2048}
+]}"#;
+
+/// LM Studio build that lists models natively but serves no native inference endpoints
+async fn serve_models_without_inference() -> String {
+    serve(|request| {
+        if request.starts_with("GET /api/v0/models ") {
+            return Reply::json(NATIVE_MODELS);
+        }
+        let line = request_line(request);
+        let target = line.rsplit_once(' ').map_or(line, |(target, _)| target);
+        Reply::json(json!({"error": format!("Unexpected endpoint or method. ({})", target)})).with_status("404 Not Found")
+    })
+    .await
+}
+
+#[tokio::test]
+async fn missing_chat_endpoint_suggests_legacy() {
+    let url = serve_models_without_inference().await;
+    let server = server(&url, &[]);
+
+    for stream in [false, true] {
+        let body = json!({"model": "qwen2.5-7b-instruct", "stream": stream, "messages": [{"role": "user", "content": "hi"}]});
+        let error = handle_ollama_chat(context(&server), server.current_resolver(), body, CancellationToken::new(), &server.config)
+            .await
+            .unwrap_err();
+
+        assert_eq!(error.status_code, 404);
+        assert!(error.message.contains("/api/v0/chat/completions"), "{}", error.message);
+        assert!(error.message.contains("--legacy"), "{}", error.message);
+    }
+}
+
+#[tokio::test]
+async fn missing_embeddings_endpoint_suggests_legacy() {
+    let url = serve_models_without_inference().await;
+    let server = server(&url, &[]);
+
+    let body = json!({"model": "text-embedding-nomic-embed-text-v1.5", "input": "hi"});
+    let error = handle_ollama_embeddings(context(&server), server.current_resolver(), body, EmbeddingsEndpoint::Embed, CancellationToken::new(), &server.config)
+        .await
+        .unwrap_err();
+
+    assert_eq!(error.status_code, 404);
+    assert!(error.message.contains("--legacy"), "{}", error.message);
+}
+
+#[test]
+fn endpoint_constants_match_documented_paths() {
+    let native_docs = include_str!("../src/docs/lmstudio-api-new.md");
+    for (method, path) in [
+        ("GET", LM_STUDIO_NATIVE_MODELS),
+        ("POST", LM_STUDIO_NATIVE_CHAT),
+        ("POST", LM_STUDIO_NATIVE_COMPLETIONS),
+        ("POST", LM_STUDIO_NATIVE_EMBEDDINGS),
+    ] {
+        assert!(native_docs.contains(&format!("`{} {}`", method, path)), "{} {} is not documented", method, path);
+    }
+
+    let legacy_docs = include_str!("../src/docs/lmstudio-api.md");
+    for path in [LM_STUDIO_LEGACY_MODELS, LM_STUDIO_LEGACY_CHAT, LM_STUDIO_LEGACY_COMPLETIONS, LM_STUDIO_LEGACY_EMBEDDINGS] {
+        assert!(legacy_docs.contains(&format!("### `{}`", path)), "{} is not documented", path);
+    }
+}
@@ -0,0 +1,64 @@
+mod common;
+
+use serde_json::Value;
+use tokio_util::sync::CancellationToken;
+
+use ollama_lmstudio_proxy_rust::common::CancellableRequest;
+use ollama_lmstudio_proxy_rust::failover::Failover;
+use ollama_lmstudio_proxy_rust::server::build_http_client;
+use ollama_lmstudio_proxy_rust::RequestContext;
+
+use common::{bare_context, serve, unreachable_url, Reply};
+
+#[tokio::test]
+async fn connection_errors_fail_over_until_the_primary_recovers() {
+    let primary = unreachable_url().await;
+    let fallback = serve(|_| Reply::json(r#"{"data":[{"id":"fallback-model"}]}"#)).await;
+    let failover = Failover::new(primary.clone(), fallback.clone());
+    assert_eq!(failover.active_backend(), primary);
+
+    let client = build_http_client().unwrap();
+    let context = RequestContext {
+        failover: Some(&failover),
+        ..bare_context(&client, &primary)
+    };
+    let url = format!("{}/v1/models", primary);
+
+    let response = CancellableRequest::new(context.clone(), CancellationToken::new())
+        .make_request(reqwest::Method::GET, &url, None::<Value>)
+        .await
+        .unwrap();
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["data"][0]["id"], "fallback-model");
+    assert_eq!(failover.active_backend(), fallback);
+    assert_eq!(failover.route_url(&url), format!("{}/v1/models", fallback));
+
+    // Probes bypass failover so the primary's own state stays visible
+    let probe = CancellableRequest::new(context, CancellationToken::new())
+        .without_failover()
+        .make_request(reqwest::Method::GET, &url, None::<Value>)
+        .await;
+    assert!(probe.is_err());
+
+    failover.record_primary_reachable(true);
+    assert_eq!(failover.active_backend(), primary);
+    assert_eq!(failover.route_url(&url), url);
+}
+
+#[test]
+fn only_urls_on_the_whole_origin_are_rerouted() {
+    let failover = Failover::new("http://localhost:1234".to_string(), "http://backup:1234/".to_string());
+    failover.record_primary_reachable(false);
+
+    assert_eq!(failover.route_url("http://localhost:1234/v1/models"), "http://backup:1234/v1/models");
+    assert_eq!(failover.route_url("http://localhost:1234"), "http://backup:1234");
+    assert_eq!(failover.route_url("http://localhost:12340/v1/models"), "http://localhost:12340/v1/models");
+    assert_eq!(failover.route_url("http://localhost:1234.evil/v1"), "http://localhost:1234.evil/v1");
+
+    assert_eq!(failover.fail_over_from("http://localhost:12340/v1/models"), None);
+    assert_eq!(
+        failover.fail_over_from("http://backup:1234/v1/models").as_deref(),
+        Some("http://localhost:1234/v1/models")
+    );
+    assert_eq!(failover.active_backend(), "http://localhost:1234");
+}
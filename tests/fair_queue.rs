@@ -0,0 +1,114 @@
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr};
+use std::sync::{Arc, Mutex};
+
+use ollama_lmstudio_proxy_rust::fair_queue::{client_key, parse_fair_queue_weights, parse_trusted_proxies, FairQueue};
+
+/// Queue `clients` (in order) behind one held slot, release it and return the order they were served in
+async fn service_order(queue: Arc<FairQueue>, clients: &[&str]) -> Vec<String> {
+    let blocker = queue.acquire("blocker").await.unwrap();
+    let served = Arc::new(Mutex::new(Vec::new()));
+    let handles: Vec<_> = clients
+        .iter()
+        .map(|client| {
+            let waiting = queue.acquire(client);
+            let served = served.clone();
+            let client = client.to_string();
+            tokio::spawn(async move {
+                let permit = waiting.await.unwrap();
+                served.lock().unwrap().push(client);
+                drop(permit);
+            })
+        })
+        .collect();
+    assert_eq!(queue.queued(), clients.len());
+
+    drop(blocker);
+    for handle in handles {
+        handle.await.unwrap();
+    }
+    assert_eq!(queue.in_flight(), 0);
+    let order = served.lock().unwrap().clone();
+    order
+}
+
+#[tokio::test]
+async fn batch_client_does_not_starve_others() {
+    let queue = FairQueue::new(1, HashMap::new());
+    let order = service_order(queue, &["batch", "batch", "batch", "chat", "batch", "chat"]).await;
+    assert_eq!(order, ["batch", "chat", "batch", "chat", "batch", "batch"]);
+}
+
+#[tokio::test]
+async fn weights_give_clients_more_turns() {
+    let weights = parse_fair_queue_weights(&["batch=2".to_string()]).unwrap();
+    let queue = FairQueue::new(1, weights);
+    let order = service_order(queue, &["batch", "batch", "batch", "chat", "chat"]).await;
+    assert_eq!(order, ["batch", "batch", "chat", "batch", "chat"]);
+}
+
+#[tokio::test]
+async fn abandoned_waiters_pass_their_slot_on() {
+    let queue = FairQueue::new(1, HashMap::new());
+    let blocker = queue.acquire("a").await.unwrap();
+    let abandoned = queue.acquire("b");
+    let waiting = queue.acquire("c");
+    drop(abandoned);
+
+    drop(blocker);
+    let permit = waiting.await.unwrap();
+    assert_eq!(queue.in_flight(), 1);
+    drop(permit);
+    assert_eq!(queue.in_flight(), 0);
+}
+
+#[test]
+fn client_key_prefers_api_key_then_forwarded_ip_from_trusted_proxies() {
+    let peer = Some(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 7)));
+    let trusted = parse_trusted_proxies(&["10.0.0.7".to_string()]).unwrap();
+    let mut headers = warp::http::HeaderMap::new();
+    assert_eq!(client_key(&headers, peer, &trusted), "10.0.0.7");
+
+    headers.insert("x-forwarded-for", "192.168.1.20, 10.0.0.1".parse().unwrap());
+    assert_eq!(client_key(&headers, peer, &trusted), "192.168.1.20");
+
+    headers.insert("authorization", "Bearer team-key".parse().unwrap());
+    assert_eq!(client_key(&headers, peer, &trusted), "team-key");
+}
+
+#[test]
+fn client_key_ignores_forwarded_headers_from_untrusted_peers() {
+    let peer = Some(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 7)));
+    let mut headers = warp::http::HeaderMap::new();
+    headers.insert("x-forwarded-for", "192.168.1.20".parse().unwrap());
+    headers.insert("x-real-ip", "192.168.1.21".parse().unwrap());
+    headers.insert("authorization", "Bearer random-key".parse().unwrap());
+    assert_eq!(client_key(&headers, peer, &[]), "10.0.0.7");
+
+    let other_proxy = parse_trusted_proxies(&["10.0.0.1".to_string()]).unwrap();
+    assert_eq!(client_key(&headers, peer, &other_proxy), "10.0.0.7");
+    assert!(parse_trusted_proxies(&["not-an-ip".to_string()]).is_err());
+}
+
+#[tokio::test]
+async fn many_abandoned_waiters_are_skipped() {
+    let queue = FairQueue::new(1, HashMap::new());
+    let blocker = queue.acquire("a").await.unwrap();
+    let abandoned: Vec<_> = (0..10_000).map(|_| queue.acquire("b")).collect();
+    let waiting = queue.acquire("c");
+    drop(abandoned);
+
+    drop(blocker);
+    let permit = waiting.await.unwrap();
+    assert_eq!(queue.in_flight(), 1);
+    assert_eq!(queue.queued(), 0);
+    drop(permit);
+    assert_eq!(queue.in_flight(), 0);
+}
+
+#[test]
+fn invalid_weights_are_rejected() {
+    assert!(parse_fair_queue_weights(&["client=0".to_string()]).is_err());
+    assert!(parse_fair_queue_weights(&["=2".to_string()]).is_err());
+    assert!(parse_fair_queue_weights(&["client".to_string()]).is_err());
+}
@@ -0,0 +1,68 @@
+mod common;
+
+use std::sync::{Arc, Mutex};
+
+use clap::Parser;
+use serde_json::json;
+use tokio_util::sync::CancellationToken;
+
+use ollama_lmstudio_proxy_rust::handlers::{forwarded_headers, handle_ollama_chat};
+use ollama_lmstudio_proxy_rust::{validate_config, Config, RequestContext};
+
+use common::{context, serve, server, Reply};
+
+/// Legacy-mode LM Studio that records the head of each chat request
+async fn serve_recording_heads(heads: Arc<Mutex<Vec<String>>>) -> String {
+    serve(move |request| {
+        if request.starts_with("GET /v1/models") {
+            Reply::json(json!({"object": "list", "data": [{"id": "qwen2.5-7b-instruct", "object": "model"}]}))
+        } else {
+            heads.lock().unwrap().push(request.split("\r\n\r\n").next().unwrap().to_lowercase());
+            Reply::json(json!({"choices": [{"message": {"role": "assistant", "content": "hi"}, "finish_reason": "stop"}]}))
+        }
+    })
+    .await
+}
+
+fn inbound() -> warp::http::HeaderMap {
+    let mut headers = warp::http::HeaderMap::new();
+    headers.insert("x-tenant-id", "acme".parse().unwrap());
+    headers.insert("x-internal-secret", "hidden".parse().unwrap());
+    headers
+}
+
+#[test]
+fn only_listed_headers_are_forwarded() {
+    let forwarded = forwarded_headers(&inbound(), &["X-Tenant-Id".to_string(), "X-Route".to_string()]);
+    assert_eq!(forwarded.len(), 1);
+    assert_eq!(forwarded["x-tenant-id"], "acme");
+
+    assert!(forwarded_headers(&inbound(), &[]).is_empty());
+}
+
+#[test]
+fn invalid_header_names_are_rejected_at_startup() {
+    let config = Config::parse_from(["proxy", "--forward-header", "X Tenant"]);
+    assert!(validate_config(&config).unwrap_err().contains("--forward-header"));
+}
+
+#[tokio::test]
+async fn listed_header_reaches_lm_studio() {
+    let heads = Arc::new(Mutex::new(Vec::new()));
+    let url = serve_recording_heads(heads.clone()).await;
+    let server = server(&url, &["--legacy", "--forward-header", "X-Tenant-Id"]);
+    let context = RequestContext {
+        forwarded_headers: forwarded_headers(&inbound(), &server.config.forward_header),
+        ..context(&server)
+    };
+
+    let body = json!({"model": "qwen2.5-7b-instruct", "stream": false, "messages": [{"role": "user", "content": "hi"}]});
+    handle_ollama_chat(context, server.current_resolver(), body, CancellationToken::new(), &server.config)
+        .await
+        .unwrap();
+
+    let heads = heads.lock().unwrap();
+    assert_eq!(heads.len(), 1);
+    assert!(heads[0].contains("\r\nx-tenant-id: acme"), "{}", heads[0]);
+    assert!(!heads[0].contains("x-internal-secret"), "{}", heads[0]);
+}
@@ -0,0 +1,51 @@
+mod common;
+
+use std::time::Instant;
+
+use serde_json::{json, Value};
+use tokio_util::sync::CancellationToken;
+
+use ollama_lmstudio_proxy_rust::handlers::{handle_streaming_response, ResponseTransformer};
+use ollama_lmstudio_proxy_rust::server::build_http_client;
+
+use common::serve_sse_once;
+
+#[test]
+fn chat_shaped_reply_becomes_a_flat_response() {
+    let lm_response = json!({
+        "object": "chat.completion",
+        "choices": [{"index": 0, "message": {"role": "assistant", "content": "A red square."}, "finish_reason": "stop"}]
+    });
+    for native in [true, false] {
+        let ollama = ResponseTransformer::convert_to_ollama_generate(&lm_response, "llava:latest", "describe", Instant::now(), native);
+        assert_eq!(ollama["response"], "A red square.");
+        assert!(ollama.get("message").is_none());
+        assert!(ollama.get("role").is_none());
+    }
+}
+
+#[tokio::test]
+async fn streamed_chat_reply_has_only_response_fields() {
+    let deltas = [json!({"role": "assistant"}), json!({"content": "A red"}), json!({"content": " square."})];
+    let mut body = String::new();
+    for delta in deltas {
+        body.push_str(&format!("data: {}\n\n", json!({"object": "chat.completion.chunk", "choices": [{"delta": delta}]})));
+    }
+    body.push_str("data: [DONE]\n\n");
+    let url = serve_sse_once(body).await;
+
+    let response = build_http_client().unwrap().get(&url).send().await.unwrap();
+    let reply = handle_streaming_response(response, false, "llava:latest", "now", Instant::now(), CancellationToken::new(), 5, None)
+        .await
+        .unwrap();
+    let bytes = warp::hyper::body::to_bytes(reply.into_body()).await.unwrap();
+
+    let chunks: Vec<Value> = String::from_utf8(bytes.to_vec())
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert!(chunks.iter().all(|chunk| chunk.get("message").is_none()));
+    let response: String = chunks.iter().filter_map(|chunk| chunk["response"].as_str()).collect();
+    assert_eq!(response, "A red square.");
+}
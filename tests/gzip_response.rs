@@ -0,0 +1,57 @@
+mod common;
+
+use std::io::Write;
+
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use moka::future::Cache;
+use tokio::io::AsyncWriteExt;
+use tokio::net::TcpListener;
+use tokio_util::sync::CancellationToken;
+
+use ollama_lmstudio_proxy_rust::server::build_http_client;
+use ollama_lmstudio_proxy_rust::ModelResolverLegacy;
+
+use common::read_request;
+
+/// Serve a single gzip-encoded JSON response and return the base URL
+async fn serve_gzip_once(json: &'static str) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    tokio::spawn(async move {
+        let (mut socket, _) = listener.accept().await.unwrap();
+        read_request(&mut socket).await;
+
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(json.as_bytes()).unwrap();
+        let body = encoder.finish().unwrap();
+
+        let head = format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Encoding: gzip\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            body.len()
+        );
+        socket.write_all(head.as_bytes()).await.unwrap();
+        socket.write_all(&body).await.unwrap();
+        socket.shutdown().await.unwrap();
+    });
+
+    format!("http://{}", addr)
+}
+
+#[tokio::test]
+async fn decodes_gzip_models_response() {
+    let url = serve_gzip_once(
+        r#"{"object":"list","data":[{"id":"qwen2.5-7b-instruct","object":"model"},{"id":"llama-3.2-3b","object":"model"}]}"#,
+    )
+    .await;
+
+    let client = build_http_client().unwrap();
+    let resolver = ModelResolverLegacy::new_legacy(url, Cache::new(10));
+    let ids = resolver
+        .available_model_ids_legacy(&client, CancellationToken::new())
+        .await
+        .expect("gzip response should be decoded");
+
+    assert_eq!(ids, vec!["qwen2.5-7b-instruct", "llama-3.2-3b"]);
+}
@@ -0,0 +1,94 @@
+mod common;
+
+use serde_json::{json, Value};
+use tokio_util::sync::CancellationToken;
+
+use ollama_lmstudio_proxy_rust::handlers::handle_health_check;
+use ollama_lmstudio_proxy_rust::handlers::ollama::handle_ollama_chat;
+use ollama_lmstudio_proxy_rust::ProxyServer;
+
+use common::{context, serve, server, Reply};
+
+const NATIVE_MODELS: &str = r#"{"object":"list","data":[
+    {"id":"qwen2.5-7b-instruct","object":"model","type":"llm","publisher":"qwen","arch":"qwen2","compatibility_type":"gguf","quantization":"Q4_K_M","state":"loaded","max_context_length":32768},
+    {"id":"text-embedding-nomic-embed-text-v1.5","object":"model","type":"embeddings","publisher":"nomic-ai","arch":"nomic-bert","compatibility_type":"gguf","quantization":"Q4_K_M","state":"loaded","max_context_length":2048},
+    {"id":"phi-4","object":"model","type":"llm","publisher":"microsoft","arch":"phi3","compatibility_type":"gguf","quantization":"Q4_K_M","state":"not-loaded","max_context_length":16384}
+]}"#;
+
+/// Native LM Studio with two loaded models whose chat responses name their runtime
+async fn serve_native() -> String {
+    serve(|request| {
+        if request.starts_with("GET /api/v0/models ") || request.starts_with("GET /v1/models ") {
+            Reply::json(NATIVE_MODELS)
+        } else if request.contains("\"stream\":true") {
+            let delta = json!({"choices": [{"delta": {"content": "hi"}, "finish_reason": null}]});
+            let last = json!({
+                "choices": [{"delta": {}, "finish_reason": "stop"}],
+                "runtime": {"name": "mlx-llm-macos-arm64", "version": "0.9.0"}
+            });
+            Reply::sse(format!("data: {}\n\ndata: {}\n\ndata: [DONE]\n\n", delta, last))
+        } else {
+            Reply::json(json!({
+                "choices": [{"message": {"role": "assistant", "content": "hi"}, "finish_reason": "stop"}],
+                "runtime": {"name": "llama.cpp-linux-x86_64-nvidia-cuda-avx2", "version": "1.3.0", "supported_formats": ["gguf"]}
+            }))
+        }
+    })
+    .await
+}
+
+async fn health(server: &ProxyServer, native: bool) -> Value {
+    handle_health_check(context(server), native, CancellationToken::new()).await.unwrap()
+}
+
+#[tokio::test]
+async fn native_health_reports_backend_info() {
+    let url = serve_native().await;
+    let server = server(&url, &[]);
+
+    let report = health(&server, true).await;
+    assert_eq!(report["status"], "healthy");
+    assert_eq!(report["models_known_to_lmstudio"], 3);
+    assert_eq!(report["backend_info"], json!({"loaded_models": 2}));
+
+    let body = json!({"model": "qwen2.5-7b-instruct", "stream": false, "messages": [{"role": "user", "content": "hi"}]});
+    handle_ollama_chat(context(&server), server.current_resolver(), body, CancellationToken::new(), &server.config)
+        .await
+        .unwrap();
+
+    let report = health(&server, true).await;
+    assert_eq!(report["backend_info"]["loaded_models"], 2);
+    assert_eq!(report["backend_info"]["runtime"]["name"], "llama.cpp-linux-x86_64-nvidia-cuda-avx2");
+    assert_eq!(report["backend_info"]["runtime"]["version"], "1.3.0");
+
+    let report = health(&server, false).await;
+    assert_eq!(report["status"], "healthy");
+    assert!(report.get("backend_info").is_none());
+}
+
+#[tokio::test]
+async fn health_report_redacts_url_credentials() {
+    let url = serve_native().await.replace("http://", "http://admin:hunter2@");
+    let server = server(&url, &[]);
+
+    let report = health(&server, true).await;
+    assert_eq!(report["status"], "healthy");
+    let reported = report["lmstudio_url"].as_str().unwrap();
+    assert!(!reported.contains("hunter2"), "{}", reported);
+    assert!(reported.contains("REDACTED"), "{}", reported);
+}
+
+#[tokio::test]
+async fn streamed_responses_record_the_final_runtime() {
+    let url = serve_native().await;
+    let server = server(&url, &[]);
+    assert!(server.backend_runtime.get().is_none());
+
+    let body = json!({"model": "qwen2.5-7b-instruct", "stream": true, "messages": [{"role": "user", "content": "hi"}]});
+    let reply = handle_ollama_chat(context(&server), server.current_resolver(), body, CancellationToken::new(), &server.config)
+        .await
+        .unwrap();
+    warp::hyper::body::to_bytes(reply.into_body()).await.unwrap();
+
+    assert_eq!(server.backend_runtime.get().unwrap()["name"], "mlx-llm-macos-arm64");
+}
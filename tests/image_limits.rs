@@ -0,0 +1,51 @@
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use serde_json::json;
+use std::io::Cursor;
+
+use ollama_lmstudio_proxy_rust::images::{prepare_image, prepare_request_images, ImageLimits};
+
+fn png(width: u32, height: u32) -> String {
+    let mut output = Cursor::new(Vec::new());
+    image::DynamicImage::new_rgb8(width, height)
+        .write_to(&mut output, image::ImageFormat::Png)
+        .unwrap();
+    BASE64.encode(output.into_inner())
+}
+
+fn limits(max_bytes: usize, max_dimension: u32) -> ImageLimits {
+    ImageLimits { max_bytes, max_dimension, resize: false }
+}
+
+#[test]
+fn unknown_formats_pass_when_no_limit_needs_the_size() {
+    let unknown = BASE64.encode(b"not an image the proxy can decode");
+    assert_eq!(prepare_image(&unknown, &limits(0, 0)), Ok(None));
+    assert_eq!(prepare_image(&unknown, &limits(1024, 0)), Ok(None));
+
+    let error = prepare_image(&unknown, &limits(0, 512)).unwrap_err();
+    assert!(error.contains("unrecognized image format"), "{}", error);
+    assert!(prepare_image(&unknown, &limits(4, 0)).is_err());
+}
+
+#[test]
+fn dimension_limit_is_checked_from_the_header() {
+    assert_eq!(prepare_image(&png(16, 8), &limits(0, 16)), Ok(None));
+    let error = prepare_image(&png(32, 8), &limits(0, 16)).unwrap_err();
+    assert!(error.contains("32x8"), "{}", error);
+}
+
+#[tokio::test]
+async fn request_images_are_prepared_off_the_runtime() {
+    let body = json!({
+        "model": "llava",
+        "messages": [{"role": "user", "content": "hi", "images": [png(64, 64)]}]
+    });
+    let resized = ImageLimits { max_bytes: 0, max_dimension: 16, resize: true };
+    let body = prepare_request_images(body, resized).await.unwrap();
+    let image = body["messages"][0]["images"][0].as_str().unwrap();
+    assert_eq!(prepare_image(image, &limits(0, 16)), Ok(None));
+
+    let rejected = prepare_request_images(json!({"images": [png(64, 64)]}), limits(0, 16)).await;
+    assert_eq!(rejected.unwrap_err().status_code, 400);
+}
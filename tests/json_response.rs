@@ -0,0 +1,29 @@
+use serde_json::json;
+
+use ollama_lmstudio_proxy_rust::common::parse_json_lossy;
+
+#[test]
+fn valid_utf8_is_parsed_strictly() {
+    let value = parse_json_lossy(r#"{"content":"héllo"}"#.as_bytes()).unwrap();
+    assert_eq!(value, json!({"content": "héllo"}));
+}
+
+#[test]
+fn invalid_utf8_falls_back_to_lossy_decoding() {
+    let mut body = br#"{"choices":[{"message":{"content":"bad "#.to_vec();
+    body.push(0xFF);
+    body.extend_from_slice(br#" byte"}}]}"#);
+
+    let value = parse_json_lossy(&body).unwrap();
+    assert_eq!(value["choices"][0]["message"]["content"], "bad \u{FFFD} byte");
+}
+
+#[test]
+fn malformed_json_is_still_an_error() {
+    let error = parse_json_lossy(b"{\"content\": ").unwrap_err();
+    assert_eq!(error.status_code, 500);
+    assert!(error.message.starts_with("Invalid JSON from LM Studio"));
+
+    let error = parse_json_lossy(&[b'{', 0xFF]).unwrap_err();
+    assert_eq!(error.status_code, 500);
+}
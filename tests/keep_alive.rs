@@ -0,0 +1,94 @@
+mod common;
+
+use std::time::Duration;
+
+use serde_json::{json, Value};
+use tokio_util::sync::CancellationToken;
+
+use ollama_lmstudio_proxy_rust::handlers::ollama::{handle_ollama_chat, handle_ollama_generate};
+use ollama_lmstudio_proxy_rust::handlers::{is_unload_request, parse_keep_alive, KeepAlive};
+
+use common::{context, server};
+
+#[test]
+fn integer_seconds_as_number_or_string() {
+    assert_eq!(parse_keep_alive(&json!(300)), KeepAlive::Duration(Duration::from_secs(300)));
+    assert_eq!(parse_keep_alive(&json!("300")), KeepAlive::Duration(Duration::from_secs(300)));
+    assert_eq!(parse_keep_alive(&json!(1.5)), KeepAlive::Duration(Duration::from_millis(1500)));
+}
+
+#[test]
+fn negative_values_keep_forever() {
+    assert_eq!(parse_keep_alive(&json!(-1)), KeepAlive::Forever);
+    assert_eq!(parse_keep_alive(&json!("-1")), KeepAlive::Forever);
+    assert_eq!(parse_keep_alive(&json!("-1m")), KeepAlive::Forever);
+}
+
+#[test]
+fn zero_unloads() {
+    assert_eq!(parse_keep_alive(&json!(0)), KeepAlive::Unload);
+    assert_eq!(parse_keep_alive(&json!("0")), KeepAlive::Unload);
+    assert_eq!(parse_keep_alive(&json!("0s")), KeepAlive::Unload);
+    assert_eq!(parse_keep_alive(&json!("0m")), KeepAlive::Unload);
+}
+
+#[test]
+fn go_style_durations() {
+    assert_eq!(parse_keep_alive(&json!("30s")), KeepAlive::Duration(Duration::from_secs(30)));
+    assert_eq!(parse_keep_alive(&json!("5m")), KeepAlive::Duration(Duration::from_secs(300)));
+    assert_eq!(parse_keep_alive(&json!("1h")), KeepAlive::Duration(Duration::from_secs(3600)));
+    assert_eq!(parse_keep_alive(&json!("1h30m")), KeepAlive::Duration(Duration::from_secs(5400)));
+    assert_eq!(parse_keep_alive(&json!("1.5h")), KeepAlive::Duration(Duration::from_secs(5400)));
+    assert_eq!(parse_keep_alive(&json!("500ms")), KeepAlive::Duration(Duration::from_millis(500)));
+}
+
+#[test]
+fn malformed_values_use_the_default() {
+    for value in [json!("soon"), json!("5x"), json!("m"), json!("-"), json!(""), json!(true), json!([1]), json!(null)] {
+        assert_eq!(parse_keep_alive(&value), KeepAlive::default(), "{}", value);
+    }
+    assert_eq!(KeepAlive::default(), KeepAlive::Duration(Duration::from_secs(300)));
+}
+
+#[test]
+fn values_too_large_for_a_duration_use_the_default() {
+    for value in [json!(1e20), json!("1e20"), json!("99999999999999999999h"), json!("inf")] {
+        assert_eq!(parse_keep_alive(&value), KeepAlive::default(), "{}", value);
+    }
+    assert!(!is_unload_request(&json!({"model": "m", "keep_alive": 1e20})));
+}
+
+#[test]
+fn only_zero_keep_alive_is_an_unload_request() {
+    assert!(is_unload_request(&json!({"model": "m", "keep_alive": "0s"})));
+    assert!(!is_unload_request(&json!({"model": "m", "keep_alive": "5m"})));
+    assert!(!is_unload_request(&json!({"model": "m", "keep_alive": "bogus"})));
+    assert!(!is_unload_request(&json!({"model": "m"})));
+}
+
+#[tokio::test]
+async fn unload_requests_get_ollamas_unload_response() {
+    // Nothing listens here: the acknowledgement must not depend on LM Studio
+    let server = server("http://127.0.0.1:9", &[]);
+
+    let body = json!({"model": "llama3", "messages": [], "keep_alive": 0});
+    let response = handle_ollama_chat(context(&server), server.current_resolver(), body, CancellationToken::new(), &server.config)
+        .await
+        .unwrap();
+    let body = warp::hyper::body::to_bytes(response.into_body()).await.unwrap();
+    let body: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(body["model"], "llama3");
+    assert_eq!(body["message"], json!({"role": "assistant", "content": ""}));
+    assert_eq!(body["done"], true);
+    assert_eq!(body["done_reason"], "unload");
+
+    let body = json!({"model": "llama3", "prompt": "", "keep_alive": "0s"});
+    let response = handle_ollama_generate(context(&server), server.current_resolver(), body, CancellationToken::new(), &server.config)
+        .await
+        .unwrap();
+    let body = warp::hyper::body::to_bytes(response.into_body()).await.unwrap();
+    let body: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(body["response"], "");
+    assert_eq!(body["done"], true);
+    assert_eq!(body["done_reason"], "unload");
+}
@@ -0,0 +1,114 @@
+mod common;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use moka::future::Cache;
+use serde_json::{json, Value};
+use tokio::io::AsyncWriteExt;
+use tokio::net::TcpListener;
+use tokio_util::sync::CancellationToken;
+
+use ollama_lmstudio_proxy_rust::handlers::{load_method_for, trigger_model_loading, LoadMethod, LoadTriggers};
+use ollama_lmstudio_proxy_rust::model::ModelResolver;
+use ollama_lmstudio_proxy_rust::model_legacy::ModelResolverLegacy;
+use ollama_lmstudio_proxy_rust::server::build_http_client;
+use ollama_lmstudio_proxy_rust::{ModelResolverType, RequestContext};
+
+use common::{bare_context, read_request, request_body, request_line, serve, Reply};
+
+/// Mock LM Studio that counts requests and answers each slowly, like a model being loaded
+async fn serve_slow_counting(counter: Arc<AtomicUsize>) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    tokio::spawn(async move {
+        loop {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let counter = counter.clone();
+            tokio::spawn(async move {
+                read_request(&mut socket).await;
+                counter.fetch_add(1, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(200)).await;
+                let _ = socket.write_all(Reply::json("{}").to_http().as_bytes()).await;
+            });
+        }
+    });
+
+    format!("http://{}", addr)
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn concurrent_triggers_for_one_model_send_a_single_request() {
+    let counter = Arc::new(AtomicUsize::new(0));
+    let url = serve_slow_counting(counter.clone()).await;
+    let client = build_http_client().unwrap();
+    let triggers = Arc::new(LoadTriggers::new());
+
+    let handles: Vec<_> = (0..10)
+        .map(|_| {
+            let client = client.clone();
+            let url = url.clone();
+            let triggers = triggers.clone();
+            tokio::spawn(async move {
+                let context = RequestContext {
+                    load_triggers: Some(&triggers),
+                    ..bare_context(&client, &url)
+                };
+                trigger_model_loading(&context, "single-flight-model:latest", LoadMethod::Ping, CancellationToken::new())
+                    .await
+            })
+        })
+        .collect();
+
+    for handle in handles {
+        assert!(handle.await.unwrap().unwrap());
+    }
+    assert_eq!(counter.load(Ordering::SeqCst), 1);
+    // The slot goes away once nobody waits on it
+    assert!(triggers.is_empty());
+}
+
+/// Mock LM Studio that records the request line and JSON body of every request
+async fn serve_recording(requests: Arc<Mutex<Vec<(String, Value)>>>) -> String {
+    serve(move |request| {
+        requests.lock().unwrap().push((request_line(request).to_string(), request_body(request)));
+        Reply::json("{}")
+    })
+    .await
+}
+
+#[tokio::test]
+async fn native_trigger_uses_native_endpoint_with_ttl() {
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let url = serve_recording(requests.clone()).await;
+    let client = build_http_client().unwrap();
+    let context = bare_context(&client, &url);
+
+    let native = LoadMethod::Native { ttl_seconds: Some(600) };
+    assert!(trigger_model_loading(&context, "ttl-model:latest", native, CancellationToken::new()).await.unwrap());
+    assert!(trigger_model_loading(&context, "ping-model", LoadMethod::Ping, CancellationToken::new()).await.unwrap());
+
+    let requests = requests.lock().unwrap();
+    assert!(requests[0].0.starts_with("POST /api/v0/chat/completions "));
+    assert_eq!(requests[0].1["model"], "ttl-model");
+    assert_eq!(requests[0].1["ttl"], 600);
+    assert!(requests[1].0.starts_with("POST /v1/chat/completions "));
+    assert!(requests[1].1.get("ttl").is_none());
+}
+
+#[test]
+fn load_method_follows_api_mode_and_keep_alive() {
+    let native = ModelResolverType::Native(Arc::new(ModelResolver::new(String::new(), Cache::new(1))));
+    let legacy = ModelResolverType::Legacy(Arc::new(ModelResolverLegacy::new_legacy(String::new(), Cache::new(1))));
+
+    assert_eq!(load_method_for(&legacy, &json!({ "keep_alive": "10m" })), LoadMethod::Ping);
+    assert_eq!(
+        load_method_for(&native, &json!({ "keep_alive": "10m" })),
+        LoadMethod::Native { ttl_seconds: Some(600) }
+    );
+    assert_eq!(load_method_for(&native, &json!({})), LoadMethod::Native { ttl_seconds: Some(300) });
+    assert_eq!(load_method_for(&native, &json!({ "keep_alive": 0.5 })), LoadMethod::Native { ttl_seconds: Some(1) });
+    assert_eq!(load_method_for(&native, &json!({ "keep_alive": -1 })), LoadMethod::Native { ttl_seconds: None });
+}
@@ -0,0 +1,66 @@
+mod common;
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use tokio_util::sync::CancellationToken;
+
+use ollama_lmstudio_proxy_rust::handlers::{trigger_model_loading, LoadMethod};
+use ollama_lmstudio_proxy_rust::server::build_http_client;
+use ollama_lmstudio_proxy_rust::{init_runtime_config, RuntimeConfig};
+
+use common::{bare_context, serve, Reply};
+
+/// Mock LM Studio: `missing` does not exist, `flaky` fails twice before loading, `broken` always fails
+async fn serve_models(attempts: Arc<Mutex<HashMap<String, usize>>>) -> String {
+    serve(move |request| {
+        let model = ["missing", "flaky", "broken"]
+            .into_iter()
+            .find(|m| request.contains(&format!("\"model\":\"{}\"", m)))
+            .unwrap_or("other");
+        let attempt = {
+            let mut attempts = attempts.lock().unwrap();
+            let count = attempts.entry(model.to_string()).or_default();
+            *count += 1;
+            *count
+        };
+        match model {
+            "missing" => Reply::json(r#"{"error":{"message":"Model \"missing\" not found"}}"#).with_status("404 Not Found"),
+            "flaky" if attempt <= 2 => Reply::json(r#"{"error":"busy"}"#).with_status("500 Internal Server Error"),
+            "broken" => Reply::json(r#"{"error":"busy"}"#).with_status("500 Internal Server Error"),
+            _ => Reply::json(r#"{"choices":[]}"#),
+        }
+    })
+    .await
+}
+
+#[tokio::test]
+async fn retries_transient_failures_and_fails_clearly() {
+    init_runtime_config(RuntimeConfig {
+        load_trigger_retries: Some(2),
+        ..RuntimeConfig::default()
+    });
+    let attempts = Arc::new(Mutex::new(HashMap::new()));
+    let url = serve_models(attempts.clone()).await;
+    let client = build_http_client().unwrap();
+    let context = bare_context(&client, &url);
+    let trigger = |model: &'static str| trigger_model_loading(&context, model, LoadMethod::Ping, CancellationToken::new());
+
+    // Definitively absent: no retries, 404
+    let missing = trigger("missing").await.unwrap_err();
+    assert_eq!(missing.status_code, 404);
+    assert!(missing.message.starts_with("Could not load model 'missing'"));
+
+    // Transient failures are retried until the model loads
+    assert!(trigger("flaky").await.unwrap());
+
+    // Still failing after the retries: 503 instead of proceeding
+    let broken = trigger("broken").await.unwrap_err();
+    assert_eq!(broken.status_code, 503);
+    assert!(broken.message.contains("after 3 attempt(s)"));
+
+    let attempts = attempts.lock().unwrap();
+    assert_eq!(attempts["missing"], 1);
+    assert_eq!(attempts["flaky"], 3);
+    assert_eq!(attempts["broken"], 3);
+}
@@ -0,0 +1,45 @@
+use serde_json::{json, Value};
+
+use ollama_lmstudio_proxy_rust::handlers::{apply_max_tokens_header, build_lm_studio_request, LMStudioRequestType};
+
+fn capped(mut body: Value, header: Option<&str>) -> Value {
+    apply_max_tokens_header(&mut body, header);
+    body
+}
+
+#[test]
+fn header_lowers_num_predict() {
+    let body = capped(json!({"model": "m", "options": {"num_predict": 500, "temperature": 0.2}}), Some("128"));
+    assert_eq!(body["options"], json!({"num_predict": 128, "temperature": 0.2}));
+
+    let messages = json!([{"role": "user", "content": "hi"}]);
+    let request = build_lm_studio_request(
+        "m",
+        LMStudioRequestType::Chat { messages: &messages, stream: false },
+        body.get("options"),
+        None,
+        false,
+    );
+    assert_eq!(request["max_completion_tokens"], 128);
+}
+
+#[test]
+fn header_applies_when_request_is_unlimited() {
+    assert_eq!(capped(json!({"model": "m"}), Some("64"))["options"]["num_predict"], 64);
+    assert_eq!(capped(json!({"model": "m", "options": null}), Some("64"))["options"]["num_predict"], 64);
+    assert_eq!(capped(json!({"model": "m", "options": {"num_predict": -1}}), Some("64"))["options"]["num_predict"], 64);
+}
+
+#[test]
+fn header_never_raises_num_predict() {
+    let body = json!({"model": "m", "options": {"num_predict": 32}});
+    assert_eq!(capped(body.clone(), Some("4096")), body);
+}
+
+#[test]
+fn invalid_header_is_ignored() {
+    let body = json!({"model": "m", "options": {"num_predict": 32}});
+    for header in [None, Some("0"), Some("lots"), Some("-5")] {
+        assert_eq!(capped(body.clone(), header), body);
+    }
+}
@@ -0,0 +1,91 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use ollama_lmstudio_proxy_rust::metrics::{mark_streamed, measure, record_request_model, record_upstream_time, ProxyMetrics};
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 8)]
+async fn concurrent_recording_keeps_correct_totals() {
+    const TASKS: usize = 64;
+    const REQUESTS_PER_TASK: usize = 250;
+
+    let metrics = Arc::new(ProxyMetrics::new());
+    let endpoints = ["POST /api/chat", "POST /api/generate", "GET /api/tags"];
+    let models = ["model-a", "model-b"];
+
+    let handles: Vec<_> = (0..TASKS)
+        .map(|task| {
+            let metrics = metrics.clone();
+            tokio::spawn(async move {
+                for i in 0..REQUESTS_PER_TASK {
+                    let model = models[(task + i) % models.len()];
+                    let (_, timing) = measure(async {
+                        record_request_model(model);
+                        tokio::task::yield_now().await;
+                    })
+                    .await;
+                    let endpoint = endpoints[(task + i) % endpoints.len()];
+                    metrics.record_request(endpoint, &timing, i % 10 == 0);
+                }
+            })
+        })
+        .collect();
+
+    let all_done = async {
+        for handle in handles {
+            handle.await.expect("task panicked");
+        }
+    };
+    tokio::time::timeout(Duration::from_secs(30), all_done)
+        .await
+        .expect("metrics recording deadlocked");
+
+    let expected_total = (TASKS * REQUESTS_PER_TASK) as u64;
+    assert_eq!(metrics.requests_total(), expected_total);
+
+    let endpoint_total: u64 = endpoints
+        .iter()
+        .map(|e| metrics.endpoint_counts(e).expect("endpoint recorded").0)
+        .sum();
+    assert_eq!(endpoint_total, expected_total);
+
+    let (model_requests, model_errors): (Vec<u64>, Vec<u64>) = models
+        .iter()
+        .map(|m| metrics.model_counts(m).expect("model recorded"))
+        .unzip();
+    assert_eq!(model_requests.iter().sum::<u64>(), expected_total);
+    assert_eq!(
+        model_errors.iter().sum::<u64>(),
+        (TASKS * REQUESTS_PER_TASK.div_ceil(10)) as u64
+    );
+
+    let snapshot = metrics.snapshot();
+    assert_eq!(snapshot["requests_total"], expected_total);
+    assert!(snapshot["endpoints"].get("GET /api/tags").is_some());
+}
+
+#[tokio::test]
+async fn streamed_requests_are_left_out_of_overhead_totals() {
+    let metrics = ProxyMetrics::new();
+
+    let (_, timing) = measure(async {
+        record_upstream_time(Duration::from_millis(40));
+    })
+    .await;
+    assert!(!timing.streamed);
+    metrics.record_request("POST /api/chat", &timing, false);
+
+    let (_, timing) = measure(async {
+        record_upstream_time(Duration::from_millis(5));
+        mark_streamed();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    })
+    .await;
+    assert!(timing.streamed);
+    metrics.record_request("POST /api/chat", &timing, false);
+
+    let snapshot = metrics.snapshot();
+    assert_eq!(snapshot["requests_total"], 2);
+    assert_eq!(snapshot["timed_requests_total"], 1);
+    let upstream_ms = snapshot["upstream_time_ms_total"].as_f64().unwrap();
+    assert!((40.0..45.0).contains(&upstream_ms), "{}", upstream_ms);
+}
@@ -0,0 +1,26 @@
+use ollama_lmstudio_proxy_rust::model_metadata::{model_digest, model_modified_at};
+
+#[test]
+fn digest_is_stable_sha256() {
+    let first = model_digest("llama3:latest", "Q4_K_M", "8B");
+    let second = model_digest("llama3:latest", "Q4_K_M", "8B");
+    assert_eq!(first, second);
+    assert_eq!(first.len(), 64);
+    assert!(first.chars().all(|c| c.is_ascii_hexdigit()));
+}
+
+#[test]
+fn distinct_variants_get_distinct_digests() {
+    let q4 = model_digest("llama3:latest", "Q4_K_M", "8B");
+    assert_ne!(q4, model_digest("llama3:latest", "Q8_0", "8B"));
+    assert_ne!(q4, model_digest("llama3:latest", "Q4_K_M", "70B"));
+    assert_ne!(q4, model_digest("llama3.1:latest", "Q4_K_M", "8B"));
+}
+
+#[test]
+fn modified_at_is_stable_per_model() {
+    let first = model_modified_at("stable-model:latest");
+    std::thread::sleep(std::time::Duration::from_millis(5));
+    assert_eq!(model_modified_at("stable-model:latest"), first);
+    assert!(chrono::DateTime::parse_from_rfc3339(&first).is_ok());
+}
@@ -0,0 +1,149 @@
+mod common;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use moka::future::Cache;
+use tokio_util::sync::CancellationToken;
+
+use ollama_lmstudio_proxy_rust::model::NativeModelData;
+use ollama_lmstudio_proxy_rust::server::build_http_client;
+use ollama_lmstudio_proxy_rust::{ModelIndex, ModelInfo, ModelResolver};
+
+use common::{serve, Reply};
+
+fn model(id: &str, state: &str) -> ModelInfo {
+    ModelInfo::from_native_data(&NativeModelData {
+        id: id.to_string(),
+        object: "model".to_string(),
+        model_type: "llm".to_string(),
+        publisher: Some("test".to_string()),
+        arch: "llama".to_string(),
+        compatibility_type: "gguf".to_string(),
+        quantization: "Q4_K_M".to_string(),
+        state: state.to_string(),
+        max_context_length: 4096,
+        chat_template: None,
+    })
+}
+
+fn synthetic_models(count: usize) -> Vec<ModelInfo> {
+    const FAMILIES: &[&str] = &["llama", "qwen", "mistral", "gemma", "phi"];
+    (0..count)
+        .map(|i| {
+            let id = format!(
+                "publisher-{}/{}-{}b-variant-{}-instruct",
+                i % 17,
+                FAMILIES[i % FAMILIES.len()],
+                (i % 9) + 1,
+                i
+            );
+            model(&id, if i % 7 == 0 { "loaded" } else { "not-loaded" })
+        })
+        .collect()
+}
+
+#[test]
+fn resolves_500_synthetic_models() {
+    let models = synthetic_models(500);
+    let ids: Vec<String> = models.iter().map(|m| m.id.clone()).collect();
+    let index = ModelIndex::new(models);
+    assert_eq!(index.len(), 500);
+
+    let start = Instant::now();
+    for id in &ids {
+        let matched = index.find_best_match(&id.to_uppercase()).expect("exact match");
+        assert_eq!(&matched.id, id);
+    }
+    for i in 0..500 {
+        let query = format!("variant-{}-instruct", i);
+        assert!(index.find_best_match(&query).is_some(), "no match for {}", query);
+    }
+    let elapsed = start.elapsed();
+    println!("1000 resolutions over 500 models: {:?}", elapsed);
+    assert!(elapsed.as_secs() < 5, "resolution too slow: {:?}", elapsed);
+}
+
+#[test]
+fn prefix_match_prefers_loaded_then_shortest() {
+    let index = ModelIndex::new(vec![
+        model("qwen2.5-coder-7b-instruct", "not-loaded"),
+        model("qwen2.5-7b-instruct-long", "not-loaded"),
+        model("qwen2.5-7b-instruct-mlx", "loaded"),
+    ]);
+    assert_eq!(
+        index.find_best_match("qwen2.5-7b-instruct").unwrap().id,
+        "qwen2.5-7b-instruct-mlx"
+    );
+
+    let index = ModelIndex::new(vec![
+        model("qwen2.5-7b-instruct-long", "not-loaded"),
+        model("qwen2.5-7b-instruct-q8", "not-loaded"),
+    ]);
+    assert_eq!(
+        index.find_best_match("qwen2.5-7b-instruct").unwrap().id,
+        "qwen2.5-7b-instruct-q8"
+    );
+}
+
+#[test]
+fn scoring_is_independent_of_list_order() {
+    let mut models = vec![
+        model("org/llama-3-8b-chat", "not-loaded"),
+        model("org/llama-3-8b-chat-v2", "not-loaded"),
+        model("other/mistral-7b", "loaded"),
+    ];
+    let forward = ModelIndex::new(models.clone())
+        .find_best_match("llama 8b chat")
+        .map(|m| m.id.clone());
+    models.reverse();
+    let reversed = ModelIndex::new(models)
+        .find_best_match("llama 8b chat")
+        .map(|m| m.id.clone());
+    assert_eq!(forward, reversed);
+    assert_eq!(forward.as_deref(), Some("org/llama-3-8b-chat"));
+}
+
+#[test]
+fn unknown_model_has_no_match() {
+    let index = ModelIndex::new(synthetic_models(50));
+    assert!(index.find_best_match("zz").is_none());
+}
+
+const NATIVE_MODELS: &str = r#"{"object":"list","data":[
+    {"id":"qwen2.5-7b-instruct","object":"model","type":"llm","publisher":"qwen","arch":"qwen2","compatibility_type":"gguf","quantization":"Q8_0","state":"loaded","max_context_length":32768},
+    {"id":"meta-llama-3.1-8b-instruct","object":"model","type":"llm","publisher":"meta","arch":"llama","compatibility_type":"gguf","quantization":"Q4_K_M","state":"loaded","max_context_length":8192}
+]}"#;
+
+/// Native LM Studio that counts model list requests
+async fn serve_counted_models(list_requests: Arc<AtomicUsize>) -> String {
+    serve(move |request| {
+        if request.starts_with("GET /api/v0/models ") {
+            list_requests.fetch_add(1, Ordering::SeqCst);
+            Reply::json(NATIVE_MODELS)
+        } else {
+            Reply::not_found()
+        }
+    })
+    .await
+}
+
+#[tokio::test]
+async fn cache_misses_share_one_indexed_model_list() {
+    let list_requests = Arc::new(AtomicUsize::new(0));
+    let url = serve_counted_models(list_requests.clone()).await;
+    let cache = Cache::builder().time_to_live(Duration::from_secs(300)).build();
+    let resolver = ModelResolver::new(url, cache);
+    let client = build_http_client().unwrap();
+
+    for name in ["qwen2.5-7b", "llama-3.1-8b-instruct", "meta-llama-3.1-8b"] {
+        resolver.resolve_model(name, &client, CancellationToken::new()).await.unwrap();
+    }
+    assert!(resolver.find_model_info("qwen2.5-7b-instruct", &client, CancellationToken::new()).await.is_some());
+    assert_eq!(list_requests.load(Ordering::SeqCst), 1);
+
+    // A name the cached list cannot match refetches in case the model was added since
+    assert!(resolver.resolve_model("zz", &client, CancellationToken::new()).await.is_err());
+    assert_eq!(list_requests.load(Ordering::SeqCst), 2);
+}
@@ -0,0 +1,45 @@
+mod common;
+
+use std::time::{Duration, Instant};
+
+use serde_json::json;
+use tokio::net::TcpListener;
+use tokio_util::sync::CancellationToken;
+
+use ollama_lmstudio_proxy_rust::handlers::ollama::handle_ollama_chat;
+
+use common::{context, read_request, server};
+
+/// LM Studio that accepts connections but never answers
+async fn serve_stalled() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    tokio::spawn(async move {
+        loop {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            tokio::spawn(async move {
+                read_request(&mut socket).await;
+                tokio::time::sleep(Duration::from_secs(30)).await;
+            });
+        }
+    });
+
+    format!("http://{}", addr)
+}
+
+#[tokio::test]
+async fn stalled_model_list_fails_fast() {
+    let url = serve_stalled().await;
+    let server = server(&url, &["--model-list-timeout-seconds", "1"]);
+
+    let body = json!({"model": "qwen2.5-7b-instruct", "stream": false, "messages": [{"role": "user", "content": "hi"}]});
+    let start = Instant::now();
+    let error = handle_ollama_chat(context(&server), server.current_resolver(), body, CancellationToken::new(), &server.config)
+        .await
+        .unwrap_err();
+
+    assert!(start.elapsed() < Duration::from_secs(5), "{:?}", start.elapsed());
+    assert_eq!(error.status_code, 503);
+    assert!(error.message.contains("--model-list-timeout-seconds"), "{}", error.message);
+}
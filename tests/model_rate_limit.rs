@@ -0,0 +1,39 @@
+use clap::Parser;
+
+use ollama_lmstudio_proxy_rust::rate_limit::{check_model_rate_limit, init_model_rate_limits, parse_model_rate_limits};
+use ollama_lmstudio_proxy_rust::{validate_config, Config};
+
+#[test]
+fn parses_limit_specs() {
+    let limits = parse_model_rate_limits(&["Llama3.3:70B=0.5".to_string(), "qwen=2".to_string()]).unwrap();
+    assert_eq!(limits, vec![("llama3.3:70b".to_string(), 0.5), ("qwen".to_string(), 2.0)]);
+
+    for bad in ["model", "=1", "model=0", "model=-1", "model=fast", "model=1e-300", "model=1e30", "model=inf", "model=NaN"] {
+        assert!(parse_model_rate_limits(&[bad.to_string()]).is_err(), "{}", bad);
+    }
+    // The bounds themselves are accepted
+    assert_eq!(parse_model_rate_limits(&["a=0.001".to_string(), "b=1000000".to_string()]).unwrap().len(), 2);
+}
+
+#[test]
+fn limited_model_gets_429_while_others_pass() {
+    init_model_rate_limits(parse_model_rate_limits(&["llama3.3:70b=1".to_string(), "big-embedder-id=1".to_string()]).unwrap());
+
+    assert!(check_model_rate_limit("llama3.3:70b", "llama-3.3-70b-instruct").is_ok());
+    let error = check_model_rate_limit("LLAMA3.3:70b", "llama-3.3-70b-instruct").unwrap_err();
+    assert_eq!(error.status_code, 429);
+
+    // Matched by the resolved LM Studio id as well
+    assert!(check_model_rate_limit("embedder:latest", "big-embedder-id").is_ok());
+    assert!(check_model_rate_limit("embedder:latest", "big-embedder-id").is_err());
+
+    for _ in 0..20 {
+        assert!(check_model_rate_limit("qwen2.5:7b", "qwen2.5-7b-instruct").is_ok());
+    }
+}
+
+#[test]
+fn out_of_range_limits_fail_validation_instead_of_panicking_at_startup() {
+    let config = Config::parse_from(["proxy", "--model-rate-limit", "llama3=0.0000000001"]);
+    assert!(validate_config(&config).unwrap_err().starts_with("Invalid --model-rate-limit"));
+}
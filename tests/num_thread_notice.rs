@@ -0,0 +1,24 @@
+use serde_json::json;
+
+use ollama_lmstudio_proxy_rust::common::{
+    dropped_ollama_options, map_ollama_to_lmstudio_params, newly_dropped_ollama_options, num_thread_notice,
+};
+use ollama_lmstudio_proxy_rust::constants::NOTICE_NUM_THREAD_IGNORED;
+
+#[test]
+fn num_thread_is_dropped_and_explained_once_per_process() {
+    let options = json!({"num_thread": 8, "temperature": 0.3});
+    assert_eq!(num_thread_notice(&json!({"temperature": 0.3})), None);
+    assert_eq!(num_thread_notice(&options), Some(NOTICE_NUM_THREAD_IGNORED));
+    assert_eq!(num_thread_notice(&options), None);
+
+    assert_eq!(dropped_ollama_options(&options), vec!["num_thread"]);
+    assert!(!map_ollama_to_lmstudio_params(Some(&options)).contains_key("num_thread"));
+}
+
+#[test]
+fn dropped_options_are_logged_once_per_name() {
+    assert_eq!(newly_dropped_ollama_options(&json!({"num_batch": 8, "temperature": 0.3})), vec!["num_batch"]);
+    assert!(newly_dropped_ollama_options(&json!({"num_batch": 16})).is_empty());
+    assert_eq!(newly_dropped_ollama_options(&json!({"num_batch": 16, "numa": true})), vec!["numa"]);
+}
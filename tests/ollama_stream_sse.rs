@@ -0,0 +1,44 @@
+mod common;
+
+use std::time::Instant;
+
+use serde_json::Value;
+use tokio_util::sync::CancellationToken;
+
+use ollama_lmstudio_proxy_rust::constants::{init_runtime_config, OllamaStreamFormat, RuntimeConfig};
+use ollama_lmstudio_proxy_rust::handlers::handle_streaming_response;
+use ollama_lmstudio_proxy_rust::server::build_http_client;
+
+use common::serve_sse_once;
+
+/// A one-token chat stream
+const SSE_BODY: &str = "data: {\"choices\":[{\"delta\":{\"content\":\"Hi\"}}]}\n\ndata: [DONE]\n\n";
+
+#[tokio::test]
+async fn chat_stream_is_framed_as_sse() {
+    init_runtime_config(RuntimeConfig {
+        ollama_stream_format: OllamaStreamFormat::Sse,
+        ..RuntimeConfig::default()
+    });
+    let url = serve_sse_once(SSE_BODY).await;
+    let upstream = build_http_client().unwrap().get(&url).send().await.unwrap();
+
+    let reply = handle_streaming_response(upstream, true, "m:latest", "now", Instant::now(), CancellationToken::new(), 5, None)
+        .await
+        .unwrap();
+    assert!(reply.headers()["content-type"].to_str().unwrap().starts_with("text/event-stream"));
+
+    let body = warp::hyper::body::to_bytes(reply.into_body()).await.unwrap();
+    let body = String::from_utf8(body.to_vec()).unwrap();
+    assert!(body.ends_with("data: [DONE]\n\n"), "{}", body);
+
+    let events: Vec<&str> = body.split_terminator("\n\n").collect();
+    let (done, chunks) = events.split_last().unwrap();
+    assert_eq!(*done, "data: [DONE]");
+    let chunks: Vec<Value> = chunks
+        .iter()
+        .map(|event| serde_json::from_str(event.strip_prefix("data: ").expect(event)).unwrap())
+        .collect();
+    assert_eq!(chunks.first().unwrap()["message"]["content"], "Hi");
+    assert_eq!(chunks.last().unwrap()["done"], true);
+}
@@ -0,0 +1,154 @@
+use serde_json::{json, Value};
+
+use ollama_lmstudio_proxy_rust::common::{dropped_ollama_options, map_ollama_to_lmstudio_params};
+use ollama_lmstudio_proxy_rust::handlers::{
+    build_lm_studio_request, merge_top_level_options, response_format_for, validate_sampling_options,
+    LMStudioRequestType,
+};
+
+#[test]
+fn null_options_are_treated_as_absent() {
+    assert!(map_ollama_to_lmstudio_params(Some(&Value::Null)).is_empty());
+}
+
+#[test]
+fn array_options_are_treated_as_absent() {
+    assert!(map_ollama_to_lmstudio_params(Some(&json!([]))).is_empty());
+    assert!(map_ollama_to_lmstudio_params(Some(&json!([{"temperature": 0.1}]))).is_empty());
+}
+
+#[test]
+fn wrong_type_options_leave_request_intact() {
+    let messages = json!([{"role": "user", "content": "hi"}]);
+    for options in [Value::Null, json!([]), json!("fast"), json!(3)] {
+        let request = build_lm_studio_request(
+            "model-a",
+            LMStudioRequestType::Chat { messages: &messages, stream: false },
+            Some(&options),
+            None,
+            false,
+        );
+        assert_eq!(
+            request,
+            json!({"model": "model-a", "messages": messages, "stream": false})
+        );
+    }
+}
+
+#[test]
+fn object_options_are_still_mapped() {
+    let params = map_ollama_to_lmstudio_params(Some(&json!({"temperature": 0.2, "num_predict": 8})));
+    assert_eq!(params.get("temperature"), Some(&json!(0.2)));
+    assert_eq!(params.get("max_tokens"), Some(&json!(8)));
+}
+
+#[test]
+fn string_stop_is_wrapped_in_an_array() {
+    let params = map_ollama_to_lmstudio_params(Some(&json!({"stop": "\n\n"})));
+    assert_eq!(params["stop"], json!(["\n\n"]));
+}
+
+#[test]
+fn array_stop_is_forwarded() {
+    let params = map_ollama_to_lmstudio_params(Some(&json!({"stop": ["</s>", "User:"]})));
+    assert_eq!(params["stop"], json!(["</s>", "User:"]));
+}
+
+#[test]
+fn stop_is_capped_at_four_sequences() {
+    let params = map_ollama_to_lmstudio_params(Some(&json!({"stop": ["a", "b", "c", "d", "e", "f"]})));
+    assert_eq!(params["stop"], json!(["a", "b", "c", "d"]));
+}
+
+#[test]
+fn repeat_penalty_reaches_backend() {
+    let params = map_ollama_to_lmstudio_params(Some(&json!({"repeat_penalty": 1.1, "repeat_last_n": 64})));
+    assert_eq!(params.get("repeat_penalty"), Some(&json!(1.1)));
+    assert!(!params.contains_key("repeat_last_n"));
+}
+
+#[test]
+fn zero_repeat_last_n_disables_repeat_penalty() {
+    let params = map_ollama_to_lmstudio_params(Some(&json!({"repeat_penalty": 1.1, "repeat_last_n": 0})));
+    assert!(params.is_empty());
+}
+
+#[test]
+fn unsupported_penalties_are_reported_as_dropped() {
+    let options = json!({"repeat_penalty": 1.1, "repeat_last_n": 64, "penalize_newline": true, "temperature": 0.2});
+    assert_eq!(dropped_ollama_options(&options), vec!["repeat_last_n", "penalize_newline"]);
+    assert!(dropped_ollama_options(&json!({"temperature": 0.2})).is_empty());
+}
+
+#[test]
+fn top_level_temperature_on_chat_body_reaches_backend() {
+    let mut body = json!({
+        "model": "model-a",
+        "messages": [{"role": "user", "content": "hi"}],
+        "temperature": 0.2,
+        "max_tokens": 64
+    });
+    merge_top_level_options(&mut body);
+    assert_eq!(body["options"], json!({"temperature": 0.2, "num_predict": 64}));
+    assert!(body.get("temperature").is_none());
+
+    let request = build_lm_studio_request(
+        "model-a",
+        LMStudioRequestType::Chat { messages: &body["messages"], stream: false },
+        body.get("options"),
+        None,
+        false,
+    );
+    assert_eq!(request["temperature"], json!(0.2));
+}
+
+#[test]
+fn nested_options_take_precedence_over_top_level() {
+    let mut body = json!({"model": "model-a", "prompt": "hi", "temperature": 0.9, "top_k": 20, "options": {"temperature": 0.1}});
+    merge_top_level_options(&mut body);
+    assert_eq!(body["options"], json!({"temperature": 0.1, "top_k": 20}));
+}
+
+#[test]
+fn json_format_enables_json_mode() {
+    assert_eq!(response_format_for(Some(&json!("json"))), Some(json!({"type": "json_object"})));
+    let schema = json!({"type": "object", "properties": {"age": {"type": "integer"}}});
+    assert_eq!(
+        response_format_for(Some(&schema)),
+        Some(json!({"type": "json_schema", "json_schema": {"name": "response", "strict": true, "schema": schema}}))
+    );
+}
+
+#[test]
+fn empty_or_unknown_format_does_not_force_json_mode() {
+    for format in [json!(""), json!("  "), json!("xml"), json!("JSON "), json!({}), json!(true), Value::Null] {
+        assert_eq!(response_format_for(Some(&format)), None, "format {}", format);
+    }
+    assert_eq!(response_format_for(None), None);
+}
+
+#[test]
+fn min_p_is_forwarded() {
+    let params = map_ollama_to_lmstudio_params(Some(&json!({"min_p": 0.05})));
+    assert_eq!(params.get("min_p"), Some(&json!(0.05)));
+    assert!(dropped_ollama_options(&json!({"min_p": 0.05})).is_empty());
+}
+
+#[test]
+fn out_of_range_sampling_options_are_clamped() {
+    let mut body = json!({"options": {"temperature": -1, "top_p": 1.5, "top_k": -5, "min_p": 0.1}});
+    validate_sampling_options(&mut body, false).unwrap();
+    assert_eq!(body["options"], json!({"temperature": 0, "top_p": 1.0, "top_k": 0, "min_p": 0.1}));
+}
+
+#[test]
+fn strict_params_reject_negative_temperature() {
+    let mut body = json!({"options": {"temperature": -1}});
+    let error = validate_sampling_options(&mut body, true).unwrap_err();
+    assert_eq!(error.status_code, 400);
+    assert!(error.message.contains("temperature"));
+    assert_eq!(body["options"]["temperature"], json!(-1));
+
+    let mut valid = json!({"options": {"temperature": 0.7, "top_p": 1, "top_k": 40, "min_p": 0}});
+    assert!(validate_sampling_options(&mut valid, true).is_ok());
+}
@@ -0,0 +1,62 @@
+use serde_json::{json, Value};
+use warp::Filter;
+
+use ollama_lmstudio_proxy_rust::handlers::lmstudio::passthrough_body;
+use ollama_lmstudio_proxy_rust::utils::ProxyError;
+
+fn route() -> impl Filter<Extract = (Value,), Error = warp::Rejection> + Clone {
+    warp::path("v1")
+        .and(warp::path::tail())
+        .and(passthrough_body())
+        .map(|_tail: warp::path::Tail, body: Value| body)
+}
+
+#[tokio::test]
+async fn invalid_json_is_rejected_with_400() {
+    let rejection = warp::test::request()
+        .method("POST")
+        .path("/v1/chat/completions")
+        .header("content-type", "application/json")
+        .body(r#"{"model": "qwen", "messages": ["#)
+        .filter(&route())
+        .await
+        .unwrap_err();
+
+    let error = rejection.find::<ProxyError>().expect("ProxyError rejection");
+    assert_eq!(error.status_code, 400);
+    assert!(error.message.contains("not valid JSON"));
+}
+
+#[tokio::test]
+async fn chunked_json_body_is_parsed() {
+    let body = warp::test::request()
+        .method("POST")
+        .path("/v1/embeddings")
+        .header("transfer-encoding", "chunked")
+        .body(r#"{"model": "nomic", "input": "hi"}"#)
+        .filter(&route())
+        .await
+        .unwrap();
+
+    assert_eq!(body, json!({ "model": "nomic", "input": "hi" }));
+}
+
+#[tokio::test]
+async fn missing_body_is_null() {
+    let body = warp::test::request()
+        .method("GET")
+        .path("/v1/models")
+        .filter(&route())
+        .await
+        .unwrap();
+    assert_eq!(body, Value::Null);
+
+    let body = warp::test::request()
+        .method("POST")
+        .path("/v1/models")
+        .body(" \n")
+        .filter(&route())
+        .await
+        .unwrap();
+    assert_eq!(body, Value::Null);
+}
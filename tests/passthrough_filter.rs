@@ -0,0 +1,31 @@
+use ollama_lmstudio_proxy_rust::handlers::is_passthrough_allowed;
+
+fn patterns(values: &[&str]) -> Vec<String> {
+    values.iter().map(|value| value.to_string()).collect()
+}
+
+#[test]
+fn denylist_prefixes_ignore_case() {
+    let deny = patterns(&["/v1/embeddings*"]);
+    assert!(!is_passthrough_allowed("/v1/embeddings", &[], &deny));
+    assert!(!is_passthrough_allowed("/v1/Embeddings", &[], &deny));
+    assert!(!is_passthrough_allowed("/V1/EMBEDDINGS/extra", &[], &deny));
+    assert!(is_passthrough_allowed("/v1/chat/completions", &[], &deny));
+}
+
+#[test]
+fn allowlist_restricts_and_denylist_wins() {
+    let allow = patterns(&["/v1/chat/*", "/v1/models"]);
+    let deny = patterns(&["/V1/Chat/Completions"]);
+    assert!(is_passthrough_allowed("/v1/models/", &allow, &deny));
+    assert!(is_passthrough_allowed("/v1/CHAT/other", &allow, &deny));
+    assert!(!is_passthrough_allowed("/v1/chat/completions", &allow, &deny));
+    assert!(!is_passthrough_allowed("/v1/embeddings", &allow, &deny));
+}
+
+#[test]
+fn prefix_longer_than_path_does_not_match() {
+    let deny = patterns(&["/v1/chat/completions*"]);
+    assert!(is_passthrough_allowed("/v1/chat", &[], &deny));
+    assert!(is_passthrough_allowed("/v1/ü", &[], &patterns(&["/v1/u*"])));
+}
@@ -0,0 +1,52 @@
+mod common;
+
+use std::sync::{Arc, Mutex};
+
+use moka::future::Cache;
+use serde_json::{json, Value};
+use tokio_util::sync::CancellationToken;
+
+use ollama_lmstudio_proxy_rust::handlers::handle_lmstudio_passthrough;
+use ollama_lmstudio_proxy_rust::server::build_http_client;
+use ollama_lmstudio_proxy_rust::{init_runtime_config, ModelResolverLegacy, ModelResolverType, RuntimeConfig};
+
+use common::{bare_context, request_body, request_line, serve, Reply};
+
+/// LM Studio recording every request line and chat body it receives
+async fn serve_recording(requests: Arc<Mutex<Vec<(String, Value)>>>) -> String {
+    serve(move |request| {
+        requests.lock().unwrap().push((request_line(request).to_string(), request_body(request)));
+        Reply::json(r#"{"object":"list","data":[{"id":"qwen2.5-7b-instruct","object":"model"}]}"#)
+    })
+    .await
+}
+
+#[tokio::test]
+async fn model_is_forwarded_verbatim_when_resolution_is_off() {
+    init_runtime_config(RuntimeConfig {
+        passthrough_model_resolution: false,
+        ..RuntimeConfig::default()
+    });
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let url = serve_recording(requests.clone()).await;
+    let client = build_http_client().unwrap();
+    let resolver = ModelResolverType::Legacy(Arc::new(ModelResolverLegacy::new_legacy(url.clone(), Cache::new(10))));
+    let context = bare_context(&client, &url);
+
+    handle_lmstudio_passthrough(
+        context,
+        resolver,
+        "POST",
+        "/v1/chat/completions",
+        json!({"model": "Qwen2.5-7B", "messages": [{"role": "user", "content": "hi"}]}),
+        CancellationToken::new(),
+        30,
+    )
+    .await
+    .unwrap();
+
+    let requests = requests.lock().unwrap();
+    assert_eq!(requests.len(), 1, "no model list lookup expected: {:?}", requests);
+    assert!(requests[0].0.starts_with("POST /v1/chat/completions "));
+    assert_eq!(requests[0].1["model"], "Qwen2.5-7B");
+}
@@ -0,0 +1,103 @@
+mod common;
+
+use std::sync::Arc;
+
+use moka::future::Cache;
+use serde_json::json;
+use tokio::io::AsyncWriteExt;
+use tokio::net::TcpListener;
+use tokio_util::sync::CancellationToken;
+
+use ollama_lmstudio_proxy_rust::handlers::handle_lmstudio_passthrough;
+use ollama_lmstudio_proxy_rust::server::build_http_client;
+use ollama_lmstudio_proxy_rust::{ModelResolverLegacy, ModelResolverType};
+
+use common::{bare_context, read_request};
+
+/// Serve one raw HTTP response and return the base URL
+async fn serve_once(status_line: &'static str, body: &'static str) -> String {
+    serve_once_as(status_line, "application/json", body.as_bytes()).await
+}
+
+/// Serve one raw HTTP response with the given Content-Type and return the base URL
+async fn serve_once_as(status_line: &'static str, content_type: &'static str, body: &'static [u8]) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    tokio::spawn(async move {
+        let (mut socket, _) = listener.accept().await.unwrap();
+        read_request(&mut socket).await;
+        let head = format!(
+            "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            status_line,
+            content_type,
+            body.len()
+        );
+        let _ = socket.write_all(head.as_bytes()).await;
+        let _ = socket.write_all(body).await;
+    });
+
+    format!("http://{}", addr)
+}
+
+async fn passthrough(url: String) -> warp::reply::Response {
+    let client = build_http_client().unwrap();
+    let resolver = ModelResolverType::Legacy(Arc::new(ModelResolverLegacy::new_legacy(url.clone(), Cache::new(10))));
+    handle_lmstudio_passthrough(
+        bare_context(&client, &url),
+        resolver,
+        "POST",
+        "/v1/jobs",
+        json!({"input": "x"}),
+        CancellationToken::new(),
+        30,
+    )
+    .await
+    .unwrap()
+}
+
+async fn passthrough_status(url: String) -> u16 {
+    passthrough(url).await.status().as_u16()
+}
+
+#[tokio::test]
+async fn preserves_non_200_success_status() {
+    let url = serve_once("202 Accepted", r#"{"id":"job-1","status":"queued"}"#).await;
+    assert_eq!(passthrough_status(url).await, 202);
+}
+
+#[tokio::test]
+async fn forwards_empty_no_content_responses() {
+    let url = serve_once("204 No Content", "").await;
+    assert_eq!(passthrough_status(url).await, 204);
+}
+
+#[tokio::test]
+async fn forwards_plain_text_as_is() {
+    let url = serve_once_as("200 OK", "text/plain; charset=utf-8", b"model loaded, not json").await;
+    let response = passthrough(url).await;
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(response.headers()["content-type"], "text/plain; charset=utf-8");
+    let body = warp::hyper::body::to_bytes(response.into_body()).await.unwrap();
+    assert_eq!(&body[..], b"model loaded, not json");
+}
+
+#[tokio::test]
+async fn forwards_binary_bodies_byte_for_byte() {
+    const PNG: &[u8] = &[0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a, 0x00, 0xff, 0xfe];
+    let url = serve_once_as("201 Created", "image/png", PNG).await;
+    let response = passthrough(url).await;
+    assert_eq!(response.status().as_u16(), 201);
+    assert_eq!(response.headers()["content-type"], "image/png");
+    let body = warp::hyper::body::to_bytes(response.into_body()).await.unwrap();
+    assert_eq!(&body[..], PNG);
+}
+
+#[tokio::test]
+async fn json_suffix_types_are_still_parsed() {
+    let url = serve_once_as("200 OK", "application/vnd.lmstudio+json", br#"{ "ok" : true }"#).await;
+    let response = passthrough(url).await;
+    assert!(response.headers()["content-type"].to_str().unwrap().starts_with("application/json"));
+    let body = warp::hyper::body::to_bytes(response.into_body()).await.unwrap();
+    assert_eq!(&body[..], br#"{"ok":true}"#);
+}
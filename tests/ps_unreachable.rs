@@ -0,0 +1,40 @@
+mod common;
+
+use serde_json::Value;
+use tokio_util::sync::CancellationToken;
+
+use ollama_lmstudio_proxy_rust::handlers::handle_ollama_ps;
+use ollama_lmstudio_proxy_rust::ProxyError;
+
+use common::{context, server, unreachable_url};
+
+async fn ps(args: &[&str]) -> Result<warp::reply::Response, ProxyError> {
+    let url = unreachable_url().await;
+    let server = server(&url, args);
+    handle_ollama_ps(
+        context(&server),
+        server.current_resolver(),
+        CancellationToken::new(),
+        server.config.ps_error_on_unreachable,
+    )
+    .await
+}
+
+#[tokio::test]
+async fn unreachable_backend_lists_no_models_by_default() {
+    for args in [&[][..], &["--legacy"][..]] {
+        let response = ps(args).await.unwrap();
+        let body = warp::hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let body: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["models"].as_array().unwrap().len(), 0);
+    }
+}
+
+#[tokio::test]
+async fn unreachable_backend_is_503_when_enabled() {
+    for args in [&["--ps-error-on-unreachable"][..], &["--ps-error-on-unreachable", "--legacy"][..]] {
+        let error = ps(args).await.unwrap_err();
+        assert_eq!(error.status_code, 503);
+        assert!(error.message.contains("LM Studio not available"));
+    }
+}
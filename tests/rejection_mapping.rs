@@ -0,0 +1,82 @@
+use serde_json::Value;
+use warp::{Filter, Rejection};
+
+use ollama_lmstudio_proxy_rust::server::handle_rejection;
+use ollama_lmstudio_proxy_rust::ProxyError;
+
+/// Status code and `error.type` of the reply handle_rejection builds for a rejected request
+async fn reject_with<F>(filter: F, request: warp::test::RequestBuilder) -> (u16, String, Value)
+where
+    F: Filter<Extract = (&'static str,), Error = Rejection> + Clone + Send + Sync + 'static,
+{
+    let response = request.reply(&filter.recover(handle_rejection)).await;
+    let body: Value = serde_json::from_slice(response.body()).unwrap();
+    let error_type = body["error"]["type"].as_str().unwrap().to_string();
+    assert_eq!(body["error"]["code"], response.status().as_u16());
+    (response.status().as_u16(), error_type, body)
+}
+
+fn failing_with(error: ProxyError) -> impl Filter<Extract = (&'static str,), Error = Rejection> + Clone {
+    warp::any().and_then(move || {
+        let error = error.clone();
+        async move { Err::<&'static str, _>(warp::reject::custom(error)) }
+    })
+}
+
+#[tokio::test]
+async fn unknown_path_is_not_found() {
+    let route = warp::path("api").map(|| "ok");
+    let (status, error_type, _) = reject_with(route, warp::test::request().path("/missing")).await;
+    assert_eq!((status, error_type.as_str()), (404, "not_found_error"));
+}
+
+#[tokio::test]
+async fn proxy_errors_keep_their_status_and_type() {
+    let cases = [
+        (ProxyError::bad_request("bad"), 400, "bad_request_error"),
+        (ProxyError::new("no key".to_string(), 401), 401, "authentication_error"),
+        (ProxyError::forbidden("forbidden"), 403, "permission_error"),
+        (ProxyError::not_found("missing"), 404, "not_found_error"),
+        (ProxyError::new("too big".to_string(), 413), 413, "payload_too_large_error"),
+        (ProxyError::too_many_requests("slow down"), 429, "rate_limit_error"),
+        (ProxyError::request_cancelled(), 499, "client_closed_request"),
+        (ProxyError::internal_server_error("boom"), 500, "internal_server_error"),
+        (ProxyError::not_implemented("later"), 501, "not_implemented_error"),
+        (ProxyError::lm_studio_unavailable("down"), 503, "service_unavailable_error"),
+        (ProxyError::gateway_timeout("slow"), 504, "timeout_error"),
+        (ProxyError::new("teapot".to_string(), 418), 418, "api_error"),
+    ];
+
+    for (error, expected_status, expected_type) in cases {
+        let message = error.message.clone();
+        let (status, error_type, body) = reject_with(failing_with(error), warp::test::request()).await;
+        assert_eq!((status, error_type.as_str()), (expected_status, expected_type), "{}", message);
+        assert_eq!(body["error"]["message"], message);
+    }
+}
+
+#[tokio::test]
+async fn wrong_method_is_method_not_allowed() {
+    let route = warp::post().map(|| "ok");
+    let (status, error_type, _) = reject_with(route, warp::test::request().method("GET")).await;
+    assert_eq!((status, error_type.as_str()), (405, "method_not_allowed_error"));
+}
+
+#[tokio::test]
+async fn oversized_body_is_payload_too_large() {
+    let route = warp::body::content_length_limit(8).and(warp::body::bytes()).map(|_| "ok");
+    let request = warp::test::request().method("POST").body("a body longer than eight bytes");
+    let (status, error_type, _) = reject_with(route, request).await;
+    assert_eq!((status, error_type.as_str()), (413, "payload_too_large_error"));
+}
+
+#[tokio::test]
+async fn non_json_body_is_unsupported_media_type() {
+    let route = warp::body::json().map(|_: Value| "ok");
+    let request = warp::test::request()
+        .method("POST")
+        .header("content-type", "text/plain")
+        .body("{}");
+    let (status, error_type, _) = reject_with(route, request).await;
+    assert_eq!((status, error_type.as_str()), (415, "unsupported_media_type_error"));
+}
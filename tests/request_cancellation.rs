@@ -0,0 +1,98 @@
+mod common;
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use serde_json::json;
+use tokio::io::AsyncWriteExt;
+use tokio::net::TcpListener;
+
+use ollama_lmstudio_proxy_rust::handlers::handle_cancel_request;
+use ollama_lmstudio_proxy_rust::handlers::ollama::handle_ollama_chat;
+use ollama_lmstudio_proxy_rust::request_registry::RequestRegistry;
+
+use common::{context, read_request, server, Reply};
+
+const NATIVE_MODELS: &str = r#"{"object":"list","data":[
+    {"id":"qwen2.5-7b-instruct","object":"model","type":"llm","publisher":"qwen","arch":"qwen2","compatibility_type":"gguf","quantization":"Q4_K_M","state":"loaded","max_context_length":32768}
+]}"#;
+
+/// Native LM Studio that lists a model but never answers a chat request
+async fn serve_stalled_chat() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    tokio::spawn(async move {
+        loop {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            tokio::spawn(async move {
+                let request = read_request(&mut socket).await;
+                if !request.starts_with("GET /api/v0/models ") {
+                    tokio::time::sleep(Duration::from_secs(30)).await;
+                    return;
+                }
+                let _ = socket.write_all(Reply::json(NATIVE_MODELS).to_http().as_bytes()).await;
+            });
+        }
+    });
+
+    format!("http://{}", addr)
+}
+
+#[test]
+fn registered_ids_are_cancellable_until_the_guard_drops() {
+    let registry = Arc::new(RequestRegistry::new());
+
+    let (token, registration) = registry.register(Some("req-1"));
+    assert!(registry.cancel("req-1"));
+    assert!(token.is_cancelled());
+    assert!(!registry.cancel("req-1"));
+    drop(registration);
+
+    let (token, registration) = registry.register(Some("req-2"));
+    drop(registration);
+    assert!(!registry.cancel("req-2"));
+    assert!(!token.is_cancelled());
+
+    let (_, without_id) = registry.register(None);
+    assert!(registry.is_empty());
+    drop(without_id);
+}
+
+#[test]
+fn a_reused_id_belongs_to_the_newest_request() {
+    let registry = Arc::new(RequestRegistry::new());
+    let (older_token, older) = registry.register(Some("req"));
+    let (newer_token, _newer) = registry.register(Some("req"));
+
+    drop(older);
+    assert!(registry.cancel("req"));
+    assert!(newer_token.is_cancelled());
+    assert!(!older_token.is_cancelled());
+}
+
+#[tokio::test]
+async fn delete_cancels_a_running_chat() {
+    let url = serve_stalled_chat().await;
+    let server = server(&url, &[]);
+    let (token, _registration) = server.requests.register(Some("chat-42"));
+
+    let canceller = {
+        let requests = server.requests.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            handle_cancel_request(&requests, "chat-42").unwrap().status()
+        })
+    };
+
+    let body = json!({"model": "qwen2.5-7b-instruct", "stream": false, "messages": [{"role": "user", "content": "hi"}]});
+    let start = Instant::now();
+    let error = handle_ollama_chat(context(&server), server.current_resolver(), body, token, &server.config)
+        .await
+        .unwrap_err();
+
+    assert!(error.is_cancelled(), "{}", error.message);
+    assert!(start.elapsed() < Duration::from_secs(5));
+    assert_eq!(canceller.await.unwrap(), 200);
+    assert_eq!(handle_cancel_request(&server.requests, "chat-42").unwrap_err().status_code, 404);
+}
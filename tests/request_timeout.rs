@@ -0,0 +1,16 @@
+use ollama_lmstudio_proxy_rust::handlers::parse_request_timeout;
+
+#[test]
+fn accepts_values_within_limit() {
+    assert_eq!(parse_request_timeout(Some("120"), 3600), Some(120));
+    assert_eq!(parse_request_timeout(Some(" 3600 "), 3600), Some(3600));
+}
+
+#[test]
+fn ignores_missing_invalid_and_oversized_values() {
+    assert_eq!(parse_request_timeout(None, 3600), None);
+    assert_eq!(parse_request_timeout(Some("soon"), 3600), None);
+    assert_eq!(parse_request_timeout(Some("0"), 3600), None);
+    assert_eq!(parse_request_timeout(Some("-5"), 3600), None);
+    assert_eq!(parse_request_timeout(Some("3601"), 3600), None);
+}
@@ -0,0 +1,77 @@
+mod common;
+
+use std::collections::HashMap;
+
+use moka::future::Cache;
+use tokio_util::sync::CancellationToken;
+
+use ollama_lmstudio_proxy_rust::metrics;
+use ollama_lmstudio_proxy_rust::server::build_http_client;
+use ollama_lmstudio_proxy_rust::{ModelResolver, ModelResolverLegacy, ResolutionMethod};
+
+use common::{serve, Reply};
+
+const NATIVE_MODELS: &str = r#"{"object":"list","data":[
+    {"id":"qwen2.5-7b-instruct","object":"model","type":"llm","publisher":"qwen","arch":"qwen2","compatibility_type":"gguf","quantization":"Q8_0","state":"loaded","max_context_length":32768},
+    {"id":"meta-llama-3.1-8b-instruct","object":"model","type":"llm","publisher":"meta","arch":"llama","compatibility_type":"gguf","quantization":"Q4_K_M","state":"loaded","max_context_length":8192}
+]}"#;
+const LEGACY_MODELS: &str = r#"{"object":"list","data":[{"id":"qwen2.5-7b-instruct","object":"model"}]}"#;
+
+/// LM Studio answering both model list endpoints
+async fn serve_models() -> String {
+    serve(|request| {
+        if request.starts_with("GET /api/v0/models ") {
+            Reply::json(NATIVE_MODELS)
+        } else if request.starts_with("GET /v1/models ") {
+            Reply::json(LEGACY_MODELS)
+        } else {
+            Reply::not_found()
+        }
+    })
+    .await
+}
+
+#[tokio::test]
+async fn native_resolution_reports_its_method() {
+    let url = serve_models().await;
+    let pins = HashMap::from([("fast".to_string(), "qwen2.5-7b-instruct".to_string())]);
+    let resolver = ModelResolver::new(url, Cache::new(10)).with_pins(pins);
+    let client = build_http_client().unwrap();
+    let method = |name: &'static str| {
+        let (resolver, client) = (&resolver, &client);
+        async move { resolver.resolve_model(name, client, CancellationToken::new()).await.unwrap().method }
+    };
+
+    assert_eq!(method("qwen2.5-7b-instruct").await, ResolutionMethod::Exact);
+    assert_eq!(method("qwen2.5-7b-instruct").await, ResolutionMethod::Cache);
+    assert_eq!(method("qwen2.5-7b").await, ResolutionMethod::Substring);
+    assert_eq!(method("llama-3.1-instruct-8b").await, ResolutionMethod::Scored);
+    assert_eq!(method("fast").await, ResolutionMethod::Alias);
+}
+
+#[tokio::test]
+async fn legacy_resolution_falls_back_to_the_cleaned_name() {
+    let url = serve_models().await;
+    let resolver = ModelResolverLegacy::new_legacy(url, Cache::new(10));
+    let resolved = resolver
+        .resolve_model_legacy("unknown-model:latest", &build_http_client().unwrap(), CancellationToken::new())
+        .await
+        .unwrap();
+
+    assert_eq!(resolved.id, "unknown-model");
+    assert_eq!(resolved.method, ResolutionMethod::Fallback);
+}
+
+#[tokio::test]
+async fn measured_requests_record_the_method() {
+    let url = serve_models().await;
+    let resolver = ModelResolver::new(url, Cache::new(10));
+    let client = build_http_client().unwrap();
+
+    let (resolved, timing) =
+        metrics::measure(resolver.resolve_model("qwen2.5-7b", &client, CancellationToken::new())).await;
+
+    assert_eq!(resolved.unwrap().id, "qwen2.5-7b-instruct");
+    assert_eq!(timing.model.as_deref(), Some("qwen2.5-7b-instruct"));
+    assert_eq!(timing.resolution_method, Some("substring"));
+}
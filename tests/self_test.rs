@@ -0,0 +1,59 @@
+mod common;
+
+use ollama_lmstudio_proxy_rust::self_test::{self_test_steps, StepOutcome, StepReport};
+
+use common::{serve, server, unreachable_url, Reply};
+
+const NATIVE_MODELS: &str = r#"{"object":"list","data":[
+    {"id":"qwen2.5-7b-instruct","object":"model","type":"llm","publisher":"qwen","arch":"qwen2","compatibility_type":"gguf","quantization":"Q4_K_M","state":"loaded","max_context_length":32768},
+    {"id":"text-embedding-nomic-embed-text-v1.5","object":"model","type":"embeddings","publisher":"nomic-ai","arch":"nomic-bert","compatibility_type":"gguf","quantization":"Q4_K_M","state":"loaded","max_context_length":2048}
+]}"#;
+
+const CHAT_RESPONSE: &str = r#"{"choices":[{"message":{"role":"assistant","content":"OK"},"finish_reason":"stop"}],"usage":{"prompt_tokens":5,"completion_tokens":1}}"#;
+const EMBEDDINGS_RESPONSE: &str = r#"{"object":"list","data":[{"object":"embedding","index":0,"embedding":[0.1,0.2,0.3]}]}"#;
+
+/// Native LM Studio with one chat model and one embedding model
+async fn serve_lm_studio() -> String {
+    serve(|request| {
+        if request.starts_with("GET /api/v0/models ") {
+            Reply::json(NATIVE_MODELS)
+        } else if request.starts_with("POST /api/v0/chat/completions ") {
+            Reply::json(CHAT_RESPONSE)
+        } else if request.starts_with("POST /api/v0/embeddings ") {
+            Reply::json(EMBEDDINGS_RESPONSE)
+        } else {
+            Reply::not_found()
+        }
+    })
+    .await
+}
+
+async fn run(url: &str) -> Vec<StepReport> {
+    let server = server(url, &["--self-test"]);
+    let mut seen = Vec::new();
+    let reports = self_test_steps(&server, |report| seen.push(report.name)).await;
+    assert_eq!(seen, reports.iter().map(|report| report.name).collect::<Vec<_>>());
+    reports
+}
+
+#[tokio::test]
+async fn all_steps_pass_against_working_backend() {
+    let url = serve_lm_studio().await;
+    let reports = run(&url).await;
+
+    let names: Vec<_> = reports.iter().map(|report| report.name).collect();
+    assert_eq!(names, ["List models", "Resolve model", "Chat", "Embed"]);
+    assert!(reports.iter().all(|report| matches!(report.outcome, StepOutcome::Passed(_))), "{:?}", reports);
+    assert_eq!(
+        reports[1].outcome,
+        StepOutcome::Passed("qwen2.5-7b-instruct:latest -> qwen2.5-7b-instruct".to_string())
+    );
+}
+
+#[tokio::test]
+async fn unreachable_backend_fails_and_skips_the_rest() {
+    let reports = run(&unreachable_url().await).await;
+
+    assert!(reports[0].failed());
+    assert!(reports[1..].iter().all(|report| matches!(report.outcome, StepOutcome::Skipped(_))));
+}
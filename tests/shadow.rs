@@ -0,0 +1,83 @@
+mod common;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde_json::json;
+use tokio::io::AsyncWriteExt;
+use tokio::net::TcpListener;
+
+use ollama_lmstudio_proxy_rust::metrics::ProxyMetrics;
+use ollama_lmstudio_proxy_rust::server::build_http_client;
+use ollama_lmstudio_proxy_rust::shadow::Shadow;
+
+use common::{read_request, Reply};
+
+/// Shadow backend that answers slowly, returning the base URL
+async fn serve_slow_ok() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    tokio::spawn(async move {
+        let (mut socket, _) = listener.accept().await.unwrap();
+        read_request(&mut socket).await;
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        let _ = socket.write_all(Reply::json(r#"{"choices":[]}"#).to_http().as_bytes()).await;
+    });
+
+    format!("http://{}", addr)
+}
+
+#[tokio::test]
+async fn mirrored_requests_do_not_block_and_are_recorded() {
+    let metrics = Arc::new(ProxyMetrics::new());
+    let shadow = Shadow::new(serve_slow_ok().await, build_http_client().unwrap(), metrics.clone());
+
+    shadow.mirror_request("/v1/chat/completions", &json!({"model": "m", "messages": []}));
+    // Returned before the shadow backend answered
+    assert_eq!(metrics.shadow_counts(), (0, 0));
+
+    for _ in 0..50 {
+        if metrics.shadow_counts().0 == 1 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    assert_eq!(metrics.shadow_counts(), (1, 0));
+    let snapshot = metrics.snapshot();
+    assert_eq!(snapshot["shadow_statuses"]["200"]["requests"], 1);
+    assert!(snapshot["shadow_avg_time_ms"].as_f64().unwrap() >= 100.0);
+}
+
+#[tokio::test]
+async fn mirrors_over_the_in_flight_cap_are_dropped() {
+    let metrics = Arc::new(ProxyMetrics::new());
+    let shadow = Shadow::with_max_in_flight(serve_slow_ok().await, build_http_client().unwrap(), metrics.clone(), 1);
+
+    let body = json!({"model": "m", "messages": []});
+    shadow.mirror_request("/v1/chat/completions", &body);
+    shadow.mirror_request("/v1/chat/completions", &body);
+    shadow.mirror_request("/v1/chat/completions", &body);
+    assert_eq!(metrics.shadow_dropped(), 2);
+    assert_eq!(metrics.snapshot()["shadow_dropped_total"], 2);
+
+    for _ in 0..50 {
+        if metrics.shadow_counts().0 == 1 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    // The slot is free again once the mirrored request finished
+    assert_eq!(metrics.shadow_counts(), (1, 0));
+    shadow.mirror_request("/v1/chat/completions", &body);
+    assert_eq!(metrics.shadow_dropped(), 2);
+}
+
+#[test]
+fn failed_shadow_requests_count_as_errors() {
+    let metrics = ProxyMetrics::new();
+    metrics.record_shadow(Duration::from_millis(5), None);
+    metrics.record_shadow(Duration::from_millis(5), Some(500));
+    assert_eq!(metrics.shadow_counts(), (2, 2));
+    assert_eq!(metrics.snapshot()["shadow_statuses"]["error"]["errors"], 1);
+}
@@ -0,0 +1,74 @@
+mod common;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use serde_json::{json, Value};
+use tokio_util::sync::CancellationToken;
+
+use ollama_lmstudio_proxy_rust::handlers::handle_ollama_show;
+use ollama_lmstudio_proxy_rust::ProxyServer;
+
+use common::{context, serve, server, Reply};
+
+const NATIVE_MODELS: &str = r#"{"object":"list","data":[
+    {"id":"qwen2.5-7b-instruct","object":"model","type":"llm","publisher":"qwen","arch":"qwen2","compatibility_type":"gguf","quantization":"Q8_0","state":"loaded","max_context_length":32768,"chat_template":"{{ messages }}"},
+    {"id":"text-embedding-nomic-embed-text-v1.5","object":"model","type":"embeddings","publisher":"nomic-ai","arch":"nomic-bert","compatibility_type":"gguf","quantization":"Q4_K_M","state":"loaded","max_context_length":2048,"chat_template":"{{ input }}"}
+]}"#;
+
+/// Native LM Studio whose embeddings model returns 4-dimensional vectors; counts embed requests
+async fn serve_native_models(embed_requests: Arc<AtomicUsize>) -> String {
+    serve(move |request| {
+        if request.starts_with("GET /api/v0/models ") {
+            Reply::json(NATIVE_MODELS)
+        } else if request.starts_with("POST /api/v0/embeddings ") {
+            embed_requests.fetch_add(1, Ordering::SeqCst);
+            Reply::json(json!({"object": "list", "data": [{"object": "embedding", "embedding": [0.1, 0.2, 0.3, 0.4], "index": 0}]}))
+        } else {
+            Reply::not_found()
+        }
+    })
+    .await
+}
+
+async fn show(server: &ProxyServer, model: &str) -> Value {
+    let response = handle_ollama_show(context(server), json!({ "model": model }), server.current_resolver(), CancellationToken::new())
+        .await
+        .unwrap();
+    let body = warp::hyper::body::to_bytes(response.into_body()).await.unwrap();
+    serde_json::from_slice(&body).unwrap()
+}
+
+#[tokio::test]
+async fn embedding_length_is_probed_once_and_reported() {
+    let embed_requests = Arc::new(AtomicUsize::new(0));
+    let url = serve_native_models(embed_requests.clone()).await;
+    let server = server(&url, &[]);
+
+    for _ in 0..2 {
+        let body = show(&server, "text-embedding-nomic-embed-text-v1.5").await;
+        assert_eq!(body["model_info"]["nomic-bert.embedding_length"], 4);
+    }
+    assert_eq!(embed_requests.load(Ordering::SeqCst), 1);
+
+    let body = show(&server, "qwen2.5-7b-instruct").await;
+    assert!(body["model_info"].get("qwen2.embedding_length").is_none());
+    assert_eq!(embed_requests.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn probed_lengths_belong_to_the_resolver_and_expire_with_its_cache() {
+    let embed_requests = Arc::new(AtomicUsize::new(0));
+    let url = serve_native_models(embed_requests.clone()).await;
+    let args = ["--model-resolution-cache-ttl-seconds", "1"];
+    let first_server = server(&url, &args);
+
+    show(&first_server, "text-embedding-nomic-embed-text-v1.5").await;
+    let other_server = server(&url, &args);
+    show(&other_server, "text-embedding-nomic-embed-text-v1.5").await;
+    assert_eq!(embed_requests.load(Ordering::SeqCst), 2);
+
+    tokio::time::sleep(std::time::Duration::from_millis(1100)).await;
+    show(&first_server, "text-embedding-nomic-embed-text-v1.5").await;
+    assert_eq!(embed_requests.load(Ordering::SeqCst), 3);
+}
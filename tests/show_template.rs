@@ -0,0 +1,68 @@
+mod common;
+
+use serde_json::{json, Value};
+use tokio_util::sync::CancellationToken;
+
+use ollama_lmstudio_proxy_rust::handlers::handle_ollama_show;
+use ollama_lmstudio_proxy_rust::ProxyServer;
+
+use common::{context, serve, server, Reply};
+
+const NATIVE_MODELS: &str = r#"{"object":"list","data":[
+    {"id":"qwen2.5-7b-instruct","object":"model","type":"llm","publisher":"qwen","arch":"qwen2","compatibility_type":"gguf","quantization":"Q8_0","state":"loaded","max_context_length":32768,"chat_template":"{% for m in messages %}<|im_start|>{{ m.role }}{% endfor %}"},
+    {"id":"phi-4","object":"model","type":"llm","publisher":"microsoft","arch":"phi3","compatibility_type":"gguf","quantization":"Q4_K_M","state":"loaded","max_context_length":16384},
+    {"id":"gemma-3-4b","object":"model","type":"llm","publisher":"google","arch":"gemma3","compatibility_type":"gguf","quantization":"Q4_K_M","state":"loaded","max_context_length":8192}
+]}"#;
+
+const PHI_DETAIL: &str = r#"{"id":"phi-4","object":"model","type":"llm","publisher":"microsoft","arch":"phi3","compatibility_type":"gguf","quantization":"Q4_K_M","state":"loaded","max_context_length":16384,"prompt_template":"<|user|>{{ content }}<|end|>"}"#;
+
+/// LM Studio listing three models: one with a template in the list, one only in its detail endpoint, one with none
+async fn serve_native_models() -> String {
+    serve(|request| {
+        if request.starts_with("GET /api/v0/models ") {
+            Reply::json(NATIVE_MODELS)
+        } else if request.starts_with("GET /api/v0/models/phi-4 ") {
+            Reply::json(PHI_DETAIL)
+        } else {
+            Reply::not_found()
+        }
+    })
+    .await
+}
+
+async fn show(server: &ProxyServer, model: &str) -> Value {
+    let response = handle_ollama_show(context(server), json!({ "model": model }), server.current_resolver(), CancellationToken::new())
+        .await
+        .unwrap();
+    let body = warp::hyper::body::to_bytes(response.into_body()).await.unwrap();
+    serde_json::from_slice(&body).unwrap()
+}
+
+#[tokio::test]
+async fn show_reports_template_from_model_list() {
+    let url = serve_native_models().await;
+    let body = show(&server(&url, &[]), "qwen2.5-7b-instruct").await;
+
+    let template = "{% for m in messages %}<|im_start|>{{ m.role }}{% endfor %}";
+    assert_eq!(body["template"], template);
+    assert_eq!(body["model_info"]["tokenizer.chat_template"], template);
+    assert!(body["modelfile"].as_str().unwrap().contains(&format!("TEMPLATE \"\"\"{}\"\"\"", template)));
+}
+
+#[tokio::test]
+async fn show_fetches_template_from_model_detail() {
+    let url = serve_native_models().await;
+    let body = show(&server(&url, &[]), "phi-4").await;
+
+    assert_eq!(body["template"], "<|user|>{{ content }}<|end|>");
+    assert_eq!(body["model_info"]["tokenizer.chat_template"], "<|user|>{{ content }}<|end|>");
+}
+
+#[tokio::test]
+async fn show_falls_back_to_generic_template() {
+    let url = serve_native_models().await;
+    let body = show(&server(&url, &[]), "gemma-3-4b").await;
+
+    assert!(body["template"].as_str().unwrap().starts_with("{{ if .System }}"));
+    assert!(body["model_info"].get("tokenizer.chat_template").is_none());
+}
@@ -0,0 +1,50 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde_json::Value;
+use warp::Filter;
+
+use ollama_lmstudio_proxy_rust::activity::{drain_in_flight, ActivityTracker};
+use ollama_lmstudio_proxy_rust::server::{handle_rejection, reject_while_shutting_down};
+
+#[tokio::test]
+async fn new_requests_get_503_once_shutdown_starts() {
+    let activity = Arc::new(ActivityTracker::new());
+    let routes = reject_while_shutting_down(activity.clone())
+        .and(warp::path("api").map(|| "ok"))
+        .recover(handle_rejection);
+
+    let response = warp::test::request().path("/api").reply(&routes).await;
+    assert_eq!(response.status(), 200);
+
+    activity.begin_shutdown();
+    let response = warp::test::request().path("/api").reply(&routes).await;
+    assert_eq!(response.status(), 503);
+    let body: Value = serde_json::from_slice(response.body()).unwrap();
+    assert!(body["error"]["message"].as_str().unwrap().contains("Server shutting down"));
+}
+
+#[tokio::test]
+async fn drain_waits_for_in_flight_requests() {
+    let activity = Arc::new(ActivityTracker::new());
+    let guard = activity.begin();
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        drop(guard);
+    });
+
+    assert!(drain_in_flight(&activity, Duration::from_secs(5)).await);
+    assert!(activity.is_shutting_down());
+    assert_eq!(activity.in_flight(), 0);
+}
+
+#[tokio::test]
+async fn drain_gives_up_after_its_timeout() {
+    let activity = Arc::new(ActivityTracker::new());
+    let _guard = activity.begin();
+
+    let started = std::time::Instant::now();
+    assert!(!drain_in_flight(&activity, Duration::from_secs(1)).await);
+    assert!(started.elapsed() < Duration::from_secs(3));
+    assert_eq!(activity.in_flight(), 1);
+}
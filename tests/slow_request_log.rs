@@ -0,0 +1,49 @@
+use std::time::{Duration, Instant};
+
+use ollama_lmstudio_proxy_rust::constants::{init_runtime_config, RuntimeConfig, LOG_PREFIX_SUCCESS};
+use ollama_lmstudio_proxy_rust::metrics::{describe_slow_request, RequestTiming};
+use ollama_lmstudio_proxy_rust::utils::init_log_file;
+use ollama_lmstudio_proxy_rust::{init_global_logger, log_request, log_timed};
+
+fn timing(handler_ms: u64, upstream_ms: u64) -> RequestTiming {
+    RequestTiming {
+        handler_time: Duration::from_millis(handler_ms),
+        upstream_time: Duration::from_millis(upstream_ms),
+        model: Some("qwen2.5-7b-instruct".to_string()),
+        resolution_method: None,
+        streamed: false,
+    }
+}
+
+#[test]
+fn only_slow_requests_are_described() {
+    let threshold = Duration::from_millis(500);
+    assert_eq!(describe_slow_request("POST /api/chat", 200, &timing(120, 100), threshold), None);
+
+    let line = describe_slow_request("POST /api/chat", 200, &timing(1500, 1400), threshold).unwrap();
+    assert!(line.starts_with("POST /api/chat | 200 | total "), "{}", line);
+    assert!(line.contains("upstream "), "{}", line);
+    assert!(line.contains("proxy "), "{}", line);
+    assert!(line.ends_with("model qwen2.5-7b-instruct"), "{}", line);
+}
+
+#[test]
+fn fast_request_logs_are_suppressed() {
+    init_runtime_config(RuntimeConfig {
+        slow_request_threshold_ms: 60_000,
+        ..RuntimeConfig::default()
+    });
+    let path = std::env::temp_dir().join(format!("slow-request-log-{}.log", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    init_log_file(path.to_str().unwrap(), 1, 1).unwrap();
+    init_global_logger(true);
+
+    log_request("POST", "/api/chat", Some("fast-model"));
+    log_timed(LOG_PREFIX_SUCCESS, "Ollama chat fast-model", Instant::now());
+    log_timed("❌", "Ollama chat failed", Instant::now());
+
+    let log = std::fs::read_to_string(&path).unwrap();
+    let _ = std::fs::remove_file(&path);
+    assert!(!log.contains("fast-model"), "{}", log);
+    assert!(log.contains("Ollama chat failed"), "{}", log);
+}
@@ -0,0 +1,48 @@
+mod common;
+
+use std::time::Instant;
+
+use serde_json::Value;
+use tokio_util::sync::CancellationToken;
+
+use ollama_lmstudio_proxy_rust::handlers::{handle_streaming_response, sse_event_data};
+use ollama_lmstudio_proxy_rust::server::build_http_client;
+
+use common::serve_sse_once;
+
+#[test]
+fn data_lines_are_joined_with_newlines() {
+    assert_eq!(sse_event_data("data: {\"a\":\ndata: 1}").as_deref(), Some("{\"a\":\n1}"));
+    assert_eq!(sse_event_data("event: message\nid: 7\ndata:[DONE]").as_deref(), Some("[DONE]"));
+    assert_eq!(sse_event_data("data: one\r\ndata:  two").as_deref(), Some("one\n two"));
+    assert_eq!(sse_event_data(": keep-alive"), None);
+}
+
+#[tokio::test]
+async fn two_data_line_event_is_parsed_as_one_chunk() {
+    let body = concat!(
+        "data: {\"choices\":[{\"delta\":\n",
+        "data: {\"content\":\"Hel\"}}]}\n\n",
+        "data: {\"choices\":[{\"delta\":{\"content\":\"lo\"}}]}\n\n",
+        "data: [DONE]\n\n"
+    );
+    let url = serve_sse_once(body.to_string()).await;
+
+    let response = build_http_client().unwrap().get(&url).send().await.unwrap();
+    let reply = handle_streaming_response(response, true, "m:latest", "now", Instant::now(), CancellationToken::new(), 5, None)
+        .await
+        .unwrap();
+    let bytes = warp::hyper::body::to_bytes(reply.into_body()).await.unwrap();
+
+    let chunks: Vec<Value> = String::from_utf8(bytes.to_vec())
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    let content: String = chunks
+        .iter()
+        .filter_map(|chunk| chunk["message"]["content"].as_str())
+        .collect();
+    assert_eq!(content, "Hello");
+    assert_eq!(chunks.last().unwrap()["done"], true);
+}
@@ -0,0 +1,64 @@
+use serde_json::json;
+
+use ollama_lmstudio_proxy_rust::handlers::render_status_page;
+
+#[test]
+fn renders_health_models_and_metrics() {
+    let html = render_status_page(&json!({
+        "version": "1.2.3",
+        "lmstudio_url": "http://localhost:1234",
+        "active_backend": null,
+        "api_mode": "Native (LM Studio REST API)",
+        "health": { "status": "healthy", "response_time_ms": 12 },
+        "models_label": "Loaded models",
+        "models": ["qwen2.5-7b-instruct"],
+        "metrics": {
+            "requests_total": 5,
+            "errors_total": 1,
+            "avg_handler_time_ms": 40.25,
+            "endpoints": { "POST /api/chat": { "requests": 5, "errors": 1, "avg_time_ms": 40.25 } }
+        }
+    }));
+
+    assert!(html.starts_with("<!DOCTYPE html>"));
+    assert!(html.contains("<td>1.2.3</td>"));
+    assert!(html.contains("<td class=\"ok\">healthy</td>"));
+    assert!(html.contains("<li>qwen2.5-7b-instruct</li>"));
+    assert!(html.contains("<td>POST /api/chat</td><td>5</td><td>1</td><td>40.2 ms</td>"));
+    assert!(!html.contains("Active backend"));
+    // Self-contained: no scripts or external assets
+    assert!(!html.contains("<script") && !html.contains("href=") && !html.contains("src="));
+}
+
+#[test]
+fn unreachable_backend_and_missing_models() {
+    let html = render_status_page(&json!({
+        "version": "1.2.3",
+        "lmstudio_url": "http://localhost:1234",
+        "active_backend": "http://backup:1234",
+        "api_mode": "Legacy (OpenAI-compatible)",
+        "health": { "status": "unreachable", "response_time_ms": 3 },
+        "models_label": "Available models",
+        "models": null,
+        "metrics": {}
+    }));
+
+    assert!(html.contains("<td class=\"bad\">unreachable</td>"));
+    assert!(html.contains("<td>http://backup:1234</td>"));
+    assert!(html.contains("Could not fetch models from LM Studio"));
+}
+
+#[test]
+fn values_are_html_escaped() {
+    let html = render_status_page(&json!({
+        "lmstudio_url": "http://host/?a=1&b=<script>",
+        "health": { "status": "<b>odd</b>" },
+        "models": ["evil\"<img>"],
+        "metrics": {}
+    }));
+
+    assert!(html.contains("http://host/?a=1&amp;b=&lt;script&gt;"));
+    assert!(html.contains("&lt;b&gt;odd&lt;/b&gt;"));
+    assert!(html.contains("<li>evil&quot;&lt;img&gt;</li>"));
+    assert!(!html.contains("<script>"));
+}
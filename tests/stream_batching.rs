@@ -0,0 +1,58 @@
+use futures_util::StreamExt;
+use tokio::sync::mpsc;
+
+use ollama_lmstudio_proxy_rust::constants::STREAM_WRITE_BATCH_MAX_CHUNKS;
+use ollama_lmstudio_proxy_rust::handlers::batch_ready_chunks;
+
+fn token_chunk(index: usize) -> bytes::Bytes {
+    bytes::Bytes::from(format!("{{\"message\":{{\"content\":\"t{}\"}},\"done\":false}}\n", index))
+}
+
+#[tokio::test]
+async fn queued_chunks_are_merged_into_few_writes() {
+    let (tx, rx) = mpsc::unbounded_channel();
+    let mut expected = Vec::new();
+    for index in 0..500 {
+        let chunk = token_chunk(index);
+        expected.extend_from_slice(&chunk);
+        tx.send(Ok(chunk)).unwrap();
+    }
+    drop(tx);
+
+    let writes: Vec<bytes::Bytes> = batch_ready_chunks(rx).map(Result::unwrap).collect().await;
+
+    // 500 token chunks become ceil(500 / 64) = 8 body writes, in order and byte-for-byte intact
+    assert_eq!(writes.len(), 500usize.div_ceil(STREAM_WRITE_BATCH_MAX_CHUNKS));
+    assert_eq!(writes.concat(), expected);
+}
+
+#[tokio::test]
+async fn a_lone_chunk_is_written_without_waiting() {
+    let (tx, rx) = mpsc::unbounded_channel();
+    let mut writes = Box::pin(batch_ready_chunks(rx));
+
+    tx.send(Ok(token_chunk(0))).unwrap();
+    let first = tokio::time::timeout(std::time::Duration::from_millis(100), writes.next())
+        .await
+        .expect("a queued chunk must not wait for more");
+    assert_eq!(first.unwrap().unwrap(), token_chunk(0));
+
+    tx.send(Ok(token_chunk(1))).unwrap();
+    drop(tx);
+    assert_eq!(writes.next().await.unwrap().unwrap(), token_chunk(1));
+    assert!(writes.next().await.is_none());
+}
+
+#[tokio::test]
+async fn chunks_before_an_error_are_kept() {
+    let (tx, rx) = mpsc::unbounded_channel();
+    tx.send(Ok(token_chunk(0))).unwrap();
+    tx.send(Ok(token_chunk(1))).unwrap();
+    tx.send(Err(std::io::Error::other("upstream closed"))).unwrap();
+    drop(tx);
+
+    let items: Vec<_> = batch_ready_chunks(rx).collect().await;
+    assert_eq!(items.len(), 2);
+    assert_eq!(items[0].as_ref().unwrap(), &[token_chunk(0), token_chunk(1)].concat());
+    assert!(items[1].is_err());
+}
@@ -0,0 +1,43 @@
+mod common;
+
+use std::time::Instant;
+
+use tokio_util::sync::CancellationToken;
+
+use ollama_lmstudio_proxy_rust::handlers::{handle_passthrough_streaming_response, handle_streaming_response};
+use ollama_lmstudio_proxy_rust::server::build_http_client;
+
+use common::serve_sse_once;
+
+/// A one-token chat stream
+const SSE_BODY: &str = "data: {\"choices\":[{\"delta\":{\"content\":\"Hi\"}}]}\n\ndata: [DONE]\n\n";
+
+async fn upstream() -> reqwest::Response {
+    let url = serve_sse_once(SSE_BODY).await;
+    build_http_client()
+        .unwrap()
+        .get(&url)
+        .header("accept-encoding", "gzip")
+        .send()
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn ollama_stream_is_marked_uncompressed() {
+    let reply = handle_streaming_response(upstream().await, true, "m:latest", "now", Instant::now(), CancellationToken::new(), 5, None)
+        .await
+        .unwrap();
+    assert_eq!(reply.headers()["content-encoding"], "identity");
+    assert!(reply.headers()["content-type"].to_str().unwrap().starts_with("application/x-ndjson"));
+}
+
+#[tokio::test]
+async fn passthrough_stream_is_marked_uncompressed() {
+    let reply = handle_passthrough_streaming_response(upstream().await, CancellationToken::new(), 5)
+        .await
+        .unwrap();
+    assert_eq!(reply.headers()["content-encoding"], "identity");
+    let body = warp::hyper::body::to_bytes(reply.into_body()).await.unwrap();
+    assert!(String::from_utf8_lossy(&body).contains("[DONE]"));
+}
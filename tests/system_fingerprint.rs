@@ -0,0 +1,66 @@
+mod common;
+
+use std::time::Instant;
+
+use serde_json::{json, Value};
+use tokio_util::sync::CancellationToken;
+
+use ollama_lmstudio_proxy_rust::handlers::{handle_streaming_response, ResponseTransformer};
+use ollama_lmstudio_proxy_rust::server::build_http_client;
+use ollama_lmstudio_proxy_rust::{init_runtime_config, RuntimeConfig};
+
+use common::serve_sse_once;
+
+fn enable_system_fingerprint() {
+    init_runtime_config(RuntimeConfig {
+        include_system_fingerprint: true,
+        ..RuntimeConfig::default()
+    });
+}
+
+#[test]
+fn non_streaming_responses_carry_the_fingerprint() {
+    enable_system_fingerprint();
+    let chat = json!({
+        "system_fingerprint": "qwen2.5-7b-instruct@q4_k_m",
+        "choices": [{"message": {"content": "Paris"}}]
+    });
+    let ollama = ResponseTransformer::convert_to_ollama_chat(&chat, "m:latest", 1, Instant::now(), false);
+    assert_eq!(ollama["system_fingerprint"], "qwen2.5-7b-instruct@q4_k_m");
+
+    let completion = json!({"system_fingerprint": "fp_1", "choices": [{"text": "Paris"}]});
+    let ollama = ResponseTransformer::convert_to_ollama_generate(&completion, "m:latest", "hi", Instant::now(), false);
+    assert_eq!(ollama["system_fingerprint"], "fp_1");
+
+    let without = json!({"choices": [{"message": {"content": "Paris"}}]});
+    let ollama = ResponseTransformer::convert_to_ollama_chat(&without, "m:latest", 1, Instant::now(), false);
+    assert!(ollama.get("system_fingerprint").is_none());
+}
+
+#[tokio::test]
+async fn streaming_final_chunk_carries_the_fingerprint() {
+    enable_system_fingerprint();
+    let mut body = String::new();
+    for content in ["Par", "is"] {
+        let chunk = json!({"system_fingerprint": "fp_1", "choices": [{"delta": {"content": content}}]});
+        body.push_str(&format!("data: {}\n\n", chunk));
+    }
+    body.push_str("data: [DONE]\n\n");
+    let url = serve_sse_once(body).await;
+
+    let response = build_http_client().unwrap().get(&url).send().await.unwrap();
+    let reply = handle_streaming_response(response, true, "m:latest", "now", Instant::now(), CancellationToken::new(), 5, None)
+        .await
+        .unwrap();
+    let bytes = warp::hyper::body::to_bytes(reply.into_body()).await.unwrap();
+
+    let chunks: Vec<Value> = String::from_utf8(bytes.to_vec())
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    let (last, rest) = chunks.split_last().unwrap();
+    assert_eq!(last["done"], true);
+    assert_eq!(last["system_fingerprint"], "fp_1");
+    assert!(rest.iter().all(|chunk| chunk.get("system_fingerprint").is_none()));
+}
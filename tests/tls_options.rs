@@ -0,0 +1,34 @@
+use clap::Parser;
+
+use ollama_lmstudio_proxy_rust::server::{build_http_client_for, load_ca_certificate};
+use ollama_lmstudio_proxy_rust::{validate_config, Config};
+
+const TEST_CA: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/test_ca.pem");
+
+#[test]
+fn pem_ca_certificate_loads() {
+    assert!(load_ca_certificate(TEST_CA).is_ok());
+}
+
+#[test]
+fn unparseable_ca_certificate_fails_validation() {
+    let path = std::env::temp_dir().join(format!("not-a-cert-{}.pem", std::process::id()));
+    std::fs::write(&path, "-----BEGIN CERTIFICATE-----\nnot base64\n-----END CERTIFICATE-----\n").unwrap();
+    let config = Config::parse_from(["proxy", "--ca-cert", path.to_str().unwrap()]);
+    let error = validate_config(&config).unwrap_err();
+    std::fs::remove_file(&path).unwrap();
+    assert!(error.starts_with("Invalid CA certificate"), "{}", error);
+}
+
+#[test]
+fn missing_ca_certificate_fails_validation() {
+    let config = Config::parse_from(["proxy", "--ca-cert", "/nonexistent/ca.pem"]);
+    assert!(validate_config(&config).unwrap_err().starts_with("Failed to read CA certificate"));
+}
+
+#[test]
+fn tls_options_build_a_client() {
+    let config = Config::parse_from(["proxy", "--ca-cert", TEST_CA, "--insecure-skip-verify"]);
+    assert!(validate_config(&config).is_ok());
+    assert!(build_http_client_for(&config).is_ok());
+}
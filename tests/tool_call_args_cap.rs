@@ -0,0 +1,64 @@
+mod common;
+
+use std::time::Instant;
+
+use serde_json::{json, Value};
+use tokio_util::sync::CancellationToken;
+
+use ollama_lmstudio_proxy_rust::constants::{init_runtime_config, RuntimeConfig, ERROR_TOOL_CALL_ARGS_TOO_LARGE};
+use ollama_lmstudio_proxy_rust::handlers::{handle_streaming_response, tool_call_arguments_len};
+use ollama_lmstudio_proxy_rust::server::build_http_client;
+
+use common::serve_sse_once;
+
+fn tool_call_delta(arguments: &str) -> String {
+    let chunk = json!({"choices": [{"delta": {"tool_calls": [{"index": 0, "function": {"arguments": arguments}}]}}]});
+    format!("data: {}\n\n", chunk)
+}
+
+/// Serve one SSE stream whose tool call arguments never end
+async fn serve_runaway_tool_call() -> String {
+    let mut body = String::new();
+    for _ in 0..20 {
+        body.push_str(&tool_call_delta("{\"text\": \"aaaaaaaaaa"));
+    }
+    body.push_str("data: [DONE]\n\n");
+    serve_sse_once(body).await
+}
+
+#[test]
+fn argument_bytes_are_counted_per_delta() {
+    let calls = json!([
+        {"function": {"name": "f", "arguments": "{\"a\":"}},
+        {"function": {"arguments": {"b": 1}}},
+        {"type": "function"}
+    ]);
+    assert_eq!(tool_call_arguments_len(calls.as_array().unwrap()), 5 + 7);
+}
+
+#[tokio::test]
+async fn runaway_tool_call_stream_is_stopped_with_an_error() {
+    init_runtime_config(RuntimeConfig {
+        max_tool_call_args_bytes: 100,
+        ..RuntimeConfig::default()
+    });
+    let url = serve_runaway_tool_call().await;
+    let upstream = build_http_client().unwrap().get(&url).send().await.unwrap();
+
+    let reply = handle_streaming_response(upstream, true, "m:latest", "now", Instant::now(), CancellationToken::new(), 5, None)
+        .await
+        .unwrap();
+    let body = warp::hyper::body::to_bytes(reply.into_body()).await.unwrap();
+    let chunks: Vec<Value> = String::from_utf8(body.to_vec())
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+
+    // 20 bytes per delta: five deltas fit under the 100-byte cap, the sixth ends the stream
+    let (last, forwarded) = chunks.split_last().unwrap();
+    assert_eq!(forwarded.len(), 5);
+    assert!(forwarded.iter().all(|chunk| chunk["message"]["tool_calls"].is_array()));
+    assert_eq!(last["error"], ERROR_TOOL_CALL_ARGS_TOO_LARGE);
+    assert_eq!(last["done"], true);
+}
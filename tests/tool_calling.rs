@@ -0,0 +1,118 @@
+mod common;
+
+use std::sync::{Arc, Mutex};
+
+use serde_json::{json, Value};
+use tokio_util::sync::CancellationToken;
+
+use ollama_lmstudio_proxy_rust::handlers::{
+    build_lm_studio_request, handle_ollama_chat, normalize_tool_messages, LMStudioRequestType,
+};
+
+use common::{context, request_body, serve, server, Reply};
+
+fn weather_tools() -> Value {
+    json!([{
+        "type": "function",
+        "function": {
+            "name": "get_weather",
+            "parameters": {"type": "object", "properties": {"city": {"type": "string"}}}
+        }
+    }])
+}
+
+#[test]
+fn openai_style_round_trip_is_forwarded_unchanged() {
+    let messages = json!([
+        {"role": "user", "content": "Weather in Paris?"},
+        {"role": "assistant", "content": "", "tool_calls": [{
+            "id": "call_abc", "type": "function",
+            "function": {"name": "get_weather", "arguments": "{\"city\":\"Paris\"}"}
+        }]},
+        {"role": "tool", "tool_call_id": "call_abc", "content": "18°C, sunny"},
+        {"role": "assistant", "content": "It is 18°C and sunny in Paris."}
+    ]);
+
+    let request = build_lm_studio_request(
+        "qwen2.5-7b-instruct",
+        LMStudioRequestType::Chat { messages: &messages, stream: false },
+        None,
+        Some(&weather_tools()),
+        false,
+    );
+
+    assert_eq!(request["messages"], messages);
+    assert_eq!(request["tools"], weather_tools());
+}
+
+#[test]
+fn ollama_style_tool_calls_get_ids_and_string_arguments() {
+    let messages = json!([
+        {"role": "user", "content": "Weather in Paris and Oslo?"},
+        {"role": "assistant", "content": "", "tool_calls": [
+            {"function": {"name": "get_weather", "arguments": {"city": "Paris"}}},
+            {"function": {"name": "get_time", "arguments": {"city": "Oslo"}}}
+        ]},
+        {"role": "tool", "tool_name": "get_time", "content": "14:00"},
+        {"role": "tool", "content": "18°C, sunny"}
+    ]);
+
+    let normalized = normalize_tool_messages(&messages);
+    let calls = &normalized[1]["tool_calls"];
+
+    assert_eq!(calls[0]["id"], "call_1_0");
+    assert_eq!(calls[0]["type"], "function");
+    assert_eq!(calls[0]["function"]["arguments"], "{\"city\":\"Paris\"}");
+    assert_eq!(calls[1]["id"], "call_1_1");
+    assert_eq!(normalized[2]["tool_call_id"], "call_1_1");
+    assert_eq!(normalized[2]["tool_name"], "get_time");
+    assert_eq!(normalized[3]["tool_call_id"], "call_1_0");
+    assert_eq!(normalized[0], messages[0]);
+}
+
+/// Legacy-mode LM Studio that records chat request bodies and answers with a final message
+async fn serve_recording(requests: Arc<Mutex<Vec<Value>>>) -> String {
+    serve(move |request| {
+        if request.starts_with("GET /v1/models") {
+            Reply::json(json!({"object": "list", "data": [{"id": "qwen2.5-7b-instruct", "object": "model"}]}))
+        } else {
+            requests.lock().unwrap().push(request_body(request));
+            Reply::json(json!({"choices": [{
+                "message": {"role": "assistant", "content": "It is 18°C and sunny in Paris."},
+                "finish_reason": "stop"
+            }]}))
+        }
+    })
+    .await
+}
+
+#[tokio::test]
+async fn tool_result_reaches_lm_studio_and_final_answer_returns() {
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let url = serve_recording(requests.clone()).await;
+    let server = server(&url, &["--legacy"]);
+    let body = json!({
+        "model": "qwen2.5-7b-instruct",
+        "stream": false,
+        "tools": weather_tools(),
+        "messages": [
+            {"role": "user", "content": "Weather in Paris?"},
+            {"role": "assistant", "content": "", "tool_calls": [{
+                "id": "call_abc", "type": "function",
+                "function": {"name": "get_weather", "arguments": "{\"city\":\"Paris\"}"}
+            }]},
+            {"role": "tool", "tool_call_id": "call_abc", "content": "18°C, sunny"}
+        ]
+    });
+    let response = handle_ollama_chat(context(&server), server.current_resolver(), body.clone(), CancellationToken::new(), &server.config)
+        .await
+        .unwrap();
+    let response = warp::hyper::body::to_bytes(response.into_body()).await.unwrap();
+    let response: Value = serde_json::from_slice(&response).unwrap();
+
+    assert_eq!(response["message"]["content"], "It is 18°C and sunny in Paris.");
+    let forwarded = requests.lock().unwrap().pop().unwrap();
+    assert_eq!(forwarded["messages"], body["messages"]);
+    assert_eq!(forwarded["messages"][2]["role"], "tool");
+    assert_eq!(forwarded["messages"][2]["tool_call_id"], "call_abc");
+}
@@ -0,0 +1,19 @@
+use ollama_lmstudio_proxy_rust::constants::HTTP_POOL_MAX_IDLE_PER_HOST;
+use ollama_lmstudio_proxy_rust::metrics::{begin_upstream_request, upstream_pool_snapshot, ProxyMetrics};
+
+#[test]
+fn requests_beyond_the_idle_pool_count_as_saturated() {
+    let guards: Vec<_> = (0..HTTP_POOL_MAX_IDLE_PER_HOST + 2).map(|_| begin_upstream_request()).collect();
+
+    let pool = upstream_pool_snapshot();
+    assert_eq!(pool["pool_max_idle_per_host"], HTTP_POOL_MAX_IDLE_PER_HOST);
+    assert_eq!(pool["in_flight"], (HTTP_POOL_MAX_IDLE_PER_HOST + 2) as u64);
+    assert_eq!(pool["in_flight_peak"], (HTTP_POOL_MAX_IDLE_PER_HOST + 2) as u64);
+    assert_eq!(pool["saturated_total"], 2);
+
+    drop(guards);
+    let pool = ProxyMetrics::new().snapshot()["upstream_connections"].clone();
+    assert_eq!(pool["in_flight"], 0);
+    assert_eq!(pool["requests_total"], (HTTP_POOL_MAX_IDLE_PER_HOST + 2) as u64);
+    assert_eq!(pool["saturated_total"], 2);
+}